@@ -0,0 +1,5 @@
+# Map out NeoTech's front door before going in.
+# Run it with: run recon
+
+nmap neotechlabs.com
+echo "Scan started on $HOST as $USER"
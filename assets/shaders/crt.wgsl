@@ -0,0 +1,32 @@
+// Retro CRT look: barrel distortion, green/magenta chromatic aberration and scanlines.
+#import bevy_core_pipeline::fullscreen_vertex_shader::FullscreenVertexOutput
+
+@group(0) @binding(0) var screen_texture: texture_2d<f32>;
+@group(0) @binding(1) var texture_sampler: sampler;
+
+struct CrtSettings {
+    intensity: f32,
+}
+@group(0) @binding(2) var<uniform> settings: CrtSettings;
+
+@fragment
+fn fragment(in: FullscreenVertexOutput) -> @location(0) vec4<f32> {
+    // Bulge the image outward from the centre like a curved tube
+    let centered = in.uv * 2.0 - 1.0;
+    let distorted = centered * (1.0 + 0.12 * settings.intensity * dot(centered, centered));
+    let uv = distorted * 0.5 + 0.5;
+
+    // Pull magenta (red + blue) one way and green the other
+    let offset = vec2<f32>(0.004 * settings.intensity, 0.0);
+    let magenta = textureSample(screen_texture, texture_sampler, uv + offset);
+    let green = textureSample(screen_texture, texture_sampler, uv - offset);
+    var color = vec3<f32>(magenta.r, green.g, magenta.b);
+
+    let height = f32(textureDimensions(screen_texture).y);
+    let scanline = 0.5 + 0.5 * sin(uv.y * height * 3.14159);
+    color *= mix(1.0, scanline, 0.35 * settings.intensity);
+
+    // Anything bent past the edge of the tube is black
+    let inside = all(uv >= vec2<f32>(0.0)) && all(uv <= vec2<f32>(1.0));
+    return select(vec4<f32>(0.0, 0.0, 0.0, 1.0), vec4<f32>(color, 1.0), inside);
+}
@@ -0,0 +1,264 @@
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use crate::keybindings::{Action, KeyBindings};
+use crate::layout::TerminalLayout;
+use crate::terminal::{ScrollbackState, VISIBLE_LINES};
+use crate::theme::Theme;
+use crate::visuals::VisualSettings;
+
+const MIN_FONT_SCALE: f32 = 0.75;
+const MAX_FONT_SCALE: f32 = 2.0;
+/// How much one Ctrl+Plus or Ctrl+Minus changes the font scale by.
+const FONT_SCALE_STEP: f32 = 0.125;
+
+/// Text size, contrast and colours, chosen in game and kept between runs.
+#[derive(Resource, Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(default)]
+pub struct A11ySettings {
+    /// Multiplies every font size, terminal and HUD alike.
+    pub font_scale: f32,
+    /// White on a solid black background, without glow.
+    pub high_contrast: bool,
+    /// Status colours colour-blind players can tell apart. Their symbols,
+    /// like a breached host's `[✓]`, show either way.
+    pub colorblind: bool,
+}
+
+impl Default for A11ySettings {
+    fn default() -> Self {
+        A11ySettings {
+            font_scale: 1.0,
+            high_contrast: false,
+            colorblind: false,
+        }
+    }
+}
+
+/// `accessibility.json` as read back, where a setting missing from a
+/// hand-edited file keeps the config's.
+#[derive(Deserialize, Default)]
+#[serde(default)]
+struct SavedA11y {
+    font_scale: Option<f32>,
+    high_contrast: Option<bool>,
+    colorblind: Option<bool>,
+}
+
+/// Errors and highlights in colour-blind mode, orange and sky blue from
+/// the same palette as the colour tags.
+const COLORBLIND_ERROR: Color = Color::srgb(0.9, 0.62, 0.0);
+const COLORBLIND_HIGHLIGHT: Color = Color::srgb(0.34, 0.71, 0.91);
+
+fn settings_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("neon-city").join("accessibility.json"))
+}
+
+impl A11ySettings {
+    /// `defaults`, from `a11y` in the config, overridden by the settings
+    /// saved last time.
+    pub fn load(defaults: A11ySettings) -> Self {
+        let Some(path) = settings_path() else {
+            return defaults;
+        };
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(err) => {
+                if err.kind() != io::ErrorKind::NotFound {
+                    warn!("Could not read {}: {}", path.display(), err);
+                }
+                return defaults;
+            }
+        };
+        defaults.overridden_by(&contents).unwrap_or_else(|err| {
+            warn!("Ignoring invalid {}: {}", path.display(), err);
+            defaults
+        })
+    }
+
+    /// These settings with those saved in `contents` in their place.
+    fn overridden_by(self, contents: &str) -> serde_json::Result<Self> {
+        let saved: SavedA11y = serde_json::from_str(contents)?;
+        Ok(A11ySettings {
+            font_scale: saved.font_scale.unwrap_or(self.font_scale),
+            high_contrast: saved.high_contrast.unwrap_or(self.high_contrast),
+            colorblind: saved.colorblind.unwrap_or(self.colorblind),
+        }
+        .clamped())
+    }
+
+    fn save(&self) -> io::Result<()> {
+        let path = settings_path()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no config directory"))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&path, serde_json::to_string_pretty(self)?)
+    }
+
+    /// Output lines that fit in the terminal at this font scale.
+    pub fn visible_rows(&self) -> usize {
+        (VISIBLE_LINES as f32 / self.font_scale) as usize
+    }
+
+    pub fn clamped(self) -> Self {
+        A11ySettings {
+            font_scale: self.font_scale.clamp(MIN_FONT_SCALE, MAX_FONT_SCALE),
+            ..self
+        }
+    }
+
+    /// The colours text and background are drawn in: `theme`, glowing as
+    /// `visuals` says, or plain white on black in high contrast. Colour-blind
+    /// mode swaps errors and highlights for colours that stay distinct.
+    pub fn theme(&self, theme: &Theme, visuals: &VisualSettings) -> Theme {
+        let theme = if self.colorblind {
+            Theme {
+                error: COLORBLIND_ERROR,
+                highlight: COLORBLIND_HIGHLIGHT,
+                colorblind: true,
+                ..theme.clone()
+            }
+        } else {
+            theme.clone()
+        };
+        if !self.high_contrast {
+            return visuals.glowing(&theme);
+        }
+        let (error, highlight) = if self.colorblind {
+            (theme.error, theme.highlight)
+        } else {
+            // Errors and highlights stay told apart, just brighter
+            (Color::srgb(1.0, 0.4, 0.4), Color::srgb(1.0, 1.0, 0.3))
+        };
+        Theme {
+            foreground: Color::WHITE,
+            background: Color::BLACK,
+            error,
+            highlight,
+            ..theme
+        }
+    }
+}
+
+/// Ctrl+Plus and Ctrl+Minus resize text, Ctrl+0 resets it, and the
+/// high-contrast and colour-blind keys toggle those modes. Changes are
+/// saved straight away.
+pub fn adjust_a11y(
+    keys: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut settings: ResMut<A11ySettings>,
+) {
+    let ctrl = keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    let mut next = *settings;
+    if ctrl && keys.any_just_pressed([KeyCode::Equal, KeyCode::NumpadAdd]) {
+        next.font_scale += FONT_SCALE_STEP;
+    }
+    if ctrl && keys.any_just_pressed([KeyCode::Minus, KeyCode::NumpadSubtract]) {
+        next.font_scale -= FONT_SCALE_STEP;
+    }
+    if ctrl && keys.any_just_pressed([KeyCode::Digit0, KeyCode::Numpad0]) {
+        next.font_scale = 1.0;
+    }
+    if bindings.just_pressed(&keys, Action::ToggleHighContrast) {
+        next.high_contrast = !next.high_contrast;
+    }
+    if bindings.just_pressed(&keys, Action::ToggleColorblindMode) {
+        next.colorblind = !next.colorblind;
+    }
+    let next = next.clamped();
+    if next == *settings {
+        return;
+    }
+    *settings = next;
+    if let Err(err) = settings.save() {
+        warn!("Could not save accessibility settings: {}", err);
+    }
+}
+
+/// Lays the terminal out again for a new font scale, so output wraps to
+/// the columns that now fit, and scales the HUD and menus to match.
+pub fn apply_font_scale(
+    settings: Res<A11ySettings>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut layout: ResMut<TerminalLayout>,
+    mut scrollback: ResMut<ScrollbackState>,
+    mut ui_scale: ResMut<UiScale>,
+) {
+    if !settings.is_changed() || layout.font_scale == settings.font_scale {
+        return;
+    }
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+    let size = Vec2::new(window.width(), window.height());
+    *layout = TerminalLayout::new(size, settings.font_scale, layout.split);
+    scrollback.set_rows(settings.visible_rows());
+    ui_scale.0 = settings.font_scale;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn high_contrast_is_white_on_black_and_scale_is_clamped() {
+        let settings = A11ySettings {
+            font_scale: 9.0,
+            high_contrast: true,
+            colorblind: false,
+        }
+        .clamped();
+        assert_eq!(settings.font_scale, MAX_FONT_SCALE);
+        let theme = settings.theme(&Theme::default(), &VisualSettings::default());
+        assert_eq!(theme.foreground, Color::WHITE);
+        assert_eq!(theme.background, Color::BLACK);
+    }
+
+    #[test]
+    fn colorblind_mode_swaps_errors_and_tags_in_any_contrast() {
+        let mut settings = A11ySettings {
+            colorblind: true,
+            ..default()
+        };
+        let visuals = VisualSettings {
+            bloom: false,
+            ..default()
+        };
+        let theme = settings.theme(&Theme::default(), &visuals);
+        assert_eq!(theme.error, COLORBLIND_ERROR);
+        assert!(theme.colorblind);
+        assert_eq!(theme.foreground, Theme::default().foreground);
+        settings.high_contrast = true;
+        let theme = settings.theme(&Theme::default(), &visuals);
+        assert_eq!(
+            (theme.foreground, theme.error),
+            (Color::WHITE, COLORBLIND_ERROR)
+        );
+        assert!(
+            !A11ySettings::default()
+                .theme(&Theme::default(), &visuals)
+                .colorblind
+        );
+    }
+
+    #[test]
+    fn saved_settings_override_the_configured_ones_they_set() {
+        let configured = A11ySettings {
+            font_scale: 1.5,
+            colorblind: true,
+            ..default()
+        };
+        let settings = configured
+            .overridden_by(r#"{ "high_contrast": true, "font_scale": 9.0 }"#)
+            .unwrap();
+        assert_eq!(settings.font_scale, MAX_FONT_SCALE);
+        assert!(settings.high_contrast);
+        assert!(settings.colorblind);
+        assert!(configured.overridden_by("not json").is_err());
+    }
+}
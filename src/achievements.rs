@@ -0,0 +1,272 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use crate::commands::GameLogicState;
+use crate::events::{CommandSubmitted, HostBreached, TraceEvaded};
+
+/// How long an unlock toast stays up, sliding in and out included.
+const TOAST_SECONDS: f32 = 4.0;
+/// Seconds the toast takes to slide in, and again to slide out.
+const TOAST_SLIDE_SECONDS: f32 = 0.3;
+const TOAST_WIDTH: f32 = 280.0;
+/// Gap between the toast and the right edge of the window.
+const TOAST_MARGIN: f32 = 10.0;
+
+/// What just happened, for achievements to judge.
+pub enum Trigger<'a> {
+    /// Any command ran.
+    Submitted,
+    Breached,
+    Evaded(&'a TraceEvaded),
+}
+
+pub struct Achievement {
+    pub id: &'static str,
+    pub title: &'static str,
+    pub description: &'static str,
+    /// Whether `trigger` earns the achievement, with the game as it now is.
+    pub predicate: fn(&Trigger, &GameLogicState) -> bool,
+}
+
+pub const ACHIEVEMENTS: &[Achievement] = &[
+    Achievement {
+        id: "first_breach",
+        title: "First Breach",
+        description: "Break through a firewall",
+        predicate: |trigger, _| matches!(trigger, Trigger::Breached),
+    },
+    Achievement {
+        id: "ghost_in_the_machine",
+        title: "Ghost in the Machine",
+        description: "Evade a trace with less than a second left",
+        predicate: |trigger, _| matches!(trigger, Trigger::Evaded(evaded) if evaded.remaining_secs < 1.0),
+    },
+    Achievement {
+        id: "data_hoarder",
+        title: "Data Hoarder",
+        description: "Collect every piece of loot",
+        predicate: |trigger, game| {
+            matches!(trigger, Trigger::Submitted) && game.inventory.has_everything()
+        },
+    },
+];
+
+/// Achievements unlocked so far, by id, kept between runs.
+#[derive(Resource, Serialize, Deserialize, Default, Clone, PartialEq, Debug)]
+#[serde(default)]
+pub struct Achievements {
+    pub unlocked: BTreeSet<String>,
+}
+
+fn achievements_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("neon-city").join("achievements.json"))
+}
+
+impl Achievements {
+    /// The achievements unlocked in earlier runs, or none if there are none.
+    pub fn load() -> Self {
+        let Some(path) = achievements_path() else {
+            return Achievements::default();
+        };
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(err) => {
+                if err.kind() != io::ErrorKind::NotFound {
+                    warn!("Could not read {}: {}", path.display(), err);
+                }
+                return Achievements::default();
+            }
+        };
+        serde_json::from_str(&contents).unwrap_or_else(|err| {
+            warn!("Ignoring invalid {}: {}", path.display(), err);
+            Achievements::default()
+        })
+    }
+
+    fn save(&self) -> io::Result<()> {
+        let path = achievements_path()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no config directory"))?;
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(&path, serde_json::to_string_pretty(self)?)
+    }
+
+    /// Unlocks every achievement `trigger` earns that isn't unlocked yet,
+    /// returning the new ones.
+    pub fn award(&mut self, trigger: &Trigger, game: &GameLogicState) -> Vec<&'static Achievement> {
+        // `insert` is false for ones already unlocked, so none is awarded twice
+        ACHIEVEMENTS
+            .iter()
+            .filter(|achievement| {
+                (achievement.predicate)(trigger, game)
+                    && self.unlocked.insert(achievement.id.to_string())
+            })
+            .collect()
+    }
+}
+
+/// An unlock notice sliding in from the right edge, then back out.
+#[derive(Component)]
+pub struct Toast {
+    timer: Timer,
+}
+
+impl Toast {
+    /// Distance from the right edge at this point of its life: off screen
+    /// at either end, `TOAST_MARGIN` in between.
+    fn right(&self) -> f32 {
+        let elapsed = self.timer.elapsed_secs();
+        let left = TOAST_SECONDS - elapsed;
+        let shown = (elapsed.min(left) / TOAST_SLIDE_SECONDS).clamp(0.0, 1.0);
+        let hidden = -(TOAST_WIDTH + TOAST_MARGIN);
+        hidden + (TOAST_MARGIN - hidden) * shown
+    }
+}
+
+/// Checks every terminal event against the achievements, saving and
+/// announcing any that unlock.
+pub fn award_achievements(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    game: Res<GameLogicState>,
+    mut achievements: ResMut<Achievements>,
+    mut submitted: EventReader<CommandSubmitted>,
+    mut breached: EventReader<HostBreached>,
+    mut evaded: EventReader<TraceEvaded>,
+    toasts: Query<(), With<Toast>>,
+) {
+    let triggers = submitted
+        .read()
+        .map(|_| Trigger::Submitted)
+        .chain(breached.read().map(|_| Trigger::Breached))
+        .chain(evaded.read().map(Trigger::Evaded));
+    let mut unlocked = Vec::new();
+    for trigger in triggers {
+        unlocked.extend(achievements.award(&trigger, &game));
+    }
+    if unlocked.is_empty() {
+        return;
+    }
+    if let Err(err) = achievements.save() {
+        warn!("Could not save achievements: {}", err);
+    }
+    let font = asset_server.load("fonts/FiraMono-Regular.ttf");
+    // Stack below any toasts already showing
+    for (i, achievement) in unlocked.into_iter().enumerate() {
+        let slot = toasts.iter().count() + i;
+        spawn_toast(&mut commands, &font, achievement, slot);
+    }
+}
+
+fn spawn_toast(
+    commands: &mut Commands,
+    font: &Handle<Font>,
+    achievement: &Achievement,
+    slot: usize,
+) {
+    let toast = Toast {
+        timer: Timer::from_seconds(TOAST_SECONDS, TimerMode::Once),
+    };
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(10.0 + 64.0 * slot as f32),
+                    right: Val::Px(toast.right()),
+                    width: Val::Px(TOAST_WIDTH),
+                    flex_direction: FlexDirection::Column,
+                    padding: UiRect::all(Val::Px(8.0)),
+                    border: UiRect::all(Val::Px(1.0)),
+                    ..default()
+                },
+                background_color: Color::srgba(0.0, 0.0, 0.0, 0.8).into(),
+                border_color: Color::srgb(1.0, 0.9, 0.2).into(),
+                ..default()
+            },
+            toast,
+        ))
+        .with_children(|toast| {
+            toast.spawn(TextBundle::from_section(
+                format!("Achievement unlocked: {}", achievement.title),
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 16.0,
+                    color: Color::srgb(1.0, 0.9, 0.2),
+                },
+            ));
+            toast.spawn(TextBundle::from_section(
+                achievement.description,
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 14.0,
+                    color: Color::srgb(0.8, 0.8, 0.8),
+                },
+            ));
+        });
+}
+
+/// Slides toasts in and out, and removes them once they are done.
+pub fn animate_toasts(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut toasts: Query<(Entity, &mut Toast, &mut Style)>,
+) {
+    for (entity, mut toast, mut style) in &mut toasts {
+        if toast.timer.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+        } else {
+            style.right = Val::Px(toast.right());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn achievements_unlock_once() {
+        let game = GameLogicState::default();
+        let mut achievements = Achievements::default();
+        let unlocked = achievements.award(&Trigger::Breached, &game);
+        assert_eq!(unlocked.len(), 1);
+        assert_eq!(unlocked[0].id, "first_breach");
+        assert!(achievements.award(&Trigger::Breached, &game).is_empty());
+
+        let close_call = TraceEvaded {
+            remaining_secs: 0.5,
+        };
+        let comfortable = TraceEvaded {
+            remaining_secs: 12.0,
+        };
+        assert!(achievements
+            .award(&Trigger::Evaded(&comfortable), &game)
+            .is_empty());
+        assert_eq!(
+            achievements.award(&Trigger::Evaded(&close_call), &game)[0].title,
+            "Ghost in the Machine"
+        );
+    }
+
+    #[test]
+    fn toasts_slide_in_and_back_out() {
+        let mut toast = Toast {
+            timer: Timer::from_seconds(TOAST_SECONDS, TimerMode::Once),
+        };
+        assert!(toast.right() < -TOAST_WIDTH);
+        toast
+            .timer
+            .tick(std::time::Duration::from_secs_f32(TOAST_SECONDS / 2.0));
+        assert_eq!(toast.right(), TOAST_MARGIN);
+        toast
+            .timer
+            .tick(std::time::Duration::from_secs_f32(TOAST_SECONDS / 2.0));
+        assert!(toast.right() < -TOAST_WIDTH);
+    }
+}
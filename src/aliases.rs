@@ -0,0 +1,72 @@
+use std::collections::HashMap;
+
+use crate::locale::{tr, Locale};
+use crate::output::OutputLine;
+
+/// How many aliases one command line may pass through before giving up,
+/// so `alias a=b` plus `alias b=a` can't hang the terminal.
+pub const MAX_ALIAS_DEPTH: usize = 8;
+
+/// User-defined command shortcuts, e.g. `scan` for `nmap neotechlabs.com`.
+#[derive(Default)]
+pub struct Aliases(pub HashMap<String, String>);
+
+impl Aliases {
+    /// Replaces an aliased verb with its target, keeping any arguments
+    /// typed after it. Errors if the chain is deeper than `MAX_ALIAS_DEPTH`.
+    pub fn expand(&self, line: &str, locale: &Locale) -> Result<String, String> {
+        let mut line = line.to_string();
+        // One look past the last expansion allowed, to see if it's needed
+        for depth in 0..=MAX_ALIAS_DEPTH {
+            let (verb, rest) = line.split_once(' ').unwrap_or((&line, ""));
+            let Some(target) = self.0.get(verb) else {
+                return Ok(line);
+            };
+            if depth == MAX_ALIAS_DEPTH {
+                break;
+            }
+            line = if rest.is_empty() {
+                target.clone()
+            } else {
+                format!("{} {}", target, rest)
+            };
+        }
+        let verb = line.split_whitespace().next().unwrap_or_default();
+        Err(tr!(locale, "alias.too_deep", name = verb))
+    }
+
+    /// Handles `alias`, listing every alias, and `alias name="command"`.
+    pub fn define(&mut self, definition: &str, locale: &Locale) -> Vec<OutputLine> {
+        if definition.is_empty() {
+            if self.0.is_empty() {
+                return vec![OutputLine::info(locale.get("alias.none"))];
+            }
+            let mut names: Vec<&String> = self.0.keys().collect();
+            names.sort_unstable();
+            return names
+                .into_iter()
+                .map(|name| OutputLine::Info(format!("alias {}='{}'", name, self.0[name])))
+                .collect();
+        }
+        let Some((name, target)) = definition.split_once('=') else {
+            return vec![OutputLine::error(locale.get("alias.usage"))];
+        };
+        let target = target.trim_matches(|c| c == '"' || c == '\'').trim();
+        if name.is_empty() || target.is_empty() {
+            return vec![OutputLine::error(locale.get("alias.usage"))];
+        }
+        self.0.insert(name.to_string(), target.to_string());
+        Vec::new()
+    }
+
+    pub fn remove(&mut self, name: &str, locale: &Locale) -> Vec<OutputLine> {
+        match self.0.remove(name) {
+            Some(_) => Vec::new(),
+            None => vec![OutputLine::Error(tr!(
+                locale,
+                "unalias.not_found",
+                name = name
+            ))],
+        }
+    }
+}
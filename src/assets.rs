@@ -1 +1,10 @@
-// Asset loading helpers
\ No newline at end of file
+use bevy::asset::io::file::FileAssetReader;
+use std::path::PathBuf;
+
+/// On-disk location of a file under `assets/`, for config files that are read
+/// directly at startup rather than through the `AssetServer`.
+pub fn asset_path(relative: &str) -> PathBuf {
+    FileAssetReader::get_base_path()
+        .join("assets")
+        .join(relative)
+}
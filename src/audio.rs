@@ -0,0 +1,144 @@
+use bevy::audio::Volume;
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+
+use crate::commands::GameLogicState;
+use crate::events::CommandSubmitted;
+
+#[derive(Resource)]
+pub struct AudioAssets {
+    pub keypress: Handle<AudioSource>,
+    pub accept: Handle<AudioSource>,
+    pub error: Handle<AudioSource>,
+    pub alarm: Handle<AudioSource>,
+}
+
+/// Master volume for all sound effects, from 0.0 (muted) to 1.0.
+#[derive(Resource)]
+pub struct AudioSettings {
+    pub volume: f32,
+}
+
+impl Default for AudioSettings {
+    fn default() -> Self {
+        AudioSettings { volume: 0.5 }
+    }
+}
+
+/// Marks the looping alarm that plays while a trace is active.
+#[derive(Component)]
+pub struct TraceAlarm;
+
+pub fn load_audio(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(AudioAssets {
+        keypress: asset_server.load("audio/keypress.wav"),
+        accept: asset_server.load("audio/accept.wav"),
+        error: asset_server.load("audio/error.wav"),
+        alarm: asset_server.load("audio/alarm.wav"),
+    });
+}
+
+#[derive(Clone, Copy)]
+pub enum Sound {
+    Keypress,
+    Accept,
+    Error,
+}
+
+/// System param for firing one-shot sound effects. Silent when the sounds
+/// were never loaded, as in headless runs.
+#[derive(SystemParam)]
+pub struct Sfx<'w, 's> {
+    commands: Commands<'w, 's>,
+    assets: Option<Res<'w, AudioAssets>>,
+    settings: Res<'w, AudioSettings>,
+}
+
+impl Sfx<'_, '_> {
+    /// Plays a one-shot sound, or nothing at all when muted.
+    pub fn play(&mut self, sound: Sound) {
+        let Some(assets) = &self.assets else {
+            return;
+        };
+        if self.settings.volume <= 0.0 {
+            return;
+        }
+        let source = match sound {
+            Sound::Keypress => &assets.keypress,
+            Sound::Accept => &assets.accept,
+            Sound::Error => &assets.error,
+        };
+        self.commands.spawn(AudioBundle {
+            source: source.clone(),
+            settings: PlaybackSettings::DESPAWN.with_volume(Volume::new(self.settings.volume)),
+        });
+    }
+}
+
+/// Plays the accept chime for every command that runs as asked, and the
+/// error chime for any that fails.
+pub fn play_command_sounds(mut submitted: EventReader<CommandSubmitted>, mut sfx: Sfx) {
+    for event in submitted.read() {
+        sfx.play(if event.failed {
+            Sound::Error
+        } else {
+            Sound::Accept
+        });
+    }
+}
+
+/// Sounds the alarm for as long as a trace is running, including one that
+/// was already running when the sounds loaded.
+pub fn update_trace_alarm(
+    mut commands: Commands,
+    game: Res<GameLogicState>,
+    audio: Res<AudioAssets>,
+    settings: Res<AudioSettings>,
+    alarms: Query<Entity, With<TraceAlarm>>,
+) {
+    let playing = !alarms.is_empty();
+    if game.trace.active && !playing && settings.volume > 0.0 {
+        commands.spawn((
+            AudioBundle {
+                source: audio.alarm.clone(),
+                settings: PlaybackSettings::LOOP.with_volume(Volume::new(settings.volume)),
+            },
+            TraceAlarm,
+        ));
+    } else if !game.trace.active && playing {
+        for entity in &alarms {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::system::RunSystemOnce;
+
+    fn alarms(world: &mut World) -> usize {
+        world.run_system_once(update_trace_alarm);
+        world.query::<&TraceAlarm>().iter(world).count()
+    }
+
+    #[test]
+    fn the_alarm_sounds_while_a_trace_runs_however_it_started() {
+        let mut world = World::new();
+        world.insert_resource(AudioAssets {
+            keypress: default(),
+            accept: default(),
+            error: default(),
+            alarm: default(),
+        });
+        world.init_resource::<AudioSettings>();
+        // Already running, with no event to say it started
+        let mut game = GameLogicState::default();
+        game.start_trace();
+        world.insert_resource(game);
+        assert_eq!(alarms(&mut world), 1);
+        assert_eq!(alarms(&mut world), 1);
+        world.resource_mut::<GameLogicState>().trace.active = false;
+        assert_eq!(alarms(&mut world), 0);
+    }
+}
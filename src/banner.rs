@@ -0,0 +1,54 @@
+use bevy::prelude::*;
+use std::fs;
+
+use crate::assets::asset_path;
+use crate::locale::Locale;
+use crate::markup::split_at_width;
+
+/// Used when `assets/banner.txt` is missing.
+const DEFAULT_BANNER: &str = r" _  _ ___ ___  _  _
+| \| | __/ _ \| \| |
+| .` | _| (_) | .` |
+|_|\_|___\___/|_|\_|
+      C  I  T  Y";
+
+/// The banner shown once the boot sequence is done: `assets/banner.<code>.txt`
+/// for the language in use if there is one, or else `assets/banner.txt`.
+pub fn load(locale: &Locale) -> String {
+    let localized = asset_path(&format!("banner.{}.txt", locale.code));
+    if let Ok(contents) = fs::read_to_string(localized) {
+        return contents;
+    }
+    let path = asset_path("banner.txt");
+    fs::read_to_string(&path).unwrap_or_else(|err| {
+        warn!(
+            "Using the built-in banner, could not read {}: {}",
+            path.display(),
+            err
+        );
+        DEFAULT_BANNER.to_string()
+    })
+}
+
+/// The banner's lines cut off at `columns` rather than wrapped, so the
+/// logo keeps its shape in a narrow terminal, losing only its right edge.
+pub fn fit(banner: &str, columns: usize) -> Vec<String> {
+    banner
+        .lines()
+        .map(|line| split_at_width(line, columns).0.to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn narrow_terminals_clip_the_logo_instead_of_wrapping_it() {
+        let lines = fit(DEFAULT_BANNER, 8);
+        assert_eq!(lines.len(), DEFAULT_BANNER.lines().count());
+        assert_eq!(lines[1], r"| \| | _");
+        assert_eq!(fit("{cyan}NEON CITY{/}", 4), ["{cyan}NEON"]);
+        assert_eq!(fit(DEFAULT_BANNER, 80).join("\n"), DEFAULT_BANNER);
+    }
+}
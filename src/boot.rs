@@ -0,0 +1,113 @@
+use bevy::prelude::*;
+use std::collections::VecDeque;
+use std::fs;
+
+use crate::assets::asset_path;
+use crate::banner;
+use crate::layout::TerminalLayout;
+use crate::locale::Locale;
+use crate::settings::OutputSpeed;
+use crate::terminal::{PendingOutput, ScrollbackState, TerminalConfig};
+
+/// Whether the boot sequence is still typing out. Commands can't be
+/// entered until it's `Ready`.
+#[derive(States, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum BootState {
+    #[default]
+    Booting,
+    Ready,
+}
+
+/// Pause between one boot line finishing and the next starting.
+const BOOT_LINE_SECONDS: f32 = 0.3;
+
+/// Used when `assets/boot.txt` is missing.
+const DEFAULT_BOOT_LINES: &str = "Initializing neural interface...\nMounting darknet...";
+
+/// One line of the boot sequence.
+enum BootLine {
+    /// Wrapped to the terminal like any other output.
+    Text(String),
+    /// Part of the banner, cut off at the edge to keep its shape.
+    Banner(String),
+}
+
+impl BootLine {
+    fn fitted(self, layout: &TerminalLayout) -> String {
+        match self {
+            BootLine::Text(line) => line,
+            BootLine::Banner(line) => banner::fit(&line, layout.columns).join("\n"),
+        }
+    }
+}
+
+/// Boot lines still to be shown.
+#[derive(Resource)]
+pub struct BootSequence {
+    lines: VecDeque<BootLine>,
+    timer: Timer,
+}
+
+impl BootSequence {
+    /// The lines of `assets/boot.txt`, one per output line, then the
+    /// banner and the `greeting` text from `locale`.
+    pub fn load(locale: &Locale, greeting: &str) -> Self {
+        let path = asset_path("boot.txt");
+        let contents = fs::read_to_string(&path).unwrap_or_else(|err| {
+            warn!(
+                "Using the built-in boot sequence, could not read {}: {}",
+                path.display(),
+                err
+            );
+            DEFAULT_BOOT_LINES.to_string()
+        });
+        let text = |line: &str| BootLine::Text(line.to_string());
+        let mut lines: VecDeque<BootLine> = contents.lines().map(text).collect();
+        lines.extend(
+            banner::load(locale)
+                .lines()
+                .map(|line| BootLine::Banner(line.to_string())),
+        );
+        lines.extend(locale.get(greeting).lines().map(text));
+        BootSequence {
+            lines,
+            timer: Timer::from_seconds(BOOT_LINE_SECONDS, TimerMode::Once),
+        }
+    }
+}
+
+/// Types out one boot line at a time, or all of them at once when any key
+/// is pressed or output is instant, then hands the terminal over to the
+/// player.
+pub fn run_boot(
+    time: Res<Time>,
+    keys: Res<ButtonInput<KeyCode>>,
+    layout: Res<TerminalLayout>,
+    config: Res<TerminalConfig>,
+    mut boot: ResMut<BootSequence>,
+    mut pending: ResMut<PendingOutput>,
+    mut scrollback: ResMut<ScrollbackState>,
+    mut next: ResMut<NextState<BootState>>,
+) {
+    if keys.get_just_pressed().next().is_some() || config.output_speed == OutputSpeed::Instant {
+        for line in boot.lines.drain(..) {
+            pending.push(&line.fitted(&layout), &layout);
+        }
+        pending.flush(&mut scrollback);
+        next.set(BootState::Ready);
+        return;
+    }
+    if pending.is_streaming() {
+        return;
+    }
+    if !boot.timer.tick(time.delta()).finished() {
+        return;
+    }
+    match boot.lines.pop_front() {
+        Some(line) => {
+            pending.push(&line.fitted(&layout), &layout);
+            boot.timer.reset();
+        }
+        None => next.set(BootState::Ready),
+    }
+}
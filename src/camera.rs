@@ -0,0 +1,84 @@
+use bevy::prelude::*;
+
+use crate::layout::TERMINAL_CENTER;
+
+/// Length of the fly-in at startup.
+const INTRO_SECONDS: f32 = 2.0;
+/// Where the fly-in starts, relative to the final framing.
+const INTRO_OFFSET: Vec3 = Vec3::new(0.0, 2.5, 6.0);
+
+/// Eases the camera from `start` to `end` over the intro, after which the
+/// component is removed.
+#[derive(Component)]
+pub struct CameraIntro {
+    pub timer: Timer,
+    pub start: Transform,
+    pub end: Transform,
+}
+
+impl CameraIntro {
+    /// A fly-in ending at `end` that starts further back and higher up,
+    /// already looking at the terminal.
+    pub fn new(end: Transform) -> Self {
+        CameraIntro {
+            timer: Timer::from_seconds(INTRO_SECONDS, TimerMode::Once),
+            start: Transform::from_translation(end.translation + INTRO_OFFSET)
+                .looking_at(TERMINAL_CENTER, Vec3::Y),
+            end,
+        }
+    }
+
+    /// The camera's placement at this point of the intro.
+    fn current(&self) -> Transform {
+        // Ease out, so the camera settles gently into place
+        let t = 1.0 - (1.0 - self.timer.fraction()).powi(3);
+        Transform {
+            translation: self.start.translation.lerp(self.end.translation, t),
+            rotation: self.start.rotation.slerp(self.end.rotation, t),
+            scale: self.end.scale,
+        }
+    }
+}
+
+/// Moves the camera along its intro. Any key or click skips to the end;
+/// the same press also finishes the boot sequence, so both land together.
+/// Typing is never held up by the intro.
+pub fn animate_camera_intro(
+    mut commands: Commands,
+    time: Res<Time>,
+    keys: Res<ButtonInput<KeyCode>>,
+    mouse: Res<ButtonInput<MouseButton>>,
+    mut cameras: Query<(Entity, &mut Transform, &mut CameraIntro)>,
+) {
+    let skip =
+        keys.get_just_pressed().next().is_some() || mouse.get_just_pressed().next().is_some();
+    for (entity, mut transform, mut intro) in &mut cameras {
+        if skip || intro.timer.tick(time.delta()).finished() {
+            *transform = intro.end;
+            commands.entity(entity).remove::<CameraIntro>();
+        } else {
+            *transform = intro.current();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn intro_runs_from_start_to_end() {
+        let end = Transform::from_xyz(0.0, 1.5, 4.0);
+        let mut intro = CameraIntro::new(end);
+        assert_eq!(intro.current().translation, intro.start.translation);
+        intro
+            .timer
+            .tick(Duration::from_secs_f32(INTRO_SECONDS / 2.0));
+        let halfway = intro.current().translation;
+        // Easing out covers most of the distance in the first half
+        assert!(halfway.distance(end.translation) < INTRO_OFFSET.length() / 2.0);
+        intro.timer.tick(Duration::from_secs_f32(INTRO_SECONDS));
+        assert!(intro.current().translation.distance(end.translation) < 1e-4);
+    }
+}
@@ -0,0 +1,32 @@
+use arboard::Clipboard;
+
+use crate::locale::{tr, Locale};
+use crate::output::OutputLine;
+
+/// Longest paste accepted, so a huge clipboard can't flood the input line.
+pub const MAX_PASTE_CHARS: usize = 1024;
+
+/// Reads text from the system clipboard. Empty, non-text or unavailable
+/// clipboards all come back as `None`.
+pub fn read_text() -> Option<String> {
+    let text = Clipboard::new().ok()?.get_text().ok()?;
+    if text.is_empty() {
+        None
+    } else {
+        Some(text.chars().take(MAX_PASTE_CHARS).collect())
+    }
+}
+
+pub fn write_text(text: &str) -> Result<(), arboard::Error> {
+    Clipboard::new()?.set_text(text)
+}
+
+/// Copies `text` for `clip`, with a line saying how it went. A clipboard
+/// that can't be reached, as without a display server, is reported rather
+/// than fatal.
+pub fn copy(text: &str, locale: &Locale) -> OutputLine {
+    match write_text(text) {
+        Ok(()) => OutputLine::info(tr!(locale, "clip.copied", bytes = text.len())),
+        Err(err) => OutputLine::Error(tr!(locale, "clip.failed", error = err)),
+    }
+}
@@ -0,0 +1,2552 @@
+//! The command registry and the state commands run against.
+//!
+//! A handler is given nothing but `&mut GameLogicState`, so whatever a
+//! command reads or changes lives there rather than in a resource of its
+//! own: where the runner is (`current_host`, `cwd`, `hops`), who they are
+//! (`handle`), the jobs commands start (`scan`, `download`) and the
+//! `mission` they tick off. That keeps a session switch, a reset and a
+//! saved game to one struct, and lets the tests here run commands without
+//! an `App`. What only the terminal needs, like the input line and its
+//! history, stays on `TerminalState`, and what a command asks of the rest
+//! of the app goes out as an `Effect`.
+
+use bevy::prelude::*;
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+
+use crate::aliases::Aliases;
+use crate::config::LoadedConfig;
+use crate::difficulty::{Difficulty, DifficultySettings};
+use crate::filesystem::{FileSystem, FsError};
+use crate::inventory::{self, Inventory, LootItem};
+use crate::jobs::BackgroundJobs;
+use crate::keybindings::CHORDS;
+use crate::locale::{tr, Locale, LocaleError};
+use crate::missions::Mission;
+use crate::network::{
+    HostStatus, LockoutState, Network, NetworkConditions, Port, HOME_HOST, LOCKOUT_SECONDS,
+};
+use crate::output::{self, OutputLine};
+use crate::pipes::{self, Pipeline};
+use crate::puzzle::ExploitPuzzle;
+use crate::replay;
+use crate::rng::GameRng;
+use crate::scripts::Script;
+use crate::settings::{DisplayMode, OutputSpeed};
+use crate::shellenv::{self, ShellEnv};
+use crate::status::Failure;
+use crate::theme::{Theme, ThemeError, PRESETS};
+use crate::tokenize::{self, tokenize};
+
+#[derive(Default)]
+pub struct TraceState {
+    pub active: bool,
+    pub timer: Timer,
+}
+
+/// An `nmap` in progress, revealing one port each time the timer fires.
+pub struct ScanJob {
+    pub host: String,
+    pub remaining: VecDeque<Port>,
+    pub timer: Timer,
+    open: usize,
+    vulnerable: usize,
+    /// Filters the scan's output was piped into, if any.
+    pipeline: Option<Pipeline>,
+}
+
+impl ScanJob {
+    fn new(host: &str, ports: &[Port], port_seconds: f32) -> Self {
+        ScanJob {
+            host: host.to_string(),
+            remaining: ports.iter().cloned().collect(),
+            timer: Timer::from_seconds(port_seconds, TimerMode::Repeating),
+            open: ports.len(),
+            vulnerable: ports.iter().filter(|port| port.vulnerable).count(),
+            pipeline: None,
+        }
+    }
+
+    /// Output revealed over `delta`, and whether the scan has finished. The
+    /// summary line follows one tick after the last port.
+    pub fn advance(&mut self, delta: Duration, locale: &Locale) -> (Vec<OutputLine>, bool) {
+        let mut lines = Vec::new();
+        let mut done = false;
+        for _ in 0..self.timer.tick(delta).times_finished_this_tick() {
+            match self.remaining.pop_front() {
+                Some(port) => lines.push(OutputLine::Port {
+                    num: port.number,
+                    service: port.service.to_string(),
+                    vulnerable: port.vulnerable,
+                }),
+                None => {
+                    lines.push(self.summary(locale));
+                    done = true;
+                    break;
+                }
+            }
+        }
+        if let Some(pipeline) = &mut self.pipeline {
+            lines = pipeline.feed(lines, locale);
+            if done {
+                lines.extend(pipeline.finish(locale));
+            }
+        }
+        (lines, done)
+    }
+
+    fn summary(&self, locale: &Locale) -> OutputLine {
+        if self.open == 0 {
+            return OutputLine::info(tr!(locale, "scan.complete_none", host = self.host));
+        }
+        OutputLine::info(tr!(
+            locale,
+            "scan.complete",
+            host = self.host,
+            open = self.open,
+            vulnerable = self.vulnerable
+        ))
+    }
+}
+
+/// Size of what `wget data` brings down, in megabytes.
+const DATA_MB: u64 = 500;
+/// Seconds `wget data` takes to come down once the host has answered.
+const DOWNLOAD_SECONDS: f32 = 3.0;
+/// Characters across the progress bar, between its brackets.
+const PROGRESS_BAR_WIDTH: usize = 10;
+
+/// A `wget data` in progress, which finishes when the timer does.
+pub struct DownloadJob {
+    /// Megabytes down so far, out of `total`.
+    pub downloaded: u64,
+    pub total: u64,
+    pub timer: Timer,
+}
+
+impl DownloadJob {
+    fn new(total: u64, seconds: f32) -> Self {
+        DownloadJob {
+            downloaded: 0,
+            total,
+            timer: Timer::from_seconds(seconds, TimerMode::Once),
+        }
+    }
+
+    /// Moves the download on by `delta`. Returns whether it has finished.
+    pub fn advance(&mut self, delta: Duration) -> bool {
+        self.timer.tick(delta);
+        self.downloaded = (self.total as f32 * self.timer.fraction()).round() as u64;
+        self.timer.finished()
+    }
+
+    /// How far along it is, e.g. `[####------] 42% 210MB/500MB`.
+    pub fn progress_bar(&self) -> String {
+        let percent = (self.downloaded * 100)
+            .checked_div(self.total)
+            .unwrap_or(100);
+        let filled = percent as usize * PROGRESS_BAR_WIDTH / 100;
+        format!(
+            "[{}{}] {}% {}MB/{}MB",
+            "#".repeat(filled),
+            "-".repeat(PROGRESS_BAR_WIDTH - filled),
+            percent,
+            self.downloaded,
+            self.total
+        )
+    }
+}
+
+/// Commands recharging after a run, and how long each has left.
+#[derive(Default)]
+pub struct Cooldowns(pub HashMap<String, Timer>);
+
+impl Cooldowns {
+    /// Seconds until `verb` can run again, if it is recharging.
+    pub fn remaining(&self, verb: &str) -> Option<f32> {
+        self.0.get(verb).map(Timer::remaining_secs)
+    }
+
+    /// Starts `verb` recharging, if it has a cooldown. Handlers call this
+    /// once they actually attempt something, so a mistyped host or file
+    /// can be retried straight away.
+    pub fn start(&mut self, verb: &str) {
+        if let Some(seconds) = cooldown(verb) {
+            self.0.insert(
+                verb.to_string(),
+                Timer::from_seconds(seconds, TimerMode::Once),
+            );
+        }
+    }
+
+    pub fn any_recharging(&self) -> bool {
+        !self.0.is_empty()
+    }
+
+    /// Counts down every cooldown, dropping the ones that are done.
+    pub fn tick(&mut self, delta: Duration) {
+        self.0.retain(|_, timer| !timer.tick(delta).finished());
+    }
+}
+
+/// In-game time of day, starting late at night by default.
+pub struct GameClock {
+    pub start: Duration,
+    pub elapsed: Duration,
+}
+
+impl Default for GameClock {
+    fn default() -> Self {
+        GameClock {
+            start: Duration::from_secs(23 * 3600 + 47 * 60),
+            elapsed: Duration::ZERO,
+        }
+    }
+}
+
+impl GameClock {
+    /// Current time as `HH:MM:SS`, wrapping past midnight.
+    pub fn time_of_day(&self) -> String {
+        let secs = (self.start + self.elapsed).as_secs() % (24 * 3600);
+        format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60)
+    }
+}
+
+/// Something a command needs done outside the game state.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Effect {
+    SaveSession,
+    Exit,
+    SetTheme(Theme),
+    /// Hold the response back this many seconds, for a slow remote host.
+    Latency(f32),
+    /// Run each command of the script after this one.
+    RunScript(Script),
+    /// Announce that this host has been breached.
+    Breached(String),
+    /// Announce a login to this host, played in with a transition.
+    Connected(String),
+    /// Announce a trace that completes in this many seconds.
+    TraceStarted(f32),
+    /// Announce a trace evaded with this many seconds left on it.
+    TraceEvaded(f32),
+    /// Start the game over from the boot sequence.
+    Reset,
+    /// Set the terminal background's opacity to this percentage, then
+    /// report it.
+    Opacity(Option<u8>),
+    /// Announce the loot item of this name collected.
+    Looted(String),
+    /// Print the startup banner again.
+    Banner,
+    /// Wipe the output off the screen.
+    Clear,
+    /// Copy the whole scrollback to the clipboard, then report it.
+    CopyScrollback,
+    /// Play the session so far back at this many times its speed.
+    Replay(f32),
+    /// Set how fast responses are typed out and save it, then report it.
+    OutputSpeed(Option<OutputSpeed>),
+    /// Set how the window fills the screen and save it, then report it.
+    DisplayMode(Option<DisplayMode>),
+    /// Turn matching commands by prefix on or off and save it, then report it.
+    PrefixMatching(Option<bool>),
+}
+
+/// The result of evaluating one command line.
+pub struct CommandOutcome {
+    pub lines: Vec<OutputLine>,
+    /// Whether the command didn't do what was asked: an unknown verb, a
+    /// usage error or any error in its response, even one piped away.
+    pub failed: bool,
+    pub effects: Vec<Effect>,
+}
+
+impl CommandOutcome {
+    /// The response as terminal markup, one output line per line.
+    pub fn render(&self, locale: &Locale) -> String {
+        output::render(&self.lines, locale)
+    }
+}
+
+/// Everything command logic reads and modifies. It holds no queries or
+/// other ECS handles, so commands can be run and tested without an `App`.
+#[derive(Resource)]
+pub struct GameLogicState {
+    pub network: Network,
+    pub current_host: String,
+    pub filesystem: FileSystem,
+    /// Working directory on `current_host`; reset to `/` on every hop.
+    pub cwd: String,
+    /// What the runner goes by, in the prompt and `$USER`. Set with `handle`.
+    pub handle: String,
+    /// Hosts `ssh` was run from to reach `current_host`, the first one
+    /// last, so `logout` can go back the way it came.
+    pub hops: Vec<String>,
+    pub trace: TraceState,
+    pub scan: Option<ScanJob>,
+    /// The `wget data` coming down, if one is.
+    pub download: Option<DownloadJob>,
+    /// An `exploit` challenge waiting for its answer.
+    pub puzzle: Option<ExploitPuzzle>,
+    pub clock: GameClock,
+    pub aliases: Aliases,
+    pub env: ShellEnv,
+    pub jobs: BackgroundJobs,
+    pub conditions: NetworkConditions,
+    pub difficulty: DifficultySettings,
+    /// Language of every response; commands themselves stay English.
+    pub locale: Locale,
+    pub lockouts: LockoutState,
+    pub cooldowns: Cooldowns,
+    pub inventory: Inventory,
+    /// The objectives `objectives` lists, ticked off by `track_objectives`.
+    pub mission: Mission,
+    pub rng: GameRng,
+    /// Whether a command can be run by a prefix only it starts with.
+    pub prefix_matching: bool,
+    /// The config the run started with, for `config` to show.
+    pub config: LoadedConfig,
+    /// Mistakes made since `apply_damage` last charged for them.
+    pub failures: Vec<Failure>,
+    /// Effects requested by the command being evaluated, handed back in
+    /// its `CommandOutcome`.
+    pub effects: Vec<Effect>,
+}
+
+impl Default for GameLogicState {
+    fn default() -> Self {
+        GameLogicState {
+            network: Network::default(),
+            current_host: HOME_HOST.to_string(),
+            filesystem: FileSystem::default(),
+            cwd: "/".to_string(),
+            handle: DEFAULT_HANDLE.to_string(),
+            hops: Vec::new(),
+            trace: TraceState::default(),
+            scan: None,
+            download: None,
+            puzzle: None,
+            clock: GameClock::default(),
+            aliases: Aliases::default(),
+            env: ShellEnv::default(),
+            jobs: BackgroundJobs::default(),
+            conditions: NetworkConditions::default(),
+            difficulty: DifficultySettings::default(),
+            locale: Locale::default(),
+            lockouts: LockoutState::default(),
+            cooldowns: Cooldowns::default(),
+            inventory: Inventory::default(),
+            mission: Mission::default(),
+            rng: GameRng::default(),
+            prefix_matching: false,
+            config: LoadedConfig::default(),
+            failures: Vec::new(),
+            effects: Vec::new(),
+        }
+    }
+}
+
+impl GameLogicState {
+    /// `line` with its aliases and then its variables expanded, so an
+    /// alias can refer to variables that are only read when it runs. An
+    /// `alias` or `export` line keeps its variables as typed for the same
+    /// reason: they're read when what it defines is used.
+    pub fn expand_line(&self, line: &str) -> Result<String, String> {
+        let expanded = self.aliases.expand(line, &self.locale)?;
+        if tokenize::verb(&expanded).is_some_and(|verb| DEFINITION_COMMANDS.contains(&&*verb)) {
+            return Ok(expanded);
+        }
+        Ok(self.env.expand(&expanded, &self.current_host))
+    }
+
+    /// Starts a trace, unless one is already running, and says whether
+    /// this difficulty traces at all.
+    pub fn start_trace(&mut self) -> bool {
+        let Some(seconds) = self.difficulty.trace_seconds else {
+            return false;
+        };
+        if !self.trace.active {
+            self.trace.active = true;
+            self.trace.timer = Timer::from_seconds(seconds, TimerMode::Once);
+            self.effects.push(Effect::TraceStarted(seconds));
+        }
+        true
+    }
+
+    /// Whether the run is underway: some host beyond home has been scanned
+    /// or breached, or a trace is running. Difficulty is fixed from then on.
+    pub fn mission_started(&self) -> bool {
+        self.trace.active
+            || self
+                .network
+                .discovered
+                .iter()
+                .any(|(host, status)| host != HOME_HOST && *status > HostStatus::Unknown)
+    }
+
+    /// Shell prompt for the current host and directory, e.g. `runner@neotechlabs:/var> `.
+    pub fn prompt(&self) -> String {
+        if self.puzzle.is_some() {
+            return "breach> ".to_string();
+        }
+        format!("{}@{}:{}> ", self.handle, self.short_host(), self.cwd)
+    }
+
+    /// Goes by `handle` from now on, in `$USER` as well as the prompt.
+    pub fn set_handle(&mut self, handle: &str) {
+        self.handle = handle.to_string();
+        self.env.0.insert("USER".to_string(), handle.to_string());
+    }
+
+    /// The current host without its domain, e.g. `neotechlabs`.
+    pub fn short_host(&self) -> &str {
+        self.current_host.split('.').next().unwrap_or_default()
+    }
+}
+
+pub type CommandHandler =
+    Box<dyn Fn(&[&str], &mut GameLogicState) -> Vec<OutputLine> + Send + Sync>;
+
+pub struct RegisteredCommand {
+    /// Argument spec shown in usage messages, e.g. `<host> [password]`.
+    pub args: String,
+    pub description: String,
+    /// Asks `Are you sure?` before running, for commands that can't be undone.
+    pub destructive: bool,
+    handler: CommandHandler,
+}
+
+impl RegisteredCommand {
+    /// Accepted argument counts: each `<required>` and `[optional]` in the
+    /// spec, with no upper limit if it ends in `...`.
+    fn arity(&self) -> (usize, usize) {
+        let required = self.args.matches('<').count();
+        if self.args.ends_with("...]") || self.args.ends_with("...>") {
+            return (required, usize::MAX);
+        }
+        (required, required + self.args.matches('[').count())
+    }
+}
+
+/// Maps each command verb to the handler that produces its response.
+#[derive(Resource)]
+pub struct CommandRegistry {
+    commands: HashMap<String, RegisteredCommand>,
+}
+
+impl Default for CommandRegistry {
+    fn default() -> Self {
+        let mut registry = CommandRegistry {
+            commands: HashMap::new(),
+        };
+        register_builtins(&mut registry);
+        registry
+    }
+}
+
+impl CommandRegistry {
+    /// Adds a command. `args` declares its arguments (`<required>`,
+    /// `[optional]`), and the handler only runs when the count fits.
+    pub fn register(
+        &mut self,
+        name: &str,
+        args: &str,
+        description: &str,
+        handler: impl Fn(&[&str], &mut GameLogicState) -> Vec<OutputLine> + Send + Sync + 'static,
+    ) {
+        self.commands.insert(
+            name.to_string(),
+            RegisteredCommand {
+                args: args.to_string(),
+                description: description.to_string(),
+                destructive: false,
+                handler: Box::new(handler),
+            },
+        );
+    }
+
+    /// Adds a command the terminal confirms before running.
+    pub fn register_destructive(
+        &mut self,
+        name: &str,
+        args: &str,
+        description: &str,
+        handler: impl Fn(&[&str], &mut GameLogicState) -> Vec<OutputLine> + Send + Sync + 'static,
+    ) {
+        self.register(name, args, description, handler);
+        if let Some(command) = self.commands.get_mut(name) {
+            command.destructive = true;
+        }
+    }
+
+    /// All command names, including the built-in `help`, in alphabetical order.
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.commands.keys().map(String::as_str).collect();
+        names.push("help");
+        names.sort_unstable();
+        names
+    }
+
+    /// The command `verb` runs: the one of that name, or with prefix
+    /// matching on, the only one it's the start of. Otherwise, the
+    /// commands it could be short for, if there are any.
+    pub fn resolve<'a>(
+        &'a self,
+        verb: &'a str,
+        game: &GameLogicState,
+    ) -> Result<&'a str, Vec<&'a str>> {
+        let names = self.names();
+        if names.contains(&verb) {
+            return Ok(verb);
+        }
+        if !game.prefix_matching || verb.is_empty() {
+            return Err(Vec::new());
+        }
+        let candidates: Vec<&str> = names
+            .into_iter()
+            .filter(|name| name.starts_with(verb))
+            .collect();
+        match candidates[..] {
+            [name] => Ok(name),
+            _ => Err(candidates),
+        }
+    }
+
+    /// Whether the verb of `line` names a registered command.
+    pub fn is_known(&self, line: &str, game: &GameLogicState) -> bool {
+        tokenize::verb(line).is_some_and(|verb| self.resolve(&verb, game).is_ok())
+    }
+
+    /// Whether `line`, once its aliases and variables are expanded, runs a
+    /// destructive command.
+    pub fn is_destructive(&self, line: &str, game: &GameLogicState) -> bool {
+        // Answers to a challenge are never run as commands
+        if game.puzzle.is_some() {
+            return false;
+        }
+        let Ok(expanded) = game.expand_line(line) else {
+            return false;
+        };
+        let verb = tokenize::verb(&expanded).unwrap_or_default();
+        let Ok(verb) = self.resolve(&verb, game) else {
+            return false;
+        };
+        // Logging out of a remote host only goes back to the one before it
+        if DISCONNECT_COMMANDS.contains(&verb) && !game.hops.is_empty() {
+            return false;
+        }
+        self.commands
+            .get(verb)
+            .is_some_and(|command| command.destructive)
+    }
+
+    /// Runs `cmd` with these commands, as `evaluate_command` does.
+    pub fn evaluate_command(&self, cmd: &str, state: &mut GameLogicState) -> CommandOutcome {
+        evaluate_command(self, cmd, state)
+    }
+
+    /// Starts `cmd` as a background job if it ends in `&`, returning the
+    /// line that says so. Unknown and destructive commands still run in
+    /// the foreground, so mistakes and confirmations show up straight away.
+    fn start_job(&self, cmd: &str, state: &mut GameLogicState) -> Option<OutputLine> {
+        // Only an `&` the shell would see counts, not one quoted or escaped
+        let parts = tokenize::split_unquoted(cmd, '&');
+        let [.., last] = parts[..] else {
+            return None;
+        };
+        if parts.len() < 2 || !last.trim().is_empty() {
+            return None;
+        }
+        let command = cmd[..cmd.len() - last.len() - 1].trim_end();
+        let expanded = state.expand_line(command).ok()?;
+        let verb = tokenize::verb(&expanded)?;
+        let Ok(verb) = self.resolve(&verb, state) else {
+            return None;
+        };
+        if self.is_destructive(command, state) {
+            return None;
+        }
+        let id = state
+            .jobs
+            .start(command, verb, &state.current_host, &state.cwd);
+        Some(OutputLine::Info(tr!(
+            state.locale,
+            "jobs.started",
+            id = id,
+            command = command
+        )))
+    }
+
+    /// Runs a full command line and returns its response.
+    pub fn dispatch(&self, line: &str, game: &mut GameLogicState) -> Vec<OutputLine> {
+        let words = match tokenize(line) {
+            Ok(words) => words,
+            Err(err) => return vec![OutputLine::Error(err.message(&game.locale))],
+        };
+        let Some((verb, args)) = words.split_first() else {
+            return Vec::new();
+        };
+        let args: Vec<&str> = args.iter().map(String::as_str).collect();
+        let verb = match self.resolve(verb, game) {
+            Ok(verb) => verb,
+            Err(candidates) if candidates.len() > 1 => {
+                return vec![OutputLine::Error(tr!(
+                    game.locale,
+                    "command.ambiguous",
+                    verb = verb,
+                    names = candidates.join(", ")
+                ))];
+            }
+            Err(_) => verb,
+        };
+        if verb == "help" {
+            return self.help_text(&game.locale);
+        }
+        let Some(command) = self.commands.get(verb) else {
+            let message = match closest_command(verb, &self.names()) {
+                Some(name) => tr!(
+                    game.locale,
+                    "command.unknown_suggest",
+                    line = line,
+                    name = name
+                ),
+                None => tr!(game.locale, "command.unknown", line = line),
+            };
+            return vec![OutputLine::Error(message)];
+        };
+        if !game.network.allows(&game.current_host, verb) {
+            return vec![OutputLine::Error(tr!(
+                game.locale,
+                "command.not_available",
+                name = verb
+            ))];
+        }
+        let (min, max) = command.arity();
+        if args.len() < min || args.len() > max {
+            return vec![OutputLine::Error(tr!(
+                game.locale,
+                "command.usage",
+                usage = usage(verb, command)
+            ))];
+        }
+        if let Some(seconds) = game.cooldowns.remaining(verb) {
+            return vec![OutputLine::Error(tr!(
+                game.locale,
+                "cooldown.recharging",
+                name = verb,
+                seconds = seconds.ceil()
+            ))];
+        }
+        (command.handler)(&args, game)
+    }
+
+    /// Every command with its usage and description. Descriptions are
+    /// looked up as `help.<name>`, falling back to the registered one.
+    fn help_text(&self, locale: &Locale) -> Vec<OutputLine> {
+        let mut out = vec![OutputLine::info(locale.get("help.header"))];
+        for name in self.names() {
+            let key = format!("help.{}", name);
+            let (usage, fallback) = match self.commands.get(name) {
+                Some(command) => (usage(name, command), command.description.as_str()),
+                None => (name.to_string(), "List available commands"),
+            };
+            let description = locale.try_get(&key).unwrap_or(fallback);
+            out.push(OutputLine::Info(format!("  {:<22} {}", usage, description)));
+        }
+        out.push(OutputLine::info(locale.get("help.keys")));
+        for (chord, key) in CHORDS {
+            out.push(OutputLine::Info(format!(
+                "  {:<22} {}",
+                chord,
+                locale.get(key)
+            )));
+        }
+        out
+    }
+}
+
+/// Expands aliases and variables in `cmd`, runs it with `registry`'s
+/// commands and reports what happened. This is the whole of command
+/// handling; callers only apply the outcome.
+pub fn evaluate_command(
+    registry: &CommandRegistry,
+    cmd: &str,
+    state: &mut GameLogicState,
+) -> CommandOutcome {
+    if let Some(puzzle) = state.puzzle.take() {
+        let lines = answer_puzzle(puzzle, cmd, state);
+        return CommandOutcome {
+            failed: has_error(&lines),
+            lines,
+            effects: std::mem::take(&mut state.effects),
+        };
+    }
+    if let Some(line) = registry.start_job(cmd, state) {
+        return CommandOutcome {
+            lines: vec![line],
+            failed: false,
+            effects: Vec::new(),
+        };
+    }
+    let parsed = state.expand_line(cmd).and_then(|expanded| {
+        let (command, pipeline) = Pipeline::parse(&expanded, &state.locale)?;
+        Ok((command.to_string(), pipeline))
+    });
+    let (lines, failed) = match parsed {
+        Ok((command, pipeline)) => {
+            let scanning = state.scan.is_some();
+            let response = registry.dispatch(&command, state);
+            // Judged before a filter like `grep` can drop the error
+            let failed = !registry.is_known(&command, state) || has_error(&response);
+            let lines = match pipeline {
+                Some(pipeline) => pipe(response, pipeline, scanning, state),
+                None => response,
+            };
+            (lines, failed)
+        }
+        Err(err) => (vec![OutputLine::Error(err)], true),
+    };
+    CommandOutcome {
+        lines,
+        failed,
+        effects: std::mem::take(&mut state.effects),
+    }
+}
+
+/// Filters a command's `response` through `pipeline`. A scan the command
+/// started, when none was `scanning` before, takes the pipeline along for
+/// the ports it reveals.
+fn pipe(
+    response: Vec<OutputLine>,
+    mut pipeline: Pipeline,
+    scanning: bool,
+    game: &mut GameLogicState,
+) -> Vec<OutputLine> {
+    let mut lines = pipeline.feed(response, &game.locale);
+    match &mut game.scan {
+        Some(job) if !scanning => job.pipeline = Some(pipeline),
+        _ => lines.extend(pipeline.finish(&game.locale)),
+    }
+    lines
+}
+
+fn has_error(lines: &[OutputLine]) -> bool {
+    lines
+        .iter()
+        .any(|line| matches!(line, OutputLine::Error(_)))
+}
+
+fn usage(name: &str, command: &RegisteredCommand) -> String {
+    if command.args.is_empty() {
+        name.to_string()
+    } else {
+        format!("{} {}", name, command.args)
+    }
+}
+
+/// Furthest a typo can be from a command and still get a suggestion.
+const MAX_SUGGESTION_DISTANCE: usize = 2;
+
+/// The command nearest to `input` by edit distance, if any is close enough.
+/// A match has to keep at least one character of `input`, so `xy` isn't
+/// "corrected" to `ls`.
+pub fn closest_command<'a>(input: &str, commands: &[&'a str]) -> Option<&'a str> {
+    let len = input.chars().count();
+    commands
+        .iter()
+        .map(|name| (levenshtein(input, name), *name))
+        .filter(|&(distance, _)| distance <= MAX_SUGGESTION_DISTANCE && distance < len)
+        .min_by_key(|&(distance, _)| distance)
+        .map(|(_, name)| name)
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitute = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitute.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// Seconds a command recharges for after it runs, so it can't be spammed.
+/// Until then it is turned away before its handler runs.
+const COOLDOWNS: &[(&str, f32)] = &[("exploit", 3.0), ("wget", 5.0)];
+
+fn cooldown(verb: &str) -> Option<f32> {
+    COOLDOWNS
+        .iter()
+        .find(|(name, _)| *name == verb)
+        .map(|(_, seconds)| *seconds)
+}
+
+fn register_builtins(registry: &mut CommandRegistry) {
+    pipes::register_filters(registry);
+    registry.register(
+        "nmap",
+        "<host>",
+        "Scan a host for open ports",
+        |args, game| nmap(args[0], game),
+    );
+    registry.register(
+        "ssh",
+        "<host> [password]",
+        "Connect to a remote host",
+        |args, game| match numbered_host(args[0], game) {
+            Ok(host) => ssh(&host, args.get(1).copied(), game),
+            Err(lines) => lines,
+        },
+    );
+    registry.register(
+        "connect",
+        "<host|number> [password]",
+        "Connect to a host by name or its number in hosts",
+        |args, game| match numbered_host(args[0], game) {
+            Ok(host) => ssh(&host, args.get(1).copied(), game),
+            Err(lines) => lines,
+        },
+    );
+    registry.register("hosts", "", "List discovered hosts by number", |_, game| {
+        hosts(game)
+    });
+    registry.register(
+        "exploit",
+        "[host]",
+        "Breach a host's firewall",
+        |args, game| {
+            let host = args
+                .first()
+                .map_or(game.current_host.clone(), |h| h.to_string());
+            exploit(&host, game)
+        },
+    );
+    registry.register(
+        "wget",
+        "<file>",
+        "Download data from the target",
+        |args, game| match args[0] {
+            "data" => {
+                if game.download.is_some() {
+                    return vec![OutputLine::error(game.locale.get("wget.in_progress"))];
+                }
+                let host = game.current_host.clone();
+                let lost = wait_for(&host, game);
+                game.cooldowns.start("wget");
+                // The bar fills in `tick_download`, which then finishes it
+                game.download = Some(DownloadJob::new(DATA_MB, DOWNLOAD_SECONDS));
+                after_loss(
+                    lost,
+                    vec![OutputLine::Info(tr!(
+                        game.locale,
+                        "wget.downloading",
+                        host = host
+                    ))],
+                )
+            }
+            file => vec![OutputLine::Error(tr!(
+                game.locale,
+                "wget.no_such_file",
+                file = file
+            ))],
+        },
+    );
+    registry.register(
+        "ping",
+        "<host>",
+        "Measure the round trip to a host",
+        |args, game| ping(args[0], game),
+    );
+    registry.register("loot", "", "List the data collected so far", |_, game| {
+        loot(game)
+    });
+    registry.register(
+        "objectives",
+        "",
+        "List the mission's objectives",
+        |_, game| game.mission.list(&game.locale),
+    );
+    registry.register("cloak", "", "Evade an active trace", |_, game| {
+        let key = if game.trace.active {
+            game.trace.active = false;
+            let remaining = game.trace.timer.remaining_secs();
+            game.effects.push(Effect::TraceEvaded(remaining));
+            "cloak.evaded"
+        } else {
+            "cloak.no_trace"
+        };
+        vec![OutputLine::info(game.locale.get(key))]
+    });
+    registry.register("ls", "[dir]", "List a directory", |args, game| {
+        ls(args.first().copied().unwrap_or("."), game)
+    });
+    registry.register("cd", "<dir>", "Change directory", |args, game| {
+        cd(args[0], game)
+    });
+    registry.register("cat", "<file>", "Print a file", |args, game| {
+        cat(args[0], game)
+    });
+    registry.register(
+        "decrypt",
+        "<file> <key>",
+        "Decrypt a file with its key",
+        |args, game| decrypt(args[0], args[1], game),
+    );
+    registry.register("pwd", "", "Print the working directory", |_, game| {
+        vec![OutputLine::info(game.cwd.clone())]
+    });
+    registry.register(
+        "alias",
+        "[name=\"command\"...]",
+        "Define or list command aliases",
+        |args, game| game.aliases.define(&args.join(" "), &game.locale),
+    );
+    registry.register("unalias", "<name>", "Remove an alias", |args, game| {
+        game.aliases.remove(args[0], &game.locale)
+    });
+    registry.register(
+        "export",
+        "[name=value...]",
+        "Set or list shell variables",
+        |args, game| {
+            game.env
+                .export(&args.join(" "), &game.current_host, &game.locale)
+        },
+    );
+    registry.register("echo", "[text...]", "Print text", |args, _| {
+        vec![shellenv::echo(args)]
+    });
+    registry.register(
+        "run",
+        "<script>",
+        "Run each command in a script from assets/scripts",
+        |args, game| vec![run_script(args[0], game)],
+    );
+    registry.register(
+        "jobs",
+        "",
+        "List background jobs started with &",
+        |_, game| game.jobs.list(&game.locale),
+    );
+    registry.register(
+        "difficulty",
+        "[level]",
+        "Show or set the difficulty (easy, normal, hard)",
+        |args, game| {
+            let line = match args.first() {
+                None => OutputLine::Info(tr!(
+                    game.locale,
+                    "difficulty.show",
+                    level = game.difficulty.level
+                )),
+                Some(_) if game.mission_started() => {
+                    OutputLine::error(game.locale.get("difficulty.locked"))
+                }
+                Some(name) => match Difficulty::parse(name) {
+                    Some(level) => {
+                        game.difficulty = level.into();
+                        OutputLine::Info(tr!(game.locale, "difficulty.set", level = level))
+                    }
+                    None => OutputLine::Error(tr!(game.locale, "difficulty.unknown", name = name)),
+                },
+            };
+            vec![line]
+        },
+    );
+    registry.register("clock", "", "Show the in-game time", |_, game| {
+        vec![OutputLine::Info(game.clock.time_of_day())]
+    });
+    registry.register(
+        "theme",
+        "<name>",
+        "Recolour the terminal (classic, amber, ice or a custom theme)",
+        |args, game| {
+            let line = match Theme::load(args[0]) {
+                Ok(theme) => {
+                    let line = OutputLine::Info(tr!(game.locale, "theme.set", name = theme.name));
+                    game.effects.push(Effect::SetTheme(theme));
+                    line
+                }
+                Err(ThemeError::Unknown) => OutputLine::Error(tr!(
+                    game.locale,
+                    "theme.unknown",
+                    name = args[0],
+                    presets = PRESETS.join(", ")
+                )),
+                Err(ThemeError::Invalid { path, error }) => OutputLine::Error(tr!(
+                    game.locale,
+                    "theme.invalid",
+                    path = path,
+                    error = error
+                )),
+            };
+            vec![line]
+        },
+    );
+    registry.register(
+        "opacity",
+        "[0-100]",
+        "Show or set how opaque the terminal is",
+        |args, game| {
+            let Some(percent) = args.first() else {
+                game.effects.push(Effect::Opacity(None));
+                return Vec::new();
+            };
+            match percent.trim_end_matches('%').parse::<u8>() {
+                Ok(percent) if percent <= 100 => {
+                    // Any fainter and the text gets lost in the scene
+                    let percent = percent.max(MIN_OPACITY);
+                    game.effects.push(Effect::Opacity(Some(percent)));
+                    Vec::new()
+                }
+                _ => vec![OutputLine::Error(tr!(
+                    game.locale,
+                    "opacity.invalid",
+                    percent = percent
+                ))],
+            }
+        },
+    );
+    registry.register("whoami", "", "Show the runner's handle", |_, game| {
+        vec![OutputLine::Info(game.handle.clone())]
+    });
+    registry.register(
+        "handle",
+        "[name]",
+        "Show or change the runner's handle",
+        |args, game| {
+            let Some(name) = args.first() else {
+                return vec![OutputLine::Info(tr!(
+                    game.locale,
+                    "handle.show",
+                    handle = game.handle
+                ))];
+            };
+            if !is_valid_handle(name) {
+                return vec![OutputLine::Error(tr!(
+                    game.locale,
+                    "handle.invalid",
+                    name = name,
+                    max = MAX_HANDLE_LEN
+                ))];
+            }
+            game.set_handle(name);
+            vec![OutputLine::Info(tr!(
+                game.locale,
+                "handle.set",
+                handle = name
+            ))]
+        },
+    );
+    registry.register(
+        "config",
+        "",
+        "Show the configuration and where each value came from",
+        |_, game| {
+            game.config
+                .describe(&game.locale)
+                .into_iter()
+                .map(OutputLine::Info)
+                .collect()
+        },
+    );
+    registry.register(
+        "settings",
+        "[speed|window|prefix] [value]",
+        "Show or change settings, e.g. settings speed instant",
+        |args, game| match args {
+            [] => {
+                game.effects.push(Effect::OutputSpeed(None));
+                game.effects.push(Effect::DisplayMode(None));
+                game.effects.push(Effect::PrefixMatching(None));
+                Vec::new()
+            }
+            ["prefix"] => {
+                game.effects.push(Effect::PrefixMatching(None));
+                Vec::new()
+            }
+            ["prefix", value] => match value.to_ascii_lowercase().as_str() {
+                "on" => {
+                    game.effects.push(Effect::PrefixMatching(Some(true)));
+                    Vec::new()
+                }
+                "off" => {
+                    game.effects.push(Effect::PrefixMatching(Some(false)));
+                    Vec::new()
+                }
+                _ => vec![OutputLine::Error(tr!(
+                    game.locale,
+                    "settings.invalid_prefix",
+                    value = value
+                ))],
+            },
+            ["speed"] => {
+                game.effects.push(Effect::OutputSpeed(None));
+                Vec::new()
+            }
+            ["window"] => {
+                game.effects.push(Effect::DisplayMode(None));
+                Vec::new()
+            }
+            ["window", value] => match DisplayMode::parse(value) {
+                Some(mode) => {
+                    game.effects.push(Effect::DisplayMode(Some(mode)));
+                    Vec::new()
+                }
+                None => vec![OutputLine::Error(tr!(
+                    game.locale,
+                    "settings.invalid_window",
+                    value = value
+                ))],
+            },
+            ["speed", value] => match OutputSpeed::parse(value) {
+                Some(speed) => {
+                    game.effects.push(Effect::OutputSpeed(Some(speed)));
+                    Vec::new()
+                }
+                None => vec![OutputLine::Error(tr!(
+                    game.locale,
+                    "settings.invalid_speed",
+                    value = value
+                ))],
+            },
+            [name, ..] => vec![OutputLine::Error(tr!(
+                game.locale,
+                "settings.unknown",
+                name = name
+            ))],
+        },
+    );
+    registry.register("clear", "", "Clear the screen", |_, game| {
+        game.effects.push(Effect::Clear);
+        Vec::new()
+    });
+    registry.register("banner", "", "Print the startup banner again", |_, game| {
+        game.effects.push(Effect::Banner);
+        Vec::new()
+    });
+    registry.register("seed", "", "Show the seed of this run", |_, game| {
+        vec![OutputLine::Info(tr!(
+            game.locale,
+            "seed.show",
+            seed = game.rng.seed()
+        ))]
+    });
+    registry.register(
+        "lang",
+        "[code]",
+        "Show or change the language of terminal messages",
+        |args, game| {
+            let Some(code) = args.first() else {
+                return vec![OutputLine::Info(tr!(
+                    game.locale,
+                    "lang.show",
+                    code = game.locale.code
+                ))];
+            };
+            let line = match Locale::load(code) {
+                Ok(locale) => {
+                    game.locale = locale;
+                    OutputLine::info(game.locale.get("lang.set"))
+                }
+                Err(LocaleError::Unknown) => {
+                    OutputLine::Error(tr!(game.locale, "lang.unknown", code = code))
+                }
+                Err(LocaleError::Invalid { path, error }) => {
+                    OutputLine::Error(tr!(game.locale, "lang.invalid", path = path, error = error))
+                }
+            };
+            vec![line]
+        },
+    );
+    registry.register(
+        "replay",
+        "[speed]",
+        "Play this session back, optionally faster, e.g. replay 2x",
+        |args, game| {
+            let speed = match args.first() {
+                None => 1.0,
+                Some(arg) => match arg.trim_end_matches('x').parse::<f32>() {
+                    Ok(speed) if (replay::MIN_SPEED..=replay::MAX_SPEED).contains(&speed) => speed,
+                    _ => {
+                        return vec![OutputLine::Error(tr!(
+                            game.locale,
+                            "replay.invalid_speed",
+                            speed = arg
+                        ))]
+                    }
+                },
+            };
+            game.effects.push(Effect::Replay(speed));
+            Vec::new()
+        },
+    );
+    registry.register("save", "", "Save the session to disk", |_, game| {
+        game.effects.push(Effect::SaveSession);
+        Vec::new()
+    });
+    registry.register_destructive("reset", "", "Start over from a fresh boot", |_, game| {
+        game.effects.push(Effect::Reset);
+        Vec::new()
+    });
+    for name in DISCONNECT_COMMANDS {
+        registry.register_destructive(
+            name,
+            "",
+            "Log out of this host, or close the terminal at home",
+            |_, game| disconnect(game),
+        );
+    }
+    registry.register_destructive("quit", "", "Close the terminal", |_, game| {
+        game.effects.push(Effect::Exit);
+        Vec::new()
+    });
+}
+
+/// What every runner goes by until they pick a handle.
+pub const DEFAULT_HANDLE: &str = "runner";
+/// Longest handle `handle` accepts, in chars.
+const MAX_HANDLE_LEN: usize = 16;
+
+/// Whether `name` will do as a handle: letters, digits and underscores,
+/// and not too long for the prompt.
+pub fn is_valid_handle(name: &str) -> bool {
+    (1..=MAX_HANDLE_LEN).contains(&name.len())
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Lowest opacity `opacity` allows, in percent, so text stays readable.
+pub const MIN_OPACITY: u8 = 30;
+
+/// Commands that close the connection to the current host, and close the
+/// terminal when there is none to close.
+const DISCONNECT_COMMANDS: [&str; 3] = ["disconnect", "exit", "logout"];
+
+/// Commands whose arguments define something, which `expand_line` leaves
+/// the variables in.
+const DEFINITION_COMMANDS: [&str; 2] = ["alias", "export"];
+
+/// Goes back to the host `ssh` was last run from, or exits when the runner
+/// is on the host they started on.
+fn disconnect(game: &mut GameLogicState) -> Vec<OutputLine> {
+    let Some(previous) = game.hops.pop() else {
+        game.effects.push(Effect::Exit);
+        return Vec::new();
+    };
+    let closed = std::mem::replace(&mut game.current_host, previous);
+    game.cwd = "/".to_string();
+    vec![OutputLine::Info(tr!(
+        game.locale,
+        "logout.closed",
+        host = closed
+    ))]
+}
+
+fn nmap(host: &str, game: &mut GameLogicState) -> Vec<OutputLine> {
+    if let Some(job) = &game.scan {
+        return vec![OutputLine::Error(tr!(
+            game.locale,
+            "nmap.in_progress",
+            host = job.host
+        ))];
+    }
+    let Some(node) = game.network.host(host) else {
+        return vec![OutputLine::error(game.locale.get("nmap.not_found"))];
+    };
+    // Ports are revealed over time by `tick_scan`
+    game.scan = Some(ScanJob::new(
+        host,
+        &node.ports,
+        game.difficulty.scan_port_seconds,
+    ));
+    // Only a scan that actually starts tells the runner anything
+    game.network.discover(host, HostStatus::Scanned);
+    let lost = wait_for(host, game);
+    after_loss(
+        lost,
+        vec![OutputLine::Info(tr!(
+            game.locale,
+            "nmap.scanning",
+            host = host
+        ))],
+    )
+}
+
+fn exploit(host: &str, game: &mut GameLogicState) -> Vec<OutputLine> {
+    let Some(node) = game.network.host(host) else {
+        return vec![OutputLine::error(game.locale.get("exploit.not_found"))];
+    };
+    if host != game.current_host && !game.network.is_neighbor(&game.current_host, host) {
+        return vec![OutputLine::Error(tr!(
+            game.locale,
+            "exploit.unreachable",
+            host = host,
+            from = game.current_host
+        ))];
+    }
+    let vulnerable = node.ports.iter().any(|port| port.vulnerable);
+    let lost = wait_for(host, game);
+    game.cooldowns.start("exploit");
+    if !vulnerable {
+        game.failures.push(Failure::ExploitFailed);
+        return after_loss(
+            lost,
+            vec![OutputLine::Error(tr!(
+                game.locale,
+                "exploit.nothing_vulnerable",
+                host = host
+            ))],
+        );
+    }
+    let puzzle = ExploitPuzzle::new(host, &game.difficulty, &mut game.rng);
+    let lines = vec![
+        OutputLine::Info(tr!(
+            game.locale,
+            "exploit.challenge",
+            host = host,
+            seconds = game.difficulty.puzzle_seconds
+        )),
+        OutputLine::Info(format!("  {}", puzzle.challenge())),
+    ];
+    game.puzzle = Some(puzzle);
+    after_loss(lost, lines)
+}
+
+/// Checks `answer` against the open challenge, breaching its host if it
+/// is right.
+fn answer_puzzle(
+    puzzle: ExploitPuzzle,
+    answer: &str,
+    game: &mut GameLogicState,
+) -> Vec<OutputLine> {
+    if !puzzle.accepts(answer) {
+        return fail_puzzle(&puzzle, "exploit.failed", game);
+    }
+    game.network.discover(&puzzle.target, HostStatus::Breached);
+    game.effects.push(Effect::Breached(puzzle.target.clone()));
+    vec![OutputLine::Info(tr!(
+        game.locale,
+        "exploit.breached",
+        host = puzzle.target
+    ))]
+}
+
+/// Reports a failed challenge with the message at `key`. Countermeasures
+/// start a trace, if this difficulty traces.
+pub fn fail_puzzle(
+    puzzle: &ExploitPuzzle,
+    key: &str,
+    game: &mut GameLogicState,
+) -> Vec<OutputLine> {
+    let mut lines = vec![OutputLine::Error(tr!(
+        game.locale,
+        key,
+        host = puzzle.target
+    ))];
+    if game.start_trace() {
+        lines.push(OutputLine::info(game.locale.get("exploit.traced")));
+    }
+    lines
+}
+
+/// Makes the response take a round trip to `host`, or two when packets
+/// are lost on the way, and returns the line saying so if they were.
+fn wait_for(host: &str, game: &mut GameLogicState) -> Option<OutputLine> {
+    round_trip(host, game).1
+}
+
+/// Like `wait_for`, but also returns how long the trip that got through
+/// took, in seconds.
+fn round_trip(host: &str, game: &mut GameLogicState) -> (f32, Option<OutputLine>) {
+    let host_seconds = game
+        .network
+        .host(host)
+        .map_or(0.0, |node| node.latency_seconds);
+    let conditions = &game.conditions;
+    let mut trip = conditions.round_trip(host_seconds, &mut game.rng);
+    let mut total = trip;
+    let lost = conditions.loss_chance > 0.0 && game.rng.chance(conditions.loss_chance);
+    if lost {
+        trip = conditions.round_trip(host_seconds, &mut game.rng);
+        total += trip;
+    }
+    if total > 0.0 {
+        game.effects.push(Effect::Latency(total));
+    }
+    let notice = lost.then(|| OutputLine::info(game.locale.get("net.packet_lost")));
+    (trip, notice)
+}
+
+/// `lines`, after the packet-loss line from `wait_for` if there was one.
+fn after_loss(lost: Option<OutputLine>, lines: Vec<OutputLine>) -> Vec<OutputLine> {
+    lost.into_iter().chain(lines).collect()
+}
+
+/// Handles `ping`, which reports how long a round trip to `host` takes.
+fn ping(host: &str, game: &mut GameLogicState) -> Vec<OutputLine> {
+    if game.network.host(host).is_none() {
+        return vec![OutputLine::Error(tr!(
+            game.locale,
+            "ping.not_found",
+            host = host
+        ))];
+    }
+    if host != game.current_host && !game.network.is_neighbor(&game.current_host, host) {
+        return vec![OutputLine::Error(tr!(
+            game.locale,
+            "ping.unreachable",
+            host = host,
+            from = game.current_host
+        ))];
+    }
+    let (trip, lost) = round_trip(host, game);
+    let reply = OutputLine::Info(tr!(
+        game.locale,
+        "ping.reply",
+        host = host,
+        ms = (trip * 1000.0).round()
+    ));
+    after_loss(lost, vec![reply])
+}
+
+/// Handles `hosts`.
+fn hosts(game: &GameLogicState) -> Vec<OutputLine> {
+    let mut lines = vec![OutputLine::info(game.locale.get("hosts.header"))];
+    for (index, host, status) in game.network.numbered() {
+        let status = game.locale.get(match status {
+            HostStatus::Unknown => "hosts.unknown",
+            HostStatus::Scanned => "hosts.scanned",
+            HostStatus::Breached => "hosts.breached",
+        });
+        let key = if host == game.current_host {
+            "hosts.current"
+        } else {
+            "hosts.entry"
+        };
+        lines.push(OutputLine::Info(tr!(
+            game.locale,
+            key,
+            index = index,
+            host = host,
+            status = status
+        )));
+    }
+    lines
+}
+
+/// The host `arg` names, either by name or by its number in `hosts`.
+fn numbered_host(arg: &str, game: &GameLogicState) -> Result<String, Vec<OutputLine>> {
+    if game.network.host(arg).is_some() {
+        return Ok(arg.to_string());
+    }
+    let Ok(index) = arg.parse::<usize>() else {
+        return Ok(arg.to_string());
+    };
+    game.network
+        .numbered_host(index)
+        .map(str::to_string)
+        .ok_or_else(|| {
+            vec![OutputLine::Error(tr!(
+                game.locale,
+                "hosts.invalid_index",
+                index = index,
+                count = game.network.discovered.len()
+            ))]
+        })
+}
+
+fn ssh(host: &str, password: Option<&str>, game: &mut GameLogicState) -> Vec<OutputLine> {
+    if game.network.host(host).is_none() {
+        return vec![OutputLine::error(game.locale.get("ssh.not_found"))];
+    }
+    if !game.network.is_neighbor(&game.current_host, host) {
+        return vec![OutputLine::Error(tr!(
+            game.locale,
+            "ssh.unreachable",
+            host = host,
+            from = game.current_host
+        ))];
+    }
+    let lost = wait_for(host, game);
+    after_loss(lost, log_in(host, password, game))
+}
+
+/// The rest of `ssh`, once the connection has reached `host`.
+fn log_in(host: &str, password: Option<&str>, game: &mut GameLogicState) -> Vec<OutputLine> {
+    if let Some(remaining) = game.lockouts.remaining(host) {
+        return vec![OutputLine::Error(tr!(
+            game.locale,
+            "ssh.locked",
+            host = host,
+            seconds = remaining.ceil()
+        ))];
+    }
+    let credentials = game
+        .network
+        .host(host)
+        .and_then(|node| node.credentials.clone());
+    match (&credentials, password) {
+        (Some(_), None) => return vec![OutputLine::info(game.locale.get("ssh.auth_required"))],
+        (Some(expected), Some(given)) if expected != given => {
+            game.failures.push(Failure::AuthFailed);
+            if game.lockouts.fail(host) {
+                return vec![OutputLine::Error(tr!(
+                    game.locale,
+                    "ssh.too_many_attempts",
+                    seconds = LOCKOUT_SECONDS
+                ))];
+            }
+            return vec![OutputLine::error(game.locale.get("ssh.denied"))];
+        }
+        _ => {}
+    }
+    game.lockouts.succeed(host);
+    let from = std::mem::replace(&mut game.current_host, host.to_string());
+    game.hops.push(from);
+    game.network.enter(host);
+    game.cwd = "/".to_string();
+    game.effects.push(Effect::Connected(host.to_string()));
+    let mut lines = vec![OutputLine::Info(tr!(
+        game.locale,
+        "ssh.connected",
+        host = host
+    ))];
+    lines.extend(motd(host, game).map_or_else(Vec::new, |motd| OutputLine::info_lines(&motd)));
+    lines
+}
+
+/// What `host` greets a login with: its own message of the day, or else
+/// the contents of its `/etc/motd`.
+fn motd(host: &str, game: &GameLogicState) -> Option<String> {
+    game.network
+        .host(host)
+        .and_then(|node| node.motd.clone())
+        .or_else(|| {
+            let file = game.filesystem.read(host, "/etc/motd").ok()?;
+            Some(file.shown())
+        })
+}
+
+fn ls(path: &str, game: &GameLogicState) -> Vec<OutputLine> {
+    let target = FileSystem::resolve(&game.cwd, path);
+    match game.filesystem.list(&game.current_host, &target) {
+        Ok(names) if names.is_empty() => Vec::new(),
+        Ok(names) => vec![OutputLine::Info(names.join("  "))],
+        Err(err) => vec![OutputLine::Error(tr!(
+            game.locale,
+            "ls.error",
+            path = path,
+            error = err.message(&game.locale)
+        ))],
+    }
+}
+
+fn cd(path: &str, game: &mut GameLogicState) -> Vec<OutputLine> {
+    let target = FileSystem::resolve(&game.cwd, path);
+    let err = match game.filesystem.is_dir(&game.current_host, &target) {
+        Ok(true) => {
+            game.cwd = target;
+            return Vec::new();
+        }
+        Ok(false) => FsError::NotADirectory,
+        Err(err) => err,
+    };
+    vec![OutputLine::Error(tr!(
+        game.locale,
+        "cd.error",
+        path = path,
+        error = err.message(&game.locale)
+    ))]
+}
+
+fn cat(path: &str, game: &mut GameLogicState) -> Vec<OutputLine> {
+    let target = FileSystem::resolve(&game.cwd, path);
+    match game.filesystem.read(&game.current_host, &target) {
+        Ok(file) => {
+            let mut lines = OutputLine::info_lines(&file.shown());
+            // An encrypted secret is only worth keeping once it's decrypted
+            let secret = inventory::secret_file(&game.current_host, &target);
+            if let Some(item) = secret.filter(|_| !file.encrypted) {
+                lines.extend(collect(item, game));
+            }
+            lines
+        }
+        Err(err) => vec![OutputLine::Error(tr!(
+            game.locale,
+            "cat.error",
+            path = path,
+            error = err.message(&game.locale)
+        ))],
+    }
+}
+
+/// Handles `decrypt`: shows the plain contents of an encrypted file if
+/// `key` is right, and keeps whatever it holds.
+fn decrypt(path: &str, key: &str, game: &mut GameLogicState) -> Vec<OutputLine> {
+    let target = FileSystem::resolve(&game.cwd, path);
+    let file = match game.filesystem.read(&game.current_host, &target) {
+        Ok(file) => file,
+        Err(err) => {
+            return vec![OutputLine::Error(tr!(
+                game.locale,
+                "decrypt.error",
+                path = path,
+                error = err.message(&game.locale)
+            ))]
+        }
+    };
+    if !file.encrypted {
+        return vec![OutputLine::Error(tr!(
+            game.locale,
+            "decrypt.not_encrypted",
+            path = path
+        ))];
+    }
+    let Some(contents) = file.decrypt(key) else {
+        return vec![OutputLine::Error(tr!(
+            game.locale,
+            "decrypt.failed",
+            path = path
+        ))];
+    };
+    let mut lines = OutputLine::info_lines(contents);
+    if let Some(item) = inventory::secret_file(&game.current_host, &target) {
+        lines.extend(collect(item, game));
+    }
+    lines
+}
+
+/// The end of a `wget data` once it is all down: the trace it sets off and
+/// the records it brings.
+pub fn finish_download(game: &mut GameLogicState) -> Vec<OutputLine> {
+    game.download = None;
+    let line = if game.start_trace() {
+        game.locale.get("wget.traced")
+    } else {
+        game.locale.get("wget.untraced")
+    };
+    let mut lines = vec![OutputLine::info(line)];
+    lines.extend(collect(inventory::customer_records(), game));
+    lines
+}
+
+/// Adds `item` to the inventory, returning the lines that announce it, or
+/// nothing if it was already held.
+fn collect(item: LootItem, game: &mut GameLogicState) -> Vec<OutputLine> {
+    let line = tr!(
+        game.locale,
+        "loot.added",
+        name = item.name,
+        size = inventory::format_size(item.size_kb)
+    );
+    let was_complete = game.inventory.mission_complete();
+    let name = item.name.clone();
+    if !game.inventory.add(item) {
+        return Vec::new();
+    }
+    game.effects.push(Effect::Looted(name));
+    let mut lines = vec![OutputLine::Info(line)];
+    if !was_complete && game.inventory.mission_complete() {
+        lines.push(OutputLine::info(game.locale.get("loot.mission_complete")));
+    }
+    lines
+}
+
+fn loot(game: &GameLogicState) -> Vec<OutputLine> {
+    if game.inventory.items.is_empty() {
+        return vec![OutputLine::info(game.locale.get("loot.empty"))];
+    }
+    let mut lines = vec![OutputLine::info(game.locale.get("loot.header"))];
+    for item in &game.inventory.items {
+        lines.push(OutputLine::Info(format!(
+            "  {:<18} {:>9}  {}",
+            item.name,
+            inventory::format_size(item.size_kb),
+            item.description
+        )));
+    }
+    lines.push(OutputLine::Info(tr!(
+        game.locale,
+        "loot.total",
+        size = inventory::format_size(game.inventory.total_kb())
+    )));
+    lines
+}
+
+/// Queues the commands of `name`, or of `name.sh`, to run after this one.
+fn run_script(name: &str, game: &mut GameLogicState) -> OutputLine {
+    let Some(script) = Script::load(name).or_else(|| Script::load(&format!("{}.sh", name))) else {
+        return OutputLine::Error(tr!(game.locale, "run.not_found", name = name));
+    };
+    let line = OutputLine::Info(tr!(
+        game.locale,
+        "run.started",
+        name = script.name,
+        count = script.lines.len()
+    ));
+    game.effects.push(Effect::RunScript(script));
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::aliases::MAX_ALIAS_DEPTH;
+    use crate::markup;
+
+    const COMMANDS: &[&str] = &["cat", "cd", "exploit", "help", "ls", "nmap", "ssh", "wget"];
+
+    #[test]
+    fn suggests_through_a_transposition() {
+        assert_eq!(closest_command("nmpa", COMMANDS), Some("nmap"));
+        assert_eq!(closest_command("ssh", &["hss", "help"]), Some("hss"));
+    }
+
+    #[test]
+    fn suggests_after_an_insertion_or_deletion() {
+        assert_eq!(closest_command("nmapp", COMMANDS), Some("nmap"));
+        assert_eq!(closest_command("wgt", COMMANDS), Some("wget"));
+        assert_eq!(closest_command("exploitt", COMMANDS), Some("exploit"));
+    }
+
+    #[test]
+    fn no_suggestion_when_nothing_is_close() {
+        assert_eq!(closest_command("frobnicate", COMMANDS), None);
+        assert_eq!(closest_command("xy", COMMANDS), None);
+        assert_eq!(closest_command("nmap", &[]), None);
+    }
+
+    #[test]
+    fn bad_arguments_are_not_treated_as_typos() {
+        let mut game = GameLogicState::default();
+        assert_eq!(shown("nmap", &mut game), "> usage: nmap <host>");
+        assert_eq!(
+            shown("nmpa hideout", &mut game),
+            "> Unknown command: nmpa hideout. Did you mean 'nmap'?"
+        );
+    }
+
+    fn run(cmd: &str, state: &mut GameLogicState) -> CommandOutcome {
+        evaluate_command(&CommandRegistry::default(), cmd, state)
+    }
+
+    /// The response to `cmd` as the terminal shows it, without colours.
+    fn shown(cmd: &str, game: &mut GameLogicState) -> String {
+        let outcome = run(cmd, game);
+        markup::strip(&outcome.render(&game.locale))
+    }
+
+    /// Easy never traces, which keeps failures from spilling into a test.
+    fn easy() -> GameLogicState {
+        GameLogicState {
+            difficulty: Difficulty::Easy.into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn help_lists_commands_and_unknown_verbs_are_flagged() {
+        let mut game = GameLogicState::default();
+        let help = run("help", &mut game);
+        assert!(!help.failed);
+        assert!(help.render(&game.locale).contains("ssh <host> [password]"));
+        assert!(help.lines.contains(&OutputLine::Info(format!(
+            "  {:<22} Copy the input line",
+            "Ctrl+Shift+C"
+        ))));
+        let unknown = run("frobnicate", &mut game);
+        assert!(unknown.failed);
+        assert!(matches!(
+            &unknown.lines[..],
+            [OutputLine::Error(message)] if message.starts_with("Unknown command: frobnicate")
+        ));
+    }
+
+    #[test]
+    fn usage_and_runtime_errors_count_as_failures() {
+        let mut game = GameLogicState::default();
+        assert!(!run("pwd", &mut game).failed);
+        assert!(run("frobnicate", &mut game).failed);
+        assert!(run("ssh", &mut game).failed);
+        assert!(run("ssh nowhere.net", &mut game).failed);
+        // Even when a filter hides the error
+        let piped = run("ssh | grep nothing", &mut game);
+        assert!(piped.lines.is_empty() && piped.failed);
+        assert!(run("cat \"unclosed", &mut game).failed);
+    }
+
+    #[test]
+    fn nmap_reveals_ports_over_time() {
+        let mut game = GameLogicState::default();
+        assert_eq!(
+            shown("nmap nowhere.net", &mut game),
+            "> nmap: host not found"
+        );
+        run("nmap neotechlabs.com", &mut game);
+        assert_eq!(
+            shown("nmap mail.neotechlabs.com", &mut game),
+            "> nmap: scan of neotechlabs.com already in progress"
+        );
+        assert!(!game.network.discovered.contains_key("mail.neotechlabs.com"));
+        let job = game.scan.as_mut().expect("scan queued");
+        let (lines, done) = job.advance(Duration::from_secs_f32(0.45), &game.locale);
+        assert_eq!(
+            lines,
+            vec![OutputLine::Port {
+                num: 22,
+                service: "SSH".to_string(),
+                vulnerable: false
+            }]
+        );
+        assert!(!done);
+        let (lines, done) = job.advance(Duration::from_secs(10), &game.locale);
+        assert!(done);
+        assert_eq!(
+            lines.last().unwrap(),
+            &OutputLine::info("{cyan}Scan of neotechlabs.com complete: 3 open, 1 vulnerable{/}")
+        );
+        assert!(lines.contains(&OutputLine::Port {
+            num: 80,
+            service: "HTTP".to_string(),
+            vulnerable: true
+        }));
+        assert!(game.network.discovered["neotechlabs.com"] == HostStatus::Scanned);
+    }
+
+    #[test]
+    fn ssh_follows_the_network_and_checks_passwords() {
+        let mut game = GameLogicState::default();
+        assert_eq!(
+            shown("ssh vault.neotechlabs.com", &mut game),
+            "> ssh: vault.neotechlabs.com is not reachable from hideout"
+        );
+        let outcome = run("ssh neotechlabs.com", &mut game);
+        assert_eq!(
+            outcome.lines,
+            vec![
+                OutputLine::info("Connected to neotechlabs.com"),
+                OutputLine::info("NeoTech Labs. Authorized use only."),
+            ]
+        );
+        assert_eq!(
+            outcome.effects,
+            vec![
+                Effect::Latency(0.6),
+                Effect::Connected("neotechlabs.com".to_string())
+            ]
+        );
+        assert_eq!(
+            shown("ssh mail.neotechlabs.com", &mut game),
+            "> Connected—auth required (ssh <host> <password>)"
+        );
+        assert_eq!(
+            shown("ssh mail.neotechlabs.com guess", &mut game),
+            "> ssh: permission denied"
+        );
+        assert_eq!(game.failures, vec![Failure::AuthFailed]);
+        run("ssh mail.neotechlabs.com hunter2", &mut game);
+        assert_eq!(game.current_host, "mail.neotechlabs.com");
+        assert_eq!(game.prompt(), "runner@mail:/> ");
+    }
+
+    #[test]
+    fn hosts_are_numbered_in_the_order_found() {
+        let mut game = GameLogicState::default();
+        run("ssh neotechlabs.com", &mut game);
+        let listing = shown("hosts", &mut game);
+        assert!(listing.starts_with("> Discovered hosts:\n> 1) hideout [breached]\n> 2) "));
+        assert!(listing.contains("neotechlabs.com [breached] <- connected"));
+        // Numbers given out before stay the same as more hosts turn up
+        let number = |game: &GameLogicState, name: &str| {
+            game.network
+                .numbered()
+                .find(|(_, host, _)| *host == name)
+                .map(|(index, _, _)| index)
+        };
+        let neotech = number(&game, "neotechlabs.com");
+        run("ssh dev.neotechlabs.com", &mut game);
+        assert_eq!(number(&game, "neotechlabs.com"), neotech);
+        run(&format!("connect {}", neotech.unwrap()), &mut game);
+        assert_eq!(game.current_host, "neotechlabs.com");
+        assert_eq!(
+            shown("ssh 99", &mut game),
+            format!(
+                "> hosts: no host numbered 99; there are {}",
+                game.network.discovered.len()
+            )
+        );
+    }
+
+    #[test]
+    fn logging_in_shows_the_message_of_the_day() {
+        let mut game = GameLogicState::default();
+        run("ssh neotechlabs.com", &mut game);
+        assert_eq!(
+            run("ssh dev.neotechlabs.com", &mut game).lines[1..],
+            [OutputLine::info(
+                "{yellow}Reminder: no personal notes on shared boxes. Yes, you.{/}"
+            )]
+        );
+        let lines = run("ssh vault.neotechlabs.com n30t3ch", &mut game).lines;
+        assert_eq!(lines.len(), 3);
+        assert_eq!(
+            lines[1],
+            OutputLine::info("{red}*** NEOTECH SECURE VAULT ***{/}")
+        );
+    }
+
+    #[test]
+    fn lossy_lines_delay_remote_commands_and_retry() {
+        let lossy = |seed| GameLogicState {
+            conditions: NetworkConditions {
+                base_latency_ms: 100,
+                jitter_ms: 50,
+                loss_chance: 1.0,
+            },
+            rng: GameRng::new(seed),
+            ..Default::default()
+        };
+        let mut game = lossy(3);
+        let outcome = run("ssh neotechlabs.com", &mut game);
+        assert_eq!(
+            outcome.lines,
+            vec![
+                OutputLine::info("{yellow}packet lost — retrying...{/}"),
+                OutputLine::info("Connected to neotechlabs.com"),
+                OutputLine::info("NeoTech Labs. Authorized use only."),
+            ]
+        );
+        // Two trips, each the host's 0.6s plus 100-150ms on the line
+        let [Effect::Latency(total), Effect::Connected(_)] = outcome.effects[..] else {
+            panic!("{:?}", outcome.effects);
+        };
+        assert!((1.4..=1.5).contains(&total), "{}", total);
+        assert_eq!(
+            run("ssh neotechlabs.com", &mut lossy(3)).effects,
+            outcome.effects
+        );
+        assert!(run("help", &mut game).effects.is_empty());
+        let reply = shown("ping neotechlabs.com", &mut game);
+        assert!(reply.ends_with(" ms"), "{}", reply);
+        assert!(
+            reply.contains("reply from neotechlabs.com: time=7"),
+            "{}",
+            reply
+        );
+        assert_eq!(
+            shown("ping nowhere", &mut game),
+            "> ping: nowhere: host not found"
+        );
+    }
+
+    #[test]
+    fn logout_goes_back_along_the_ssh_hops() {
+        let registry = CommandRegistry::default();
+        let mut game = GameLogicState::default();
+        run("ssh neotechlabs.com", &mut game);
+        run("ssh mail.neotechlabs.com hunter2", &mut game);
+        assert!(!registry.is_destructive("exit", &game));
+        assert!(registry.is_destructive("quit", &game));
+        let outcome = run("logout", &mut game);
+        assert_eq!(
+            outcome.lines,
+            vec![OutputLine::info(
+                "Connection to mail.neotechlabs.com closed."
+            )]
+        );
+        assert!(outcome.effects.is_empty());
+        assert_eq!(game.prompt(), "runner@neotechlabs:/> ");
+        assert!(run("disconnect", &mut game).effects.is_empty());
+        assert_eq!(game.current_host, HOME_HOST);
+        // Only leaving the base shell closes the terminal
+        assert!(registry.is_destructive("exit", &game));
+        assert_eq!(run("exit", &mut game).effects, vec![Effect::Exit]);
+    }
+
+    #[test]
+    fn ssh_locks_a_host_after_repeated_wrong_passwords() {
+        let mut game = GameLogicState::default();
+        run("ssh neotechlabs.com", &mut game);
+        let host = "mail.neotechlabs.com";
+        run(&format!("ssh {} guess", host), &mut game);
+        run(&format!("ssh {} guess", host), &mut game);
+        // A correct password in between starts the count again
+        run(&format!("ssh {} hunter2", host), &mut game);
+        run("ssh neotechlabs.com", &mut game);
+        run(&format!("ssh {} guess", host), &mut game);
+        run(&format!("ssh {} guess", host), &mut game);
+        assert_eq!(
+            shown(&format!("ssh {} guess", host), &mut game),
+            "> ssh: too many attempts — host locked for 60s"
+        );
+        game.lockouts.tick(Duration::from_secs(15));
+        assert_eq!(
+            shown(&format!("ssh {} hunter2", host), &mut game),
+            "> ssh: mail.neotechlabs.com is locked, try again in 45s"
+        );
+        game.lockouts.tick(Duration::from_secs(45));
+        assert_eq!(
+            shown(&format!("ssh {} hunter2", host), &mut game),
+            "> Connected to mail.neotechlabs.com"
+        );
+    }
+
+    #[test]
+    fn exploit_needs_a_vulnerable_reachable_host() {
+        let mut game = easy();
+        assert!(shown("exploit", &mut game).contains("nothing vulnerable on hideout"));
+        assert_eq!(game.failures, vec![Failure::ExploitFailed]);
+        game.cooldowns.tick(Duration::from_secs(3));
+        assert!(shown("exploit dev.neotechlabs.com", &mut game).contains("not reachable"));
+        let challenge = shown("exploit neotechlabs.com", &mut game);
+        assert!(challenge.contains("in reverse within 20s"), "{}", challenge);
+        assert_eq!(game.prompt(), "breach> ");
+        let outcome = run(&answer(&game), &mut game);
+        assert_eq!(
+            markup::strip(&outcome.render(&game.locale)),
+            "> Firewall breached on neotechlabs.com"
+        );
+        assert_eq!(
+            outcome.effects,
+            [Effect::Breached("neotechlabs.com".to_string())]
+        );
+        assert!(game.network.discovered["neotechlabs.com"] == HostStatus::Breached);
+        assert!(game.puzzle.is_none());
+    }
+
+    /// The right answer to the open `exploit` challenge.
+    fn answer(game: &GameLogicState) -> String {
+        let puzzle = game.puzzle.as_ref().expect("a challenge is open");
+        puzzle
+            .sequence
+            .iter()
+            .rev()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+
+    #[test]
+    fn wrong_answers_fail_the_breach_and_start_a_trace() {
+        let mut game = GameLogicState::default();
+        run("exploit neotechlabs.com", &mut game);
+        // The answer isn't run, even when it names a command
+        let response = shown("exit", &mut game);
+        assert!(
+            response.starts_with("> breach failed: wrong sequence for neotechlabs.com"),
+            "{}",
+            response
+        );
+        assert!(game.trace.active);
+        assert!(game.network.discovered["neotechlabs.com"] < HostStatus::Breached);
+        assert_eq!(shown("pwd", &mut game), "> /");
+    }
+
+    #[test]
+    fn exploit_recharges_between_attempts() {
+        let mut game = easy();
+        run("exploit neotechlabs.com", &mut game);
+        run("00", &mut game);
+        assert_eq!(
+            shown("exploit neotechlabs.com", &mut game),
+            "> exploit: recharging — 3s remaining"
+        );
+        game.cooldowns.tick(Duration::from_millis(2500));
+        assert_eq!(
+            shown("exploit neotechlabs.com", &mut game),
+            "> exploit: recharging — 1s remaining"
+        );
+        game.cooldowns.tick(Duration::from_millis(500));
+        assert!(shown("exploit neotechlabs.com", &mut game).contains("fights back"));
+    }
+
+    /// Runs `wget data` and lets the download finish. Returns what it says
+    /// then, as `shown` would, and the effects it asked for.
+    fn downloaded(game: &mut GameLogicState) -> (String, Vec<Effect>) {
+        run("wget data", game);
+        let job = game.download.as_mut().expect("the download should start");
+        assert!(job.advance(Duration::from_secs_f32(DOWNLOAD_SECONDS)));
+        let response: Vec<String> = finish_download(game)
+            .iter()
+            .map(|line| markup::strip(&line.render(&game.locale)))
+            .collect();
+        (response.join("\n"), std::mem::take(&mut game.effects))
+    }
+
+    #[test]
+    fn downloads_fill_their_progress_bar() {
+        let mut game = GameLogicState::default();
+        run("ssh neotechlabs.com", &mut game);
+        assert_eq!(
+            shown("wget data", &mut game),
+            "> Downloading data from neotechlabs.com..."
+        );
+        assert!(game.download.is_some() && !game.trace.active);
+        game.cooldowns.tick(Duration::from_secs(5));
+        assert_eq!(
+            shown("wget data", &mut game),
+            "> wget: a download is already in progress"
+        );
+        let job = game.download.as_mut().unwrap();
+        assert_eq!(job.progress_bar(), "[----------] 0% 0MB/500MB");
+        assert!(!job.advance(Duration::from_secs_f32(DOWNLOAD_SECONDS * 0.42)));
+        assert_eq!(job.progress_bar(), "[####------] 42% 210MB/500MB");
+        assert!(job.advance(Duration::from_secs(60)));
+        assert_eq!(job.progress_bar(), "[##########] 100% 500MB/500MB");
+    }
+
+    #[test]
+    fn wget_starts_a_trace_that_cloak_evades() {
+        let mut game = GameLogicState::default();
+        assert_eq!(shown("cloak", &mut game), "> No active trace to evade");
+        assert_eq!(
+            shown("wget data", &mut game),
+            "> wget: command not available here — ssh into a host first"
+        );
+        assert!(!game.trace.active);
+        run("ssh neotechlabs.com", &mut game);
+        assert_eq!(
+            shown("wget secrets", &mut game),
+            "> wget: secrets: no such file"
+        );
+        let (_, effects) = downloaded(&mut game);
+        assert!(game.trace.active);
+        assert!(effects.contains(&Effect::TraceStarted(30.0)));
+        // A second download doesn't start the trace over
+        game.cooldowns.tick(Duration::from_secs(5));
+        assert!(downloaded(&mut game).1.is_empty());
+        let outcome = run("cloak", &mut game);
+        assert_eq!(
+            markup::strip(&outcome.render(&game.locale)),
+            "> Trace evaded"
+        );
+        assert!(matches!(outcome.effects[..], [Effect::TraceEvaded(left)] if left > 29.0));
+        assert!(!game.trace.active);
+
+        let mut game = easy();
+        run("ssh neotechlabs.com", &mut game);
+        let (response, _) = downloaded(&mut game);
+        assert_eq!(
+            response.lines().next(),
+            Some("> 500MB downloaded—no trace detected")
+        );
+        assert!(!game.trace.active);
+    }
+
+    #[test]
+    fn secret_files_and_downloads_become_loot() {
+        let mut game = easy();
+        assert_eq!(shown("loot", &mut game), "> No loot collected yet");
+        game.current_host = "dev.neotechlabs.com".to_string();
+        let response = shown("cat /home/dev/todo.txt", &mut game);
+        assert!(response.ends_with("> Added dev-todo to loot (2 KB)"));
+        // Reading it again doesn't collect a second copy
+        assert!(!shown("cat /home/dev/todo.txt", &mut game).contains("Added"));
+        let (response, _) = downloaded(&mut game);
+        assert!(response.ends_with("Mission complete."));
+        assert!(game.inventory.mission_complete());
+        let listing = shown("loot", &mut game);
+        assert!(listing.contains("customer-records"));
+        assert!(listing.ends_with("> Total: 500.0 MB"));
+    }
+
+    #[test]
+    fn handle_renames_the_runner_everywhere() {
+        let mut game = GameLogicState::default();
+        assert_eq!(shown("handle", &mut game), "> Handle: runner");
+        assert_eq!(
+            shown("handle zero_cool", &mut game),
+            "> You go by zero_cool now"
+        );
+        assert_eq!(shown("whoami", &mut game), "> zero_cool");
+        assert_eq!(game.prompt(), "zero_cool@hideout:/> ");
+        assert_eq!(shown("echo $USER", &mut game), "> zero_cool");
+        for name in ["acid-burn", "\"\"", "a_very_long_handle_indeed"] {
+            assert!(
+                shown(&format!("handle {}", name), &mut game).contains("won't do"),
+                "{}",
+                name
+            );
+        }
+        assert_eq!(game.handle, "zero_cool");
+    }
+
+    #[test]
+    fn filesystem_commands_browse_the_current_host() {
+        let mut game = GameLogicState::default();
+        assert_eq!(shown("ls", &mut game), "> home/");
+        assert_eq!(shown("cd home/runner", &mut game), "");
+        assert_eq!(shown("pwd", &mut game), "> /home/runner");
+        assert_eq!(shown("ls", &mut game), "> job.txt");
+        assert!(shown("cat job.txt", &mut game).starts_with("> Client wants"));
+        assert_eq!(
+            shown("cat nope.txt", &mut game),
+            "> cat: nope.txt: no such file or directory"
+        );
+        assert_eq!(
+            shown("cd job.txt", &mut game),
+            "> cd: job.txt: not a directory"
+        );
+        run("cd ../../../..", &mut game);
+        assert_eq!(game.cwd, "/");
+        assert_eq!(shown("cat home", &mut game), "> cat: home: is a directory");
+    }
+
+    #[test]
+    fn aliases_expand_list_and_stop_at_cycles() {
+        let mut game = GameLogicState::default();
+        assert_eq!(shown("alias", &mut game), "> No aliases defined");
+        run("alias where=\"pwd\"", &mut game);
+        assert_eq!(shown("alias", &mut game), "> alias where='pwd'");
+        let outcome = run("where", &mut game);
+        assert!(!outcome.failed);
+        assert_eq!(outcome.lines, vec![OutputLine::info("/")]);
+        run("alias a=b", &mut game);
+        run("alias b=a", &mut game);
+        assert!(shown("a", &mut game).contains("expansion is too deep"));
+        // A chain exactly as deep as allowed still runs
+        for depth in 1..MAX_ALIAS_DEPTH {
+            run(&format!("alias step{}=step{}", depth, depth + 1), &mut game);
+        }
+        run(&format!("alias step{}=pwd", MAX_ALIAS_DEPTH), &mut game);
+        assert_eq!(run("step1", &mut game).lines, vec![OutputLine::info("/")]);
+        run("alias step0=step1", &mut game);
+        assert!(shown("step0", &mut game).contains("expansion is too deep"));
+        run("unalias where", &mut game);
+        assert!(run("where", &mut game).failed);
+        assert_eq!(
+            shown("unalias where", &mut game),
+            "> unalias: where: not found"
+        );
+    }
+
+    #[test]
+    fn variables_expand_before_dispatch() {
+        let mut game = GameLogicState::default();
+        assert_eq!(shown("echo", &mut game), ">");
+        assert_eq!(shown("echo \"hello $USER\"", &mut game), "> hello runner");
+        assert_eq!(shown("echo $HOST", &mut game), format!("> {}", HOME_HOST));
+        assert_eq!(shown("echo [$NOPE]", &mut game), "> []");
+        // Single quotes and backslashes keep a `$` as it is
+        assert_eq!(shown("echo '$USER' \\$USER", &mut game), "> $USER $USER");
+        assert_eq!(shown("echo \"it's $USER\"", &mut game), "> it's runner");
+        // Definitions read their variables when they're used, not before
+        run("alias here='echo $HOST'", &mut game);
+        run("export WHERE=$HOST", &mut game);
+        run("export SELF=$SELF", &mut game);
+        game.current_host = "neotechlabs.com".to_string();
+        assert_eq!(shown("here", &mut game), "> neotechlabs.com");
+        assert_eq!(shown("echo $WHERE", &mut game), "> neotechlabs.com");
+        assert_eq!(shown("echo [$SELF]", &mut game), "> [$SELF]");
+        game.current_host = HOME_HOST.to_string();
+        run("export TARGET=neotechlabs.com", &mut game);
+        assert!(shown("nmap $TARGET", &mut game).contains("Scanning neotechlabs.com"));
+        assert!(shown("export", &mut game).contains("> export TARGET='neotechlabs.com'"));
+        assert_eq!(
+            shown("export 1x=y", &mut game),
+            "> export: '1x': not a valid variable name"
+        );
+    }
+
+    #[test]
+    fn run_queues_a_script_from_the_assets() {
+        let mut game = GameLogicState::default();
+        let outcome = run("run recon", &mut game);
+        assert!(outcome
+            .render(&game.locale)
+            .contains("Running recon.sh (2 commands)"));
+        let [Effect::RunScript(script)] = &outcome.effects[..] else {
+            panic!("{:?}", outcome.effects);
+        };
+        assert_eq!(script.lines[0].number, 4);
+        assert_eq!(
+            shown("run ../locales/en.ron", &mut game),
+            "> run: ../locales/en.ron: no such script"
+        );
+    }
+
+    #[test]
+    fn difficulty_is_locked_once_the_mission_starts() {
+        let mut game = GameLogicState::default();
+        assert_eq!(shown("difficulty", &mut game), "> Difficulty: normal");
+        assert_eq!(
+            shown("difficulty hard", &mut game),
+            "> Difficulty set to hard"
+        );
+        assert_eq!(
+            shown("difficulty brutal", &mut game),
+            "> difficulty: unknown level 'brutal'"
+        );
+        run("nmap neotechlabs.com", &mut game);
+        assert_eq!(
+            shown("difficulty easy", &mut game),
+            "> difficulty: can't change once the mission has started"
+        );
+        assert_eq!(game.difficulty.level, Difficulty::Hard);
+    }
+
+    #[test]
+    fn clock_save_and_exit() {
+        let mut game = GameLogicState::default();
+        assert_eq!(shown("clock", &mut game), "> 23:47:00");
+        assert_eq!(run("save", &mut game).effects, vec![Effect::SaveSession]);
+        assert_eq!(run("exit", &mut game).effects, vec![Effect::Exit]);
+        assert!(run("clock", &mut game).effects.is_empty());
+    }
+
+    #[test]
+    fn decrypt_needs_the_key_from_the_dev_box() {
+        let mut game = GameLogicState {
+            current_host: "dev.neotechlabs.com".to_string(),
+            ..default()
+        };
+        assert_eq!(
+            shown("cat /home/dev/.keyring", &mut game),
+            "> vault:/data/archive.cred  bl4ckic3"
+        );
+        game.current_host = "vault.neotechlabs.com".to_string();
+        game.cwd = "/data".to_string();
+        assert!(!shown("cat archive.cred", &mut game).contains("c0ldst0r4ge"));
+        assert!(!game.inventory.has("archive-creds"));
+        assert_eq!(
+            shown("decrypt archive.cred hunter2", &mut game),
+            "> decrypt: archive.cred: decryption failed"
+        );
+        assert_eq!(
+            shown("decrypt README bl4ckic3", &mut game),
+            "> decrypt: README: not encrypted"
+        );
+        let decrypted = shown("decrypt archive.cred bl4ckic3", &mut game);
+        assert!(decrypted.starts_with(
+            "> host: archive.neotechlabs.com\n> password: c0ldst0r4ge\n> Added archive-creds"
+        ));
+        assert!(game.inventory.has("archive-creds"));
+    }
+
+    #[test]
+    fn settings_change_the_output_speed() {
+        let mut game = GameLogicState::default();
+        assert_eq!(
+            run("settings", &mut game).effects,
+            vec![
+                Effect::OutputSpeed(None),
+                Effect::DisplayMode(None),
+                Effect::PrefixMatching(None)
+            ]
+        );
+        assert_eq!(
+            run("settings speed instant", &mut game).effects,
+            vec![Effect::OutputSpeed(Some(OutputSpeed::Instant))]
+        );
+        assert_eq!(
+            shown("settings speed warp", &mut game),
+            "> settings: expected characters a second or 'instant', not 'warp'"
+        );
+        assert_eq!(
+            shown("settings volume 3", &mut game),
+            "> settings: unknown setting 'volume'; try 'speed', 'window' or 'prefix'"
+        );
+        assert_eq!(
+            run("settings window borderless", &mut game).effects,
+            vec![Effect::DisplayMode(Some(DisplayMode::Borderless))]
+        );
+        assert_eq!(
+            shown("settings window maximised", &mut game),
+            "> settings: expected windowed, borderless or fullscreen, not 'maximised'"
+        );
+    }
+
+    #[test]
+    fn a_unique_prefix_runs_its_command_once_turned_on() {
+        let mut game = GameLogicState::default();
+        assert_eq!(
+            shown("pw", &mut game),
+            "> Unknown command: pw. Did you mean 'pwd'?"
+        );
+        game.prefix_matching = true;
+        assert_eq!(shown("pw", &mut game), "> /");
+        assert!(!run("nm neotechlabs.com", &mut game).failed);
+        assert!(game.scan.is_some());
+        // Still confirmed when it's short for a destructive command
+        assert!(CommandRegistry::default().is_destructive("qu", &game));
+    }
+
+    #[test]
+    fn an_ambiguous_prefix_lists_what_it_could_be() {
+        let mut game = GameLogicState {
+            prefix_matching: true,
+            ..default()
+        };
+        let outcome = run("cl", &mut game);
+        assert!(outcome.failed && outcome.effects.is_empty());
+        assert_eq!(
+            markup::strip(&outcome.render(&game.locale)),
+            "> cl: ambiguous command; could be clear, clip, cloak, clock"
+        );
+    }
+
+    #[test]
+    fn an_exact_name_wins_over_longer_commands_it_starts() {
+        let mut registry = CommandRegistry::default();
+        registry.register("lsblk", "", "List block devices", |_, _| {
+            vec![OutputLine::info("sda")]
+        });
+        let mut game = GameLogicState {
+            prefix_matching: true,
+            ..default()
+        };
+        let shown = |cmd: &str, game: &mut GameLogicState| {
+            let outcome = registry.evaluate_command(cmd, game);
+            markup::strip(&outcome.render(&game.locale))
+        };
+        assert_eq!(shown("ls", &mut game), "> home/");
+        assert_eq!(shown("lsb", &mut game), "> sda");
+    }
+
+    #[test]
+    fn quoted_arguments_stay_whole() {
+        let mut game = GameLogicState::default();
+        assert_eq!(
+            shown("echo \"two  spaces\" 'and more'", &mut game),
+            "> two  spaces and more"
+        );
+        assert_eq!(
+            shown("cat \"no such file", &mut game),
+            "> parse error: unmatched \""
+        );
+        assert_eq!(
+            shown("cat \"no such file.txt\"", &mut game),
+            shown("cat no\\ such\\ file.txt", &mut game)
+        );
+    }
+
+    #[test]
+    fn pipes_filter_command_output() {
+        let mut game = GameLogicState::default();
+        assert_eq!(
+            shown("help | grep nmap", &mut game),
+            format!(">   {:<22} {}", "nmap <host>", "Scan a host for open ports")
+        );
+        assert_eq!(shown("help | head -n 3 | wc -l", &mut game), "> 3");
+        assert_eq!(shown("cd home | wc -l", &mut game), "> 0");
+        assert!(shown("grep x", &mut game).contains("nothing to read"));
+        for line in ["| grep x", "help |", "help | | wc -l"] {
+            let outcome = run(line, &mut game);
+            assert!(
+                matches!(&outcome.lines[..], [OutputLine::Error(message)] if message.starts_with("usage: <command> |")),
+                "{}",
+                line
+            );
+            assert!(outcome.failed);
+        }
+        assert_eq!(
+            shown("help | cat", &mut game),
+            "> pipe: cat can't read piped input; try grep, head, wc or clip"
+        );
+        assert_eq!(shown("help | clip -a", &mut game), "> usage: clip");
+        let outcome = run("clip", &mut game);
+        assert!(outcome.lines.is_empty());
+        assert_eq!(outcome.effects, vec![Effect::CopyScrollback]);
+        assert_eq!(
+            shown("help | head -n many", &mut game),
+            "> head: invalid line count 'many'"
+        );
+    }
+
+    #[test]
+    fn quoted_pipes_and_ampersands_are_just_text() {
+        let mut game = GameLogicState::default();
+        assert_eq!(shown(r#"echo "a|b" | grep a"#, &mut game), "> a|b");
+        assert_eq!(shown("echo 'a|b' | grep x | wc -l", &mut game), "> 0");
+        assert_eq!(shown(r"echo a\|b", &mut game), "> a|b");
+        assert_eq!(shown("echo 'rock & roll &'", &mut game), "> rock & roll &");
+        assert_eq!(shown(r"echo fish\ \&", &mut game), "> fish &");
+        assert!(game.jobs.is_empty());
+        assert_eq!(shown("\"echo\" later &", &mut game), "> [1] \"echo\" later");
+    }
+
+    #[test]
+    fn piped_scans_filter_ports_as_they_arrive() {
+        let mut game = GameLogicState::default();
+        assert_eq!(shown("nmap neotechlabs.com | grep 80", &mut game), "");
+        let job = game.scan.as_mut().unwrap();
+        let (lines, done) = job.advance(Duration::from_secs(10), &game.locale);
+        assert!(done);
+        assert!(matches!(&lines[..], [OutputLine::Port { num: 80, .. }]));
+    }
+
+    #[test]
+    fn theme_switches_to_presets_only_by_name() {
+        let mut game = GameLogicState::default();
+        let outcome = run("theme amber", &mut game);
+        assert_eq!(outcome.lines, vec![OutputLine::info("Theme set to amber")]);
+        assert_eq!(
+            outcome.effects,
+            vec![Effect::SetTheme(Theme::preset("amber").unwrap())]
+        );
+        let outcome = run("theme ../../etc/passwd", &mut game);
+        assert_eq!(
+            outcome.lines,
+            vec![OutputLine::error(
+                "theme: unknown theme '../../etc/passwd'; presets are classic, amber, ice"
+            )]
+        );
+        assert!(outcome.effects.is_empty());
+    }
+
+    #[test]
+    fn lang_switches_messages_and_keeps_unknown_codes_out() {
+        let mut game = GameLogicState::default();
+        assert_eq!(shown("lang", &mut game), "> Language: en");
+        assert_eq!(shown("lang es", &mut game), "> Idioma: español");
+        assert_eq!(shown("ssh nowhere", &mut game), "> ssh: host no encontrado");
+        assert_eq!(
+            shown("lang ../en", &mut game),
+            "> lang: no hay traducción para '../en'"
+        );
+        assert_eq!(game.locale.code, "es");
+    }
+
+    #[test]
+    fn destructive_commands_are_found_through_aliases() {
+        let registry = CommandRegistry::default();
+        let mut game = GameLogicState::default();
+        run("alias quit=exit", &mut game);
+        assert!(registry.is_destructive("exit", &game));
+        assert!(registry.is_destructive("quit", &game));
+        assert!(!registry.is_destructive("save", &game));
+        assert!(!registry.is_destructive("exitt", &game));
+    }
+
+    #[test]
+    fn same_seed_plays_out_the_same() {
+        let play = |seed| {
+            let mut game = GameLogicState {
+                difficulty: Difficulty::Hard.into(),
+                rng: GameRng::new(seed),
+                ..Default::default()
+            };
+            let challenges: Vec<String> = (0..5)
+                .map(|_| {
+                    game.cooldowns.tick(Duration::from_secs(3));
+                    let challenge = shown("exploit neotechlabs.com", &mut game);
+                    run("00", &mut game);
+                    challenge
+                })
+                .collect();
+            (challenges, shown("seed", &mut game))
+        };
+        let (first, seed) = play(42);
+        assert_eq!(play(42), (first.clone(), seed.clone()));
+        assert_eq!(seed, "> Seed: 42");
+        assert_ne!(play(7).0, first);
+        // Hard challenges are six bytes long, after the leading `>`
+        let sequence = first[0].lines().last().unwrap();
+        assert_eq!(sequence.split_whitespace().count(), 7, "{}", sequence);
+    }
+}
@@ -0,0 +1,473 @@
+use ron::extensions::Extensions;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::a11y::A11ySettings;
+use crate::assets::asset_path;
+use crate::audio::AudioSettings;
+use crate::difficulty::Difficulty;
+use crate::locale::{tr, Locale, LocaleError};
+use crate::theme::{Theme, ThemeError};
+use crate::transition::TransitionSettings;
+
+/// What a run starts with, in one place. Settings changed in game are
+/// saved on their own instead, where the game writes them back: the
+/// output speed, window mode, prefix matching and opacity in
+/// `settings.json`, and the accessibility toggles in `accessibility.json`,
+/// which overrides the `a11y` defaults here. Keybindings stay in `assets/keybindings.ron`, which maps keys to
+/// actions rather than setting values.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Config {
+    pub game: GameConfig,
+    pub display: DisplayConfig,
+    pub audio: AudioConfig,
+    pub a11y: A11yConfig,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct GameConfig {
+    pub difficulty: Difficulty,
+    /// Language code of a file in `assets/locales`.
+    pub lang: String,
+    /// Seed for the run, or `None` for a random one.
+    pub seed: Option<u64>,
+    /// Whether the session is written to a transcript file.
+    pub transcript: bool,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct DisplayConfig {
+    /// A preset or a theme in `assets/themes`.
+    pub theme: String,
+    /// Seconds `ssh` takes to play in a new host; 0 turns it off.
+    pub transition_seconds: f32,
+    /// How hard that transition glitches the screen, from 0.0 to 1.0.
+    pub transition_intensity: f32,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct AudioConfig {
+    /// Master volume, from 0.0 (muted) to 1.0.
+    pub volume: f32,
+}
+
+/// What the accessibility settings are before any are changed in game.
+#[derive(Clone, PartialEq, Debug)]
+pub struct A11yConfig {
+    /// Multiplies every font size, from 0.75 to 2.0.
+    pub font_scale: f32,
+    pub high_contrast: bool,
+    pub colorblind: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            game: GameConfig {
+                difficulty: Difficulty::default(),
+                lang: "en".to_string(),
+                seed: None,
+                transcript: true,
+            },
+            display: DisplayConfig {
+                theme: "classic".to_string(),
+                transition_seconds: TransitionSettings::default().seconds,
+                transition_intensity: TransitionSettings::default().intensity,
+            },
+            audio: AudioConfig {
+                volume: AudioSettings::default().volume,
+            },
+            a11y: A11yConfig {
+                font_scale: A11ySettings::default().font_scale,
+                high_contrast: A11ySettings::default().high_contrast,
+                colorblind: A11ySettings::default().colorblind,
+            },
+        }
+    }
+}
+
+impl Config {
+    /// The language `game.lang` names, or English if it can't be loaded.
+    pub fn locale(&self) -> Locale {
+        let code = &self.game.lang;
+        match Locale::load(code) {
+            Ok(locale) => locale,
+            Err(LocaleError::Unknown) => {
+                eprintln!("No translation for language {:?}", code);
+                Locale::default()
+            }
+            Err(LocaleError::Invalid { path, error }) => {
+                eprintln!("Invalid {}: {}", path, error);
+                Locale::default()
+            }
+        }
+    }
+
+    /// The theme `display.theme` names, or the default if it can't be loaded.
+    pub fn theme(&self) -> Theme {
+        let name = &self.display.theme;
+        match Theme::load(name) {
+            Ok(theme) => theme,
+            Err(ThemeError::Unknown) => {
+                eprintln!("Unknown theme {:?}", name);
+                Theme::default()
+            }
+            Err(ThemeError::Invalid { path, error }) => {
+                eprintln!("Invalid {}: {}", path, error);
+                Theme::default()
+            }
+        }
+    }
+
+    pub fn audio(&self) -> AudioSettings {
+        AudioSettings {
+            volume: self.audio.volume.clamp(0.0, 1.0),
+        }
+    }
+
+    pub fn transition(&self) -> TransitionSettings {
+        TransitionSettings {
+            seconds: self.display.transition_seconds.max(0.0),
+            intensity: self.display.transition_intensity.clamp(0.0, 1.0),
+        }
+    }
+
+    pub fn a11y(&self) -> A11ySettings {
+        A11ySettings {
+            font_scale: self.a11y.font_scale,
+            high_contrast: self.a11y.high_contrast,
+            colorblind: self.a11y.colorblind,
+        }
+        .clamped()
+    }
+
+    /// Every value by its dotted key, in the order `config` lists them.
+    fn entries(&self, locale: &Locale) -> Vec<(&'static str, String)> {
+        vec![
+            ("game.difficulty", self.game.difficulty.to_string()),
+            ("game.lang", self.game.lang.clone()),
+            (
+                "game.seed",
+                self.game.seed.map_or_else(
+                    || locale.get("config.seed_random").to_string(),
+                    |seed| seed.to_string(),
+                ),
+            ),
+            ("game.transcript", self.game.transcript.to_string()),
+            ("display.theme", self.display.theme.clone()),
+            (
+                "display.transition_seconds",
+                format!("{}s", self.display.transition_seconds.max(0.0)),
+            ),
+            (
+                "display.transition_intensity",
+                format!(
+                    "{:.0}%",
+                    self.display.transition_intensity.clamp(0.0, 1.0) * 100.0
+                ),
+            ),
+            (
+                "audio.volume",
+                format!("{:.0}%", self.audio.volume.clamp(0.0, 1.0) * 100.0),
+            ),
+            ("a11y.font_scale", format!("{}x", self.a11y().font_scale)),
+            ("a11y.high_contrast", self.a11y.high_contrast.to_string()),
+            ("a11y.colorblind", self.a11y.colorblind.to_string()),
+        ]
+    }
+}
+
+/// Part of a config: only the values one source sets. Config files are
+/// written this way, e.g. `(game: (difficulty: hard))`.
+#[derive(Deserialize, Default, Debug)]
+#[serde(default, deny_unknown_fields)]
+pub struct Layer {
+    game: GameLayer,
+    display: DisplayLayer,
+    audio: AudioLayer,
+    a11y: A11yLayer,
+}
+
+#[derive(Deserialize, Default, Debug)]
+#[serde(default, deny_unknown_fields)]
+struct GameLayer {
+    difficulty: Option<Difficulty>,
+    lang: Option<String>,
+    seed: Option<u64>,
+    transcript: Option<bool>,
+}
+
+#[derive(Deserialize, Default, Debug)]
+#[serde(default, deny_unknown_fields)]
+struct DisplayLayer {
+    theme: Option<String>,
+    transition_seconds: Option<f32>,
+    transition_intensity: Option<f32>,
+}
+
+#[derive(Deserialize, Default, Debug)]
+#[serde(default, deny_unknown_fields)]
+struct AudioLayer {
+    volume: Option<f32>,
+}
+
+#[derive(Deserialize, Default, Debug)]
+#[serde(default, deny_unknown_fields)]
+struct A11yLayer {
+    font_scale: Option<f32>,
+    high_contrast: Option<bool>,
+    colorblind: Option<bool>,
+}
+
+impl Layer {
+    /// The values set by `--difficulty <level>`, `--lang <code>`,
+    /// `--seed <n>` and `--no-transcript`.
+    pub fn from_args(args: &[String]) -> Self {
+        let value = |flag: &str| {
+            let index = args.iter().position(|arg| arg == flag)?;
+            args.get(index + 1)
+        };
+        let mut layer = Layer::default();
+        layer.game.difficulty = value("--difficulty").and_then(|level| {
+            let parsed = Difficulty::parse(level);
+            if parsed.is_none() {
+                eprintln!(
+                    "Unknown difficulty {:?}; expected easy, normal or hard",
+                    level
+                );
+            }
+            parsed
+        });
+        layer.game.lang = value("--lang").cloned();
+        layer.game.seed = value("--seed").and_then(|seed| {
+            let parsed = seed.parse().ok();
+            if parsed.is_none() {
+                eprintln!("Invalid seed {:?}; expected a whole number", seed);
+            }
+            parsed
+        });
+        if args.iter().any(|arg| arg == "--no-transcript") {
+            layer.game.transcript = Some(false);
+        }
+        layer
+    }
+
+    /// A layer written as RON, where a value that is set needn't be
+    /// wrapped in `Some`.
+    fn parse(contents: &str) -> Result<Self, ron::error::SpannedError> {
+        ron::Options::default()
+            .with_default_extension(Extensions::IMPLICIT_SOME)
+            .from_str(contents)
+    }
+
+    /// The layer in the file at `path`, if there is one and it's valid.
+    fn read(path: &Path) -> Option<Self> {
+        let contents = fs::read_to_string(path).ok()?;
+        Layer::parse(&contents)
+            .map_err(|err| eprintln!("Ignoring invalid {}: {}", path.display(), err))
+            .ok()
+    }
+}
+
+/// Where a value in the config came from.
+#[derive(Clone, PartialEq, Debug)]
+pub enum Source {
+    Default,
+    File(PathBuf),
+    Args,
+}
+
+impl Source {
+    fn describe(&self, locale: &Locale) -> String {
+        match self {
+            Source::Default => locale.get("config.source_default").to_string(),
+            Source::File(path) => path.display().to_string(),
+            Source::Args => locale.get("config.source_args").to_string(),
+        }
+    }
+}
+
+/// The OS config directory's `config.ron`, which overrides the one in
+/// `assets`.
+fn user_config_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("neon-city").join("config.ron"))
+}
+
+/// The config a run started with, and the source of every value that
+/// isn't a default.
+#[derive(Default)]
+pub struct LoadedConfig {
+    pub config: Config,
+    sources: BTreeMap<&'static str, Source>,
+}
+
+impl LoadedConfig {
+    /// The built-in defaults, overridden by `assets/config.ron`, then by
+    /// `config.ron` in the OS config directory, then by the command line.
+    pub fn load() -> Self {
+        let args: Vec<String> = std::env::args().collect();
+        let mut layers = Vec::new();
+        let asset_config = asset_path("config.ron");
+        for path in std::iter::once(asset_config).chain(user_config_path()) {
+            if let Some(layer) = Layer::read(&path) {
+                layers.push((Source::File(path), layer));
+            }
+        }
+        layers.push((Source::Args, Layer::from_args(&args)));
+        LoadedConfig::resolve(layers)
+    }
+
+    /// The defaults with `layers` applied in order, each one's values
+    /// taking precedence over those before it.
+    pub fn resolve(layers: Vec<(Source, Layer)>) -> Self {
+        let mut loaded = LoadedConfig::default();
+        for (source, layer) in layers {
+            let Layer {
+                game,
+                display,
+                audio,
+                a11y,
+            } = layer;
+            loaded.set("game.difficulty", game.difficulty, &source, |config| {
+                &mut config.game.difficulty
+            });
+            loaded.set("game.lang", game.lang, &source, |config| {
+                &mut config.game.lang
+            });
+            loaded.set("game.seed", game.seed.map(Some), &source, |config| {
+                &mut config.game.seed
+            });
+            loaded.set("game.transcript", game.transcript, &source, |config| {
+                &mut config.game.transcript
+            });
+            loaded.set("display.theme", display.theme, &source, |config| {
+                &mut config.display.theme
+            });
+            loaded.set(
+                "display.transition_seconds",
+                display.transition_seconds,
+                &source,
+                |config| &mut config.display.transition_seconds,
+            );
+            loaded.set(
+                "display.transition_intensity",
+                display.transition_intensity,
+                &source,
+                |config| &mut config.display.transition_intensity,
+            );
+            loaded.set("audio.volume", audio.volume, &source, |config| {
+                &mut config.audio.volume
+            });
+            loaded.set("a11y.font_scale", a11y.font_scale, &source, |config| {
+                &mut config.a11y.font_scale
+            });
+            loaded.set(
+                "a11y.high_contrast",
+                a11y.high_contrast,
+                &source,
+                |config| &mut config.a11y.high_contrast,
+            );
+            loaded.set("a11y.colorblind", a11y.colorblind, &source, |config| {
+                &mut config.a11y.colorblind
+            });
+        }
+        loaded
+    }
+
+    /// Puts `value`, if the layer has one, in the `key` field `slot` picks
+    /// out, and notes `source` as where it came from.
+    fn set<T>(
+        &mut self,
+        key: &'static str,
+        value: Option<T>,
+        source: &Source,
+        slot: fn(&mut Config) -> &mut T,
+    ) {
+        if let Some(value) = value {
+            *slot(&mut self.config) = value;
+            self.sources.insert(key, source.clone());
+        }
+    }
+
+    pub fn source(&self, key: &str) -> &Source {
+        self.sources.get(key).unwrap_or(&Source::Default)
+    }
+
+    /// Prints each value that isn't a default and where it came from.
+    pub fn log_overrides(&self) {
+        let english = Locale::default();
+        for (key, value) in self.config.entries(&english) {
+            if let Some(source) = self.sources.get(key) {
+                eprintln!(
+                    "Config: {} = {} (from {})",
+                    key,
+                    value,
+                    source.describe(&english)
+                );
+            }
+        }
+    }
+
+    /// Handles `config`.
+    pub fn describe(&self, locale: &Locale) -> Vec<String> {
+        let mut lines = vec![locale.get("config.header").to_string()];
+        lines.extend(self.config.entries(locale).into_iter().map(|(key, value)| {
+            tr!(
+                locale,
+                "config.entry",
+                key = key,
+                value = value,
+                source = self.source(key).describe(locale)
+            )
+        }));
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn layer(ron: &str) -> Layer {
+        Layer::parse(ron).unwrap()
+    }
+
+    #[test]
+    fn later_layers_override_earlier_ones_key_by_key() {
+        let file = Source::File(PathBuf::from("config.ron"));
+        let args: Vec<String> = ["neon-city", "--difficulty", "easy", "--no-transcript"]
+            .map(String::from)
+            .to_vec();
+        let loaded = LoadedConfig::resolve(vec![
+            (
+                file.clone(),
+                layer("(game: (difficulty: hard, seed: 7), a11y: (font_scale: 1.5))"),
+            ),
+            (Source::Args, Layer::from_args(&args)),
+        ]);
+        let config = &loaded.config;
+        assert_eq!(config.game.difficulty, Difficulty::Easy);
+        assert_eq!(loaded.source("game.difficulty"), &Source::Args);
+        assert_eq!(config.game.seed, Some(7));
+        assert_eq!(loaded.source("game.seed"), &file);
+        assert!(!config.game.transcript);
+        assert_eq!(config.a11y().font_scale, 1.5);
+        assert_eq!(loaded.source("a11y.font_scale"), &file);
+        assert_eq!(config.display.theme, "classic");
+        assert_eq!(loaded.source("display.theme"), &Source::Default);
+        let lines = loaded.describe(&Locale::default());
+        assert!(lines.contains(&"  game.seed = 7  (config.ron)".to_string()));
+        assert!(lines.contains(&"  display.theme = classic  (default)".to_string()));
+    }
+
+    #[test]
+    fn config_files_reject_unknown_keys() {
+        assert!(Layer::parse("(game: (difficulty: hard))").is_ok());
+        assert!(Layer::parse("(game: (dificulty: hard))").is_err());
+        let shipped = layer(include_str!("../assets/config.ron"));
+        let loaded = LoadedConfig::resolve(vec![(Source::Args, shipped)]);
+        assert_eq!(loaded.config, Config::default());
+    }
+}
@@ -0,0 +1,68 @@
+use serde::Deserialize;
+use std::fmt;
+
+#[derive(Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum Difficulty {
+    Easy,
+    #[default]
+    Normal,
+    Hard,
+}
+
+impl Difficulty {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "easy" => Some(Difficulty::Easy),
+            "normal" => Some(Difficulty::Normal),
+            "hard" => Some(Difficulty::Hard),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Difficulty {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match self {
+            Difficulty::Easy => "easy",
+            Difficulty::Normal => "normal",
+            Difficulty::Hard => "hard",
+        })
+    }
+}
+
+/// The tuning a difficulty level implies.
+pub struct DifficultySettings {
+    pub level: Difficulty,
+    /// Seconds before a trace completes, or `None` if downloads aren't traced.
+    pub trace_seconds: Option<f32>,
+    /// Length of the hex sequence in an `exploit` challenge.
+    pub puzzle_bytes: usize,
+    /// Seconds given to answer an `exploit` challenge.
+    pub puzzle_seconds: f32,
+    /// Delay between ports appearing during an `nmap`.
+    pub scan_port_seconds: f32,
+}
+
+impl From<Difficulty> for DifficultySettings {
+    fn from(level: Difficulty) -> Self {
+        let (trace_seconds, puzzle_bytes, puzzle_seconds, scan_port_seconds) = match level {
+            Difficulty::Easy => (None, 3, 20.0, 0.25),
+            Difficulty::Normal => (Some(30.0), 4, 12.0, 0.4),
+            Difficulty::Hard => (Some(20.0), 6, 10.0, 0.7),
+        };
+        DifficultySettings {
+            level,
+            trace_seconds,
+            puzzle_bytes,
+            puzzle_seconds,
+            scan_port_seconds,
+        }
+    }
+}
+
+impl Default for DifficultySettings {
+    fn default() -> Self {
+        Difficulty::default().into()
+    }
+}
@@ -0,0 +1,164 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+
+use crate::commands::Effect;
+
+/// A command line has been run, whether or not it did what was asked.
+#[derive(Event, Clone, PartialEq, Debug)]
+pub struct CommandSubmitted {
+    pub command: String,
+    /// Whether it went wrong, as `CommandOutcome::failed` has it.
+    pub failed: bool,
+}
+
+/// A host's firewall has been breached.
+#[derive(Event, Clone, PartialEq, Debug)]
+pub struct HostBreached {
+    pub host: String,
+}
+
+/// A trace has started and will complete in `seconds`.
+#[derive(Event, Clone, PartialEq, Debug)]
+pub struct TraceStarted {
+    pub seconds: f32,
+}
+
+/// `cloak` shook off a trace with `remaining_secs` still on its clock.
+#[derive(Event, Clone, PartialEq, Debug)]
+pub struct TraceEvaded {
+    pub remaining_secs: f32,
+}
+
+/// `ssh` has logged in to `host`, and its greeting is on the way.
+#[derive(Event, Clone, PartialEq, Debug)]
+pub struct HostConnected {
+    pub host: String,
+}
+
+/// An `nmap` of `host` has finished.
+#[derive(Event, Clone, PartialEq, Debug)]
+pub struct HostScanned {
+    pub host: String,
+}
+
+/// The loot item `name` has been added to the inventory.
+#[derive(Event, Clone, PartialEq, Debug)]
+pub struct LootCollected {
+    pub name: String,
+}
+
+/// The player asked to start over from a fresh boot.
+#[derive(Event, Clone, PartialEq, Debug)]
+pub struct ResetRequested;
+
+/// The player pressed Ctrl+C to stop whatever is running. Each system
+/// with something in progress drops it when it sees this.
+#[derive(Event, Clone, PartialEq, Debug)]
+pub struct InterruptRequested;
+
+/// The player asked to watch the session so far played back at `speed`.
+#[derive(Event, Clone, PartialEq, Debug)]
+pub struct ReplayRequested {
+    pub speed: f32,
+}
+
+/// Registers the terminal's events, so systems can react to what happens
+/// in the game without `update_terminal` knowing about them.
+pub fn add_events(app: &mut App) {
+    app.add_event::<CommandSubmitted>()
+        .add_event::<HostBreached>()
+        .add_event::<HostConnected>()
+        .add_event::<TraceStarted>()
+        .add_event::<TraceEvaded>()
+        .add_event::<HostScanned>()
+        .add_event::<LootCollected>()
+        .add_event::<ResetRequested>()
+        .add_event::<ReplayRequested>()
+        .add_event::<InterruptRequested>();
+}
+
+/// System param for sending the terminal's events.
+#[derive(SystemParam)]
+pub struct TerminalEvents<'w> {
+    submitted: EventWriter<'w, CommandSubmitted>,
+    breached: EventWriter<'w, HostBreached>,
+    connected: EventWriter<'w, HostConnected>,
+    trace_started: EventWriter<'w, TraceStarted>,
+    trace_evaded: EventWriter<'w, TraceEvaded>,
+    looted: EventWriter<'w, LootCollected>,
+    reset: EventWriter<'w, ResetRequested>,
+    replay: EventWriter<'w, ReplayRequested>,
+}
+
+impl TerminalEvents<'_> {
+    pub fn submitted(&mut self, command: &str, failed: bool) {
+        self.submitted.send(CommandSubmitted {
+            command: command.to_string(),
+            failed,
+        });
+    }
+
+    /// Sends the event `effect` announces, if it is one of them, and says
+    /// whether it was.
+    pub fn send(&mut self, effect: &Effect) -> bool {
+        match effect {
+            Effect::Breached(host) => {
+                self.breached.send(HostBreached { host: host.clone() });
+            }
+            Effect::Connected(host) => {
+                self.connected.send(HostConnected { host: host.clone() });
+            }
+            Effect::TraceStarted(seconds) => {
+                self.trace_started.send(TraceStarted { seconds: *seconds });
+            }
+            Effect::TraceEvaded(remaining_secs) => {
+                self.trace_evaded.send(TraceEvaded {
+                    remaining_secs: *remaining_secs,
+                });
+            }
+            Effect::Looted(name) => {
+                self.looted.send(LootCollected { name: name.clone() });
+            }
+            Effect::Reset => {
+                self.reset.send(ResetRequested);
+            }
+            Effect::Replay(speed) => {
+                self.replay.send(ReplayRequested { speed: *speed });
+            }
+            _ => return false,
+        }
+        true
+    }
+
+    /// Sends out every announcement in `effects`, leaving the rest queued
+    /// for whatever applies them.
+    pub fn announce(&mut self, effects: &mut Vec<Effect>) {
+        effects.retain(|effect| !self.send(effect));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::system::RunSystemOnce;
+
+    #[test]
+    fn announcing_leaves_other_effects_queued() {
+        let mut app = App::new();
+        add_events(&mut app);
+        let left = app
+            .world_mut()
+            .run_system_once(|mut events: TerminalEvents| {
+                let mut effects = vec![
+                    Effect::TraceStarted(30.0),
+                    Effect::Clear,
+                    Effect::Looted("payroll.db".to_string()),
+                ];
+                events.announce(&mut effects);
+                effects
+            });
+        assert_eq!(left, [Effect::Clear]);
+        assert_eq!(app.world().resource::<Events<TraceStarted>>().len(), 1);
+        assert_eq!(app.world().resource::<Events<LootCollected>>().len(), 1);
+    }
+}
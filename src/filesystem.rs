@@ -0,0 +1,289 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::locale::Locale;
+use crate::network::HOME_HOST;
+
+pub enum FsNode {
+    Dir(BTreeMap<String, FsNode>),
+    File(FileItem),
+}
+
+/// Hex digits per line of an encrypted file's gibberish.
+const SCRAMBLED_LINE_DIGITS: usize = 32;
+
+pub struct FileItem {
+    contents: String,
+    /// Whether `cat` shows only gibberish, until `decrypt` is given `key`.
+    pub encrypted: bool,
+    key: String,
+}
+
+impl FileItem {
+    /// What `cat` shows: the contents, or for an encrypted file, the
+    /// contents XORed with the key, in hex.
+    pub fn shown(&self) -> String {
+        if !self.encrypted {
+            return self.contents.clone();
+        }
+        let digits: Vec<String> = self
+            .contents
+            .bytes()
+            .zip(self.key.bytes().cycle())
+            .map(|(byte, key)| format!("{:02x}", byte ^ key))
+            .collect();
+        digits
+            .chunks(SCRAMBLED_LINE_DIGITS / 2)
+            .map(|line| line.concat())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// The contents, if `key` is the one the file is encrypted with.
+    pub fn decrypt(&self, key: &str) -> Option<&str> {
+        (self.encrypted && key == self.key).then_some(self.contents.as_str())
+    }
+}
+
+/// Why a path could not be used, phrased the way a shell would report it.
+#[derive(Debug)]
+pub enum FsError {
+    NotFound,
+    NotADirectory,
+    IsADirectory,
+}
+
+impl FsError {
+    pub fn message<'a>(&self, locale: &'a Locale) -> &'a str {
+        locale.get(match self {
+            FsError::NotFound => "fs.not_found",
+            FsError::NotADirectory => "fs.not_a_directory",
+            FsError::IsADirectory => "fs.is_a_directory",
+        })
+    }
+}
+
+/// The directory tree on each host, keyed by host name.
+pub struct FileSystem {
+    roots: HashMap<String, FsNode>,
+}
+
+impl FileSystem {
+    /// Resolves `path` against `cwd` into an absolute path. `..` stops at
+    /// the root, and the result has no trailing slash except for `/` itself.
+    pub fn resolve(cwd: &str, path: &str) -> String {
+        let mut parts: Vec<&str> = if path.starts_with('/') {
+            Vec::new()
+        } else {
+            cwd.split('/').filter(|p| !p.is_empty()).collect()
+        };
+        for part in path.split('/') {
+            match part {
+                "" | "." => {}
+                ".." => {
+                    parts.pop();
+                }
+                name => parts.push(name),
+            }
+        }
+        format!("/{}", parts.join("/"))
+    }
+
+    /// The node at absolute `path` on `host`.
+    pub fn node(&self, host: &str, path: &str) -> Result<&FsNode, FsError> {
+        let mut node = self.roots.get(host).ok_or(FsError::NotFound)?;
+        for part in path.split('/').filter(|p| !p.is_empty()) {
+            node = match node {
+                FsNode::Dir(entries) => entries.get(part).ok_or(FsError::NotFound)?,
+                FsNode::File(_) => return Err(FsError::NotADirectory),
+            };
+        }
+        Ok(node)
+    }
+
+    /// Names in the directory at `path`, with a trailing `/` on subdirectories.
+    pub fn list(&self, host: &str, path: &str) -> Result<Vec<String>, FsError> {
+        match self.node(host, path)? {
+            FsNode::Dir(entries) => Ok(entries
+                .iter()
+                .map(|(name, node)| match node {
+                    FsNode::Dir(_) => format!("{}/", name),
+                    FsNode::File(_) => name.clone(),
+                })
+                .collect()),
+            FsNode::File(_) => Err(FsError::NotADirectory),
+        }
+    }
+
+    pub fn read(&self, host: &str, path: &str) -> Result<&FileItem, FsError> {
+        match self.node(host, path)? {
+            FsNode::File(file) => Ok(file),
+            FsNode::Dir(_) => Err(FsError::IsADirectory),
+        }
+    }
+
+    pub fn is_dir(&self, host: &str, path: &str) -> Result<bool, FsError> {
+        Ok(matches!(self.node(host, path)?, FsNode::Dir(_)))
+    }
+}
+
+fn dir<const N: usize>(entries: [(&str, FsNode); N]) -> FsNode {
+    FsNode::Dir(
+        entries
+            .into_iter()
+            .map(|(name, node)| (name.to_string(), node))
+            .collect(),
+    )
+}
+
+fn file(contents: &str) -> FsNode {
+    FsNode::File(FileItem {
+        contents: contents.to_string(),
+        encrypted: false,
+        key: String::new(),
+    })
+}
+
+fn encrypted(contents: &str, key: &str) -> FsNode {
+    FsNode::File(FileItem {
+        contents: contents.to_string(),
+        encrypted: true,
+        key: key.to_string(),
+    })
+}
+
+impl Default for FileSystem {
+    /// The same files on every run; the notes lead from host to host and
+    /// hold the passwords the network expects, and the keys to the files
+    /// that hide the rest.
+    fn default() -> Self {
+        let mut roots = HashMap::new();
+        roots.insert(
+            HOME_HOST.to_string(),
+            dir([(
+                "home",
+                dir([(
+                    "runner",
+                    dir([(
+                        "job.txt",
+                        file(
+                            "Client wants the NeoTech vault dump.\n\
+                             Way in is neotechlabs.com; their dev box is sloppy.",
+                        ),
+                    )]),
+                )]),
+            )]),
+        );
+        roots.insert(
+            "neotechlabs.com".to_string(),
+            dir([
+                (
+                    "etc",
+                    dir([("motd", file("NeoTech Labs. Authorized use only."))]),
+                ),
+                (
+                    "var",
+                    dir([(
+                        "www",
+                        dir([("index.html", file("<h1>NeoTech Labs: Tomorrow, Today</h1>"))]),
+                    )]),
+                ),
+            ]),
+        );
+        roots.insert(
+            "dev.neotechlabs.com".to_string(),
+            dir([(
+                "home",
+                dir([(
+                    "dev",
+                    dir([
+                        (
+                            "todo.txt",
+                            file(
+                                "- rotate the mail admin password (still hunter2)\n\
+                                 - stop leaving creds in todo lists",
+                            ),
+                        ),
+                        (".keyring", file("vault:/data/archive.cred  bl4ckic3")),
+                    ]),
+                )]),
+            )]),
+        );
+        roots.insert(
+            "mail.neotechlabs.com".to_string(),
+            dir([(
+                "var",
+                dir([(
+                    "mail",
+                    dir([(
+                        "admin",
+                        file(
+                            "From: it@neotechlabs.com\n\
+                             Subject: vault access\n\
+                             The vault password is now n30t3ch. Delete this mail.",
+                        ),
+                    )]),
+                )]),
+            )]),
+        );
+        roots.insert(
+            "vault.neotechlabs.com".to_string(),
+            dir([(
+                "data",
+                dir([
+                    ("README", file("Customer records. Pull with wget data.")),
+                    (
+                        "archive.cred",
+                        encrypted(
+                            "host: archive.neotechlabs.com\n\
+                             password: c0ldst0r4ge",
+                            "bl4ckic3",
+                        ),
+                    ),
+                ]),
+            )]),
+        );
+        roots.insert(
+            "archive.neotechlabs.com".to_string(),
+            dir([(
+                "backups",
+                dir([(
+                    "README",
+                    file("Cold storage for the vault. Nightly dumps, kept forever."),
+                )]),
+            )]),
+        );
+        FileSystem { roots }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encrypted_files_are_gibberish_without_their_key() {
+        let fs = FileSystem::default();
+        let file = fs
+            .read("vault.neotechlabs.com", "/data/archive.cred")
+            .unwrap();
+        let shown = file.shown();
+        assert!(!shown.contains("c0ldst0r4ge"));
+        assert!(shown.lines().all(|line| line.len() <= SCRAMBLED_LINE_DIGITS
+            && line.chars().all(|c| c.is_ascii_hexdigit())));
+        // XOR is its own inverse, so the key turns the gibberish back
+        let key = b"bl4ckic3";
+        let bytes: Vec<u8> = shown
+            .replace('\n', "")
+            .as_bytes()
+            .chunks(2)
+            .map(|pair| u8::from_str_radix(std::str::from_utf8(pair).unwrap(), 16).unwrap())
+            .zip(key.iter().cycle())
+            .map(|(byte, key)| byte ^ key)
+            .collect();
+        assert_eq!(file.decrypt("bl4ckic3"), std::str::from_utf8(&bytes).ok());
+        assert_eq!(file.decrypt("hunter2"), None);
+        let plain = fs.read("neotechlabs.com", "/etc/motd").unwrap();
+        assert_eq!(plain.shown(), "NeoTech Labs. Authorized use only.");
+        assert_eq!(plain.decrypt(""), None);
+    }
+}
@@ -0,0 +1,120 @@
+use bevy::diagnostic::{
+    DiagnosticsStore, EntityCountDiagnosticsPlugin, FrameTimeDiagnosticsPlugin,
+};
+use bevy::prelude::*;
+
+use crate::keybindings::{Action, KeyBindings};
+
+/// Marks the frame rate overlay in the bottom-left corner.
+#[derive(Component)]
+struct FpsOverlay;
+
+/// Frame rate, frame time and entity count, shown on demand to diagnose
+/// performance. Hidden to begin with, and not updated while it is.
+pub struct FpsOverlayPlugin;
+
+impl Plugin for FpsOverlayPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins((FrameTimeDiagnosticsPlugin, EntityCountDiagnosticsPlugin))
+            .add_systems(Startup, setup_fps_overlay)
+            .add_systems(
+                Update,
+                (toggle_fps_overlay, update_fps_overlay.run_if(overlay_shown)).chain(),
+            );
+    }
+}
+
+fn setup_fps_overlay(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn((
+        TextBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font: asset_server.load("fonts/FiraMono-Regular.ttf"),
+                    font_size: 14.0,
+                    color: Color::srgb(1.0, 1.0, 0.3),
+                },
+            ),
+            style: Style {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(10.0),
+                left: Val::Px(10.0),
+                ..default()
+            },
+            background_color: Color::srgba(0.0, 0.0, 0.0, 0.6).into(),
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        FpsOverlay,
+    ));
+}
+
+fn toggle_fps_overlay(
+    keys: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut overlays: Query<&mut Visibility, With<FpsOverlay>>,
+) {
+    if !bindings.just_pressed(&keys, Action::ToggleFpsOverlay) {
+        return;
+    }
+    for mut visibility in &mut overlays {
+        *visibility = match *visibility {
+            Visibility::Hidden => Visibility::Inherited,
+            _ => Visibility::Hidden,
+        };
+    }
+}
+
+fn overlay_shown(overlays: Query<&Visibility, With<FpsOverlay>>) -> bool {
+    overlays
+        .iter()
+        .any(|visibility| *visibility != Visibility::Hidden)
+}
+
+/// The overlay's lines, with `--` for anything not measured yet.
+fn overlay_text(fps: Option<f64>, frame_ms: Option<f64>, entities: Option<f64>) -> String {
+    let show = |value: Option<f64>, decimals: usize| {
+        value.map_or("--".to_string(), |value| format!("{:.*}", decimals, value))
+    };
+    format!(
+        "FPS {}\n{} ms\n{} entities",
+        show(fps, 0),
+        show(frame_ms, 1),
+        show(entities, 0)
+    )
+}
+
+fn update_fps_overlay(
+    diagnostics: Res<DiagnosticsStore>,
+    mut overlays: Query<&mut Text, With<FpsOverlay>>,
+) {
+    let smoothed = |path| {
+        diagnostics
+            .get(path)
+            .and_then(|diagnostic| diagnostic.smoothed())
+    };
+    let text = overlay_text(
+        smoothed(&FrameTimeDiagnosticsPlugin::FPS),
+        smoothed(&FrameTimeDiagnosticsPlugin::FRAME_TIME),
+        diagnostics
+            .get(&EntityCountDiagnosticsPlugin::ENTITY_COUNT)
+            .and_then(|diagnostic| diagnostic.value()),
+    );
+    for mut overlay in &mut overlays {
+        overlay.sections[0].value.clone_from(&text);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overlay_rounds_readings_and_marks_missing_ones() {
+        assert_eq!(
+            overlay_text(Some(59.7), Some(16.74), Some(312.0)),
+            "FPS 60\n16.7 ms\n312 entities"
+        );
+        assert_eq!(overlay_text(None, None, None), "FPS --\n-- ms\n-- entities");
+    }
+}
@@ -0,0 +1,190 @@
+use bevy::input::gamepad::{GamepadConnection, GamepadConnectionEvent};
+use bevy::prelude::*;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+
+use crate::assets::asset_path;
+use crate::commands::GameLogicState;
+use crate::layout::TerminalLayout;
+use crate::locale::tr;
+use crate::terminal::ScrollbackState;
+
+/// Stick deflection below which the pad is taken to be at rest, so a
+/// drifting stick doesn't scroll the terminal away from the keyboard.
+const STICK_DEADZONE: f32 = 0.25;
+/// Lines the right stick scrolls per second when pushed all the way.
+const STICK_LINES_PER_SECOND: f32 = 30.0;
+
+/// Everything a gamepad can be told to do. A pad can't type, so the
+/// terminal's share is what works on a line that is already there.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PadAction {
+    /// Runs the line, or the last command again if the line is empty.
+    Submit,
+    HistoryPrev,
+    HistoryNext,
+    ToggleNetworkMap,
+    Pause,
+    MenuUp,
+    MenuDown,
+    MenuSelect,
+    MenuBack,
+}
+
+impl PadAction {
+    /// Whether the action is for the pause menu, whose buttons may be the
+    /// same ones the terminal uses, as the two never take input together.
+    fn in_menu(self) -> bool {
+        matches!(
+            self,
+            PadAction::MenuUp | PadAction::MenuDown | PadAction::MenuSelect | PadAction::MenuBack
+        )
+    }
+}
+
+#[derive(Resource)]
+pub struct PadBindings {
+    bindings: HashMap<PadAction, GamepadButtonType>,
+}
+
+impl Default for PadBindings {
+    fn default() -> Self {
+        PadBindings {
+            bindings: HashMap::from([
+                (PadAction::Submit, GamepadButtonType::South),
+                (PadAction::HistoryPrev, GamepadButtonType::DPadUp),
+                (PadAction::HistoryNext, GamepadButtonType::DPadDown),
+                (PadAction::ToggleNetworkMap, GamepadButtonType::North),
+                (PadAction::Pause, GamepadButtonType::Start),
+                (PadAction::MenuUp, GamepadButtonType::DPadUp),
+                (PadAction::MenuDown, GamepadButtonType::DPadDown),
+                (PadAction::MenuSelect, GamepadButtonType::South),
+                (PadAction::MenuBack, GamepadButtonType::East),
+            ]),
+        }
+    }
+}
+
+impl PadBindings {
+    pub fn button(&self, action: PadAction) -> GamepadButtonType {
+        self.bindings[&action]
+    }
+
+    /// Whether any connected pad just pressed the button for `action`.
+    pub fn just_pressed(&self, buttons: &ButtonInput<GamepadButton>, action: PadAction) -> bool {
+        let bound = self.button(action);
+        buttons
+            .get_just_pressed()
+            .any(|button| button.button_type == bound)
+    }
+
+    /// Defaults overridden by whatever `assets/padbindings.ron` specifies,
+    /// the way `keybindings.ron` overrides keys, e.g. `{ submit: West }`.
+    pub fn load() -> Self {
+        let mut pad_bindings = PadBindings::default();
+        let path = asset_path("padbindings.ron");
+        if let Ok(contents) = fs::read_to_string(&path) {
+            match ron::from_str::<HashMap<PadAction, GamepadButtonType>>(&contents) {
+                Ok(overrides) => pad_bindings.bindings.extend(overrides),
+                Err(err) => warn!("Ignoring invalid {}: {}", path.display(), err),
+            }
+        }
+        pad_bindings.warn_on_conflicts();
+        pad_bindings
+    }
+
+    fn warn_on_conflicts(&self) {
+        let mut by_button: HashMap<(bool, GamepadButtonType), Vec<PadAction>> = HashMap::new();
+        for (action, button) in &self.bindings {
+            by_button
+                .entry((action.in_menu(), *button))
+                .or_default()
+                .push(*action);
+        }
+        for ((_, button), actions) in by_button {
+            if actions.len() > 1 {
+                warn!(
+                    "{:?} is bound to more than one action: {:?}",
+                    button, actions
+                );
+            }
+        }
+    }
+}
+
+/// Says in the terminal when a controller is plugged in or taken away.
+pub fn announce_gamepads(
+    layout: Res<TerminalLayout>,
+    game: Res<GameLogicState>,
+    mut connections: EventReader<GamepadConnectionEvent>,
+    mut scrollback: ResMut<ScrollbackState>,
+) {
+    for ev in connections.read() {
+        let notice = match &ev.connection {
+            GamepadConnection::Connected(info) => {
+                tr!(game.locale, "gamepad.connected", name = info.name)
+            }
+            GamepadConnection::Disconnected => game.locale.get("gamepad.disconnected").to_string(),
+        };
+        scrollback.push(&notice, &layout);
+    }
+}
+
+/// Scrolls the output with the right stick, faster the further it is
+/// pushed, a line at a time.
+pub fn scroll_with_stick(
+    time: Res<Time>,
+    gamepads: Res<Gamepads>,
+    axes: Res<Axis<GamepadAxis>>,
+    mut carried: Local<f32>,
+    mut scrollback: ResMut<ScrollbackState>,
+) {
+    // The pad pushed furthest wins, so two can't cancel each other out
+    let deflection = gamepads
+        .iter()
+        .map(|gamepad| GamepadAxis::new(gamepad, GamepadAxisType::RightStickY))
+        .filter_map(|axis| axes.get(axis))
+        .filter(|value| value.abs() >= STICK_DEADZONE)
+        .fold(0.0, |furthest: f32, value| {
+            if value.abs() > furthest.abs() {
+                value
+            } else {
+                furthest
+            }
+        });
+    if deflection == 0.0 {
+        *carried = 0.0;
+        return;
+    }
+    // Whole lines scroll now, and the rest carries over to the next frame
+    *carried += deflection * STICK_LINES_PER_SECOND * time.delta_seconds();
+    let lines = carried.abs().floor();
+    if lines >= 1.0 {
+        scrollback.scroll_lines(*carried > 0.0, lines as usize);
+        *carried -= lines.copysign(*carried);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn menu_and_terminal_actions_may_share_buttons() {
+        let pad_bindings = PadBindings::default();
+        assert_eq!(
+            pad_bindings.button(PadAction::Submit),
+            pad_bindings.button(PadAction::MenuSelect)
+        );
+        let mut buttons = ButtonInput::default();
+        buttons.press(GamepadButton::new(
+            Gamepad::new(1),
+            GamepadButtonType::DPadUp,
+        ));
+        assert!(pad_bindings.just_pressed(&buttons, PadAction::HistoryPrev));
+        assert!(pad_bindings.just_pressed(&buttons, PadAction::MenuUp));
+        assert!(!pad_bindings.just_pressed(&buttons, PadAction::MenuDown));
+    }
+}
@@ -0,0 +1,107 @@
+use bevy::color::Mix;
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::a11y::A11ySettings;
+use crate::commands::{GameLogicState, TraceState};
+use crate::layout::TerminalLayout;
+use crate::terminal::{PromptSections, TerminalBackground, TerminalConfig, TerminalText};
+use crate::theme::Theme;
+use crate::transition::ConnectTransition;
+use crate::visuals::VisualSettings;
+
+/// Heat of a trace that has only just started, so the danger shows at once.
+const MIN_HEAT: f32 = 0.15;
+/// Furthest the text tears sideways, in world units, at full heat.
+const MAX_TEAR: f32 = 0.06;
+/// Chance each frame that the text tears, at full heat.
+const MAX_TEAR_CHANCE: f32 = 0.4;
+/// How far colours are pulled toward red, at full heat.
+const MAX_TINT: f32 = 0.6;
+const ALARM_RED: LinearRgba = LinearRgba::RED;
+
+/// How close a running trace is to completing, rising to 1 as it does, or
+/// `None` when there is no trace.
+pub fn trace_heat(trace: &TraceState) -> Option<f32> {
+    trace
+        .active
+        .then(|| MIN_HEAT + (1.0 - MIN_HEAT) * trace.timer.fraction())
+}
+
+/// `color` pulled toward red as far as `heat` calls for.
+fn tinted(color: Color, heat: f32) -> Color {
+    color.to_linear().mix(&ALARM_RED, heat * MAX_TINT).into()
+}
+
+/// While a trace runs, tears the terminal text sideways now and then and
+/// tints the screen red, more often and more deeply the closer the trace
+/// is to completing. A login's transition tears it too, without the red.
+/// Everything goes back to normal once they end.
+pub fn glitch_effect(
+    game: Res<GameLogicState>,
+    layout: Res<TerminalLayout>,
+    theme: Res<Theme>,
+    visuals: Res<VisualSettings>,
+    a11y: Res<A11ySettings>,
+    config: Res<TerminalConfig>,
+    transition: Res<ConnectTransition>,
+    mut glitching: Local<bool>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    backgrounds: Query<&Handle<StandardMaterial>, With<TerminalBackground>>,
+    mut texts: Query<(&mut Transform, &mut Text, &PromptSections), With<TerminalText>>,
+) {
+    let heat = trace_heat(&game.trace);
+    let burst = transition.burst();
+    if heat.is_none() && burst == 0.0 && !*glitching {
+        return;
+    }
+    // One last pass at no heat puts the colours and text back in place
+    *glitching = heat.is_some() || burst > 0.0;
+    let heat = heat.unwrap_or(0.0);
+    let shake = heat.max(burst);
+    let colors = a11y.theme(&theme, &visuals);
+    // Cosmetic, so it stays off the seeded game RNG
+    let mut rng = rand::thread_rng();
+    let tear = if shake > 0.0 && rng.gen::<f32>() < shake * MAX_TEAR_CHANCE {
+        rng.gen_range(-1.0..=1.0) * shake * MAX_TEAR
+    } else {
+        0.0
+    };
+    for handle in &backgrounds {
+        if let Some(material) = materials.get_mut(handle) {
+            material.base_color =
+                tinted(colors.background, heat).with_alpha(config.background_alpha());
+        }
+    }
+    for (mut transform, mut text, prompt) in &mut texts {
+        transform.translation = layout.text_origin() + Vec3::X * tear;
+        // Tint the prompt's own highlighting rather than paint over it
+        let start = prompt.start(&text);
+        for (section, color) in text.sections[start..].iter_mut().zip(&prompt.colors) {
+            section.style.color = tinted(*color, heat);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn heat_rises_as_the_trace_runs_down() {
+        let mut trace = TraceState {
+            active: false,
+            timer: Timer::from_seconds(10.0, TimerMode::Once),
+        };
+        assert_eq!(trace_heat(&trace), None);
+        trace.active = true;
+        let start = trace_heat(&trace).unwrap();
+        trace.timer.tick(Duration::from_secs(9));
+        let late = trace_heat(&trace).unwrap();
+        assert!(MIN_HEAT <= start && start < late && late <= 1.0);
+        assert_eq!(tinted(Color::WHITE, 0.0), Color::WHITE.to_linear().into());
+        let red = tinted(Color::WHITE, late).to_linear();
+        assert!(red.red > red.green && red.green == red.blue);
+    }
+}
@@ -0,0 +1,903 @@
+use bevy::app::ScheduleRunnerPlugin;
+use bevy::input::InputPlugin;
+use bevy::prelude::*;
+use bevy::state::app::StatesPlugin;
+use std::io::{self, BufRead};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::boot::BootState;
+use crate::commands::{CommandRegistry, GameLogicState};
+use crate::layout::TerminalLayout;
+use crate::markup;
+use crate::pause::RunState;
+use crate::scripts::ScriptQueue;
+use crate::terminal::{
+    self, PendingOutput, ResponseDelay, ScrollbackState, TerminalConfig, TerminalPlugin,
+    TerminalState,
+};
+use crate::transcript::TranscriptLogger;
+use crate::transition::TransitionSettings;
+
+/// How often the headless app updates, in place of a display's refresh.
+const FRAME_SECONDS: f64 = 1.0 / 60.0;
+
+/// Command lines read from stdin by a background thread, so reading never
+/// blocks a frame.
+#[derive(Resource)]
+pub struct StdinLines(Mutex<Receiver<String>>);
+
+/// Runs the game on stdin and stdout: one command per line in, plain text
+/// out. Quits once stdin closes and the last command has finished.
+pub fn run(registry: CommandRegistry, transcript: TranscriptLogger, game: GameLogicState) {
+    let mut app = app(registry, transcript, game);
+    app.add_systems(Update, (read_stdin, print_output));
+    app.run();
+}
+
+/// The game without a window, sounds or assets, driven only through
+/// `push_command`. There is no boot sequence, no session to restore or
+/// save, and nobody to confirm destructive commands, so they just run.
+pub fn app(registry: CommandRegistry, transcript: TranscriptLogger, game: GameLogicState) -> App {
+    let mut app = App::new();
+    app.add_plugins((
+        MinimalPlugins.set(ScheduleRunnerPlugin::run_loop(Duration::from_secs_f64(
+            FRAME_SECONDS,
+        ))),
+        StatesPlugin,
+        InputPlugin,
+    ))
+    .insert_state(BootState::Ready)
+    .insert_resource(TerminalConfig {
+        confirm_destructive: false,
+        restore_session: false,
+        // A piped run is nobody's saved game, so it leaves that one alone
+        session_path: None,
+        // Nor are the player's settings its to change
+        settings_path: None,
+        // Nobody is watching the screen for it
+        connect_transition: TransitionSettings {
+            seconds: 0.0,
+            ..default()
+        },
+        ..default()
+    })
+    // Output goes to stdout, which wraps for itself
+    .insert_resource(TerminalLayout {
+        columns: usize::MAX,
+        log_columns: usize::MAX,
+        ..default()
+    })
+    .insert_resource(registry)
+    .insert_resource(transcript)
+    .insert_resource(game)
+    .add_plugins(TerminalPlugin { headless: true });
+    app
+}
+
+/// Submits `cmd` as if it were typed and entered, then runs one update.
+/// Output is shown at once rather than typed out, so it can be checked in
+/// the scrollback straight away; a response a remote host holds back stays
+/// in `ResponseDelay` until its latency passes. The app must have run its
+/// first update, which sets the terminal up.
+#[cfg(test)]
+pub fn push_command(world: &mut World, cmd: &str) {
+    world.resource_scope(|world, mut state: Mut<TerminalState>| {
+        terminal::submit(cmd, &mut state, &mut world.resource_mut::<ResponseDelay>());
+    });
+    world.run_schedule(Main);
+    world.resource_scope(|world, mut pending: Mut<PendingOutput>| {
+        pending.flush(&mut world.resource_mut::<ScrollbackState>());
+    });
+}
+
+/// Feeds stdin to the terminal, and quits once it has closed and the last
+/// command has finished, or the run is over. The reading thread starts on
+/// the first update.
+fn read_stdin(
+    mut commands: Commands,
+    lines: Option<Res<StdinLines>>,
+    run: Res<State<RunState>>,
+    game: Res<GameLogicState>,
+    pending: Res<PendingOutput>,
+    scripts: Res<ScriptQueue>,
+    mut state: ResMut<TerminalState>,
+    mut delay: ResMut<ResponseDelay>,
+    mut exit: EventWriter<AppExit>,
+) {
+    let Some(lines) = lines else {
+        let (sender, receiver) = mpsc::channel();
+        std::thread::spawn(move || {
+            for line in io::stdin().lock().lines().map_while(Result::ok) {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        commands.insert_resource(StdinLines(Mutex::new(receiver)));
+        return;
+    };
+    let receiver = lines
+        .0
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    loop {
+        match receiver.try_recv() {
+            Ok(line) => terminal::submit(&line, &mut state, &mut delay),
+            Err(TryRecvError::Empty) => break,
+            Err(TryRecvError::Disconnected) => {
+                // Nothing is run or drained once the runner has flatlined or won
+                let over = *run.get() != RunState::Running;
+                let busy = !delay.queued.is_empty()
+                    || delay.is_waiting()
+                    || pending.is_streaming()
+                    || scripts.is_running()
+                    || !game.jobs.is_empty()
+                    || game.puzzle.is_some()
+                    || game.scan.is_some()
+                    || game.download.is_some();
+                if over || !busy {
+                    exit.send(AppExit::Success);
+                }
+                break;
+            }
+        }
+    }
+}
+
+/// Prints each new line of output without its colour tags.
+fn print_output(
+    mut pending: ResMut<PendingOutput>,
+    mut scrollback: ResMut<ScrollbackState>,
+    mut printed: Local<usize>,
+) {
+    if pending.is_streaming() {
+        pending.flush(&mut scrollback);
+    }
+    // A reset starts the scrollback over
+    if scrollback.pushed() < *printed {
+        *printed = 0;
+    }
+    if scrollback.pushed() == *printed {
+        return;
+    }
+    for line in scrollback.lines_since(*printed) {
+        println!("{}", markup::strip(line));
+    }
+    *printed = scrollback.pushed();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::events::{InterruptRequested, TraceEvaded};
+    use crate::heat::{Heat, MAX_HEAT};
+    use crate::missions::Goal;
+    use crate::network::{HostStatus, HOME_HOST};
+    use crate::rng::GameRng;
+    use crate::scripts::Script;
+    use crate::status::{RunnerStatus, MAX_INTEGRITY};
+    use bevy::ecs::system::RunSystemOnce;
+    use bevy::input::gamepad::{
+        GamepadButtonChangedEvent, GamepadConnection, GamepadConnectionEvent, GamepadEvent,
+        GamepadInfo,
+    };
+    use bevy::input::keyboard::{Key, KeyboardInput};
+    use bevy::input::ButtonState;
+    use bevy::time::TimeUpdateStrategy;
+
+    /// How far each update of a `stepped` app moves its clock.
+    const STEP: Duration = Duration::from_millis(100);
+
+    /// Shows whatever output is still being typed out, all at once.
+    fn flush(app: &mut App) {
+        app.world_mut()
+            .resource_scope(|world, mut pending: Mut<PendingOutput>| {
+                pending.flush(&mut world.resource_mut::<ScrollbackState>());
+            });
+    }
+
+    /// The scrollback's lines from the `since`th one pushed on, untagged.
+    fn lines_since(app: &App, since: usize) -> Vec<String> {
+        app.world()
+            .resource::<ScrollbackState>()
+            .lines_since(since)
+            .map(markup::strip)
+            .collect()
+    }
+
+    fn headless() -> App {
+        let mut app = app(
+            CommandRegistry::default(),
+            TranscriptLogger::default(),
+            GameLogicState::default(),
+        );
+        app.update();
+        app
+    }
+
+    /// A headless app with a fixed seed, playing `game` on a clock that
+    /// moves by exactly `STEP` each update and not at all between them,
+    /// so a test can say what must have happened after so many seconds.
+    fn stepped(game: GameLogicState) -> App {
+        let mut app = app(
+            CommandRegistry::default(),
+            TranscriptLogger::default(),
+            GameLogicState {
+                rng: GameRng::new(7),
+                ..game
+            },
+        );
+        app.insert_resource(TimeUpdateStrategy::ManualDuration(STEP));
+        // The clock only starts counting from the first update
+        app.update();
+        app
+    }
+
+    /// Runs a `stepped` app on for `duration`, a whole number of steps,
+    /// showing output the moment it is ready.
+    fn advance(app: &mut App, duration: Duration) {
+        for _ in 0..duration.as_millis() / STEP.as_millis() {
+            app.update();
+            flush(app);
+        }
+    }
+
+    /// A run on the NeoTech front end, where a trace has somewhere to
+    /// kick the runner back from.
+    fn on_neotech() -> GameLogicState {
+        GameLogicState {
+            current_host: "neotechlabs.com".to_string(),
+            ..default()
+        }
+    }
+
+    /// Runs `wget data` and steps the clock until the download is done.
+    fn download(app: &mut App) {
+        output_of(app, "wget data");
+        while app.world().resource::<GameLogicState>().download.is_some() {
+            advance(app, STEP);
+        }
+    }
+
+    fn output_of(app: &mut App, cmd: &str) -> Vec<String> {
+        let since = app.world().resource::<ScrollbackState>().pushed();
+        push_command(app.world_mut(), cmd);
+        lines_since(app, since)
+    }
+
+    #[test]
+    fn the_session_is_saved_on_app_exit() {
+        let path = std::env::temp_dir().join(format!("neon-city-exit-{}.json", std::process::id()));
+        let mut app = headless();
+        // Only ever where a test puts it, never over the player's own
+        let mut config = app.world_mut().resource_mut::<TerminalConfig>();
+        assert_eq!(config.session_path, None);
+        config.session_path = Some(path.clone());
+        output_of(&mut app, "alias ll=ls");
+        assert!(!path.exists());
+        app.world_mut().send_event(AppExit::Success);
+        app.update();
+        let saved = crate::save::load_session(&path);
+        let _ = std::fs::remove_file(&path);
+        let saved = saved.expect("the session should have been saved");
+        assert_eq!(saved.history, ["alias ll=ls"]);
+        assert_eq!(saved.aliases["ll"], "ls");
+    }
+
+    #[test]
+    fn discovered_hosts_are_restored_with_their_numbers() {
+        let path =
+            std::env::temp_dir().join(format!("neon-city-hosts-{}.json", std::process::id()));
+        let mut app = headless();
+        app.world_mut()
+            .resource_mut::<TerminalConfig>()
+            .session_path = Some(path.clone());
+        let mut game = app.world_mut().resource_mut::<GameLogicState>();
+        game.network.enter("neotechlabs.com");
+        game.network
+            .discover("vault.neotechlabs.com", HostStatus::Scanned);
+        let found: Vec<_> = game
+            .network
+            .numbered()
+            .map(|(_, host, _)| host.to_string())
+            .collect();
+        app.world_mut().send_event(AppExit::Success);
+        app.update();
+
+        let mut restored = headless();
+        let mut config = restored.world_mut().resource_mut::<TerminalConfig>();
+        config.session_path = Some(path.clone());
+        config.restore_session = true;
+        restored
+            .world_mut()
+            .run_system_once(terminal::init_terminal);
+        let _ = std::fs::remove_file(&path);
+        let network = &restored.world().resource::<GameLogicState>().network;
+        let numbered: Vec<_> = network
+            .numbered()
+            .map(|(_, host, _)| host.to_string())
+            .collect();
+        assert_eq!(numbered, found);
+        assert_eq!(network.discovered["neotechlabs.com"], HostStatus::Breached);
+        assert_eq!(
+            network.discovered["vault.neotechlabs.com"],
+            HostStatus::Scanned
+        );
+        assert_eq!(
+            network.discovered["mail.neotechlabs.com"],
+            HostStatus::Unknown
+        );
+    }
+
+    #[test]
+    fn clear_empties_the_screen_but_keeps_the_history() {
+        let mut app = headless();
+        output_of(&mut app, "cd /home");
+        assert!(output_of(&mut app, "clear").is_empty());
+        let scrollback = app.world().resource::<ScrollbackState>();
+        assert_eq!(scrollback.lines_since(0).count(), 0);
+        let state = app.world().resource::<TerminalState>();
+        assert_eq!(state.history(), ["cd /home", "clear"]);
+        assert_eq!(app.world().resource::<GameLogicState>().cwd, "/home");
+        // Output carries on as before underneath
+        let output = output_of(&mut app, "pwd");
+        assert!(output[0].ends_with("] > /home"), "{:?}", output);
+    }
+
+    #[test]
+    fn commands_run_without_a_window() {
+        let mut app = headless();
+        let output = output_of(&mut app, "pwd");
+        assert_eq!(output.len(), 1);
+        assert!(output[0].ends_with("] > /"), "{:?}", output);
+        // Remote responses wait out the round trip
+        let since = app.world().resource::<ScrollbackState>().pushed();
+        assert!(output_of(&mut app, "nmap neotechlabs.com").is_empty());
+        app.world_mut()
+            .resource_mut::<ResponseDelay>()
+            .timer
+            .tick(Duration::from_secs(60));
+        app.update();
+        flush(&mut app);
+        let output = lines_since(&app, since);
+        assert!(
+            output[0].contains("Scanning neotechlabs.com"),
+            "{:?}",
+            output
+        );
+        assert!(app.world().resource::<GameLogicState>().scan.is_some());
+    }
+
+    #[test]
+    fn a_trace_that_completes_before_cloak_ends_the_run() {
+        let mut app = stepped(on_neotech());
+        download(&mut app);
+        advance(&mut app, Duration::from_secs(29));
+        assert!(app.world().resource::<GameLogicState>().trace.active);
+        let since = app.world().resource::<ScrollbackState>().pushed();
+        advance(&mut app, Duration::from_secs(1));
+        let game = app.world().resource::<GameLogicState>();
+        assert!(!game.trace.active);
+        assert_eq!(game.current_host, HOME_HOST);
+        // Let the trace and damage reports finish typing out first
+        advance(&mut app, Duration::from_secs(3));
+        assert_eq!(
+            *app.world().resource::<State<RunState>>().get(),
+            RunState::GameOver
+        );
+        let output = lines_since(&app, since);
+        assert!(
+            output.ends_with(&[
+                "> TRACE COMPLETE — connection terminated".to_string(),
+                "> Integrity -60% (40% left)".to_string(),
+                "> They know who you are. The job is blown.".to_string(),
+                "> Press Enter to restart.".to_string(),
+            ]),
+            "{:?}",
+            output
+        );
+        let runner = app.world().resource::<RunnerStatus>();
+        assert!(runner.integrity > 0 && runner.integrity < MAX_INTEGRITY);
+    }
+
+    #[test]
+    fn too_many_loud_commands_set_off_a_trace() {
+        let mut app = stepped(on_neotech());
+        app.world_mut().resource_mut::<Heat>().level = MAX_HEAT - 10.0;
+        output_of(&mut app, "pwd");
+        assert!(!app.world().resource::<GameLogicState>().trace.active);
+        let output = output_of(&mut app, "nmap");
+        assert!(app.world().resource::<GameLogicState>().trace.active);
+        assert!(
+            output
+                .iter()
+                .any(|line| line.ends_with("the target has noticed you")),
+            "{:?}",
+            output
+        );
+        let output = output_of(&mut app, "cloak");
+        assert!(output[0].ends_with("Trace evaded"), "{:?}", output);
+        assert!(app.world().resource::<Heat>().level < MAX_HEAT);
+    }
+
+    #[test]
+    fn topping_out_during_a_trace_raises_no_second_alarm() {
+        let mut app = stepped(on_neotech());
+        app.world_mut()
+            .resource_mut::<GameLogicState>()
+            .start_trace();
+        app.world_mut().resource_mut::<Heat>().level = MAX_HEAT - 10.0;
+        let output = output_of(&mut app, "nmap");
+        assert!(
+            !output
+                .iter()
+                .any(|line| line.ends_with("the target has noticed you")),
+            "{:?}",
+            output
+        );
+    }
+
+    #[test]
+    fn prefixes_make_as_much_noise_as_what_they_run() {
+        let mut app = stepped(GameLogicState {
+            prefix_matching: true,
+            ..on_neotech()
+        });
+        app.world_mut().resource_mut::<Heat>().level = MAX_HEAT - 10.0;
+        let output = output_of(&mut app, "nm");
+        assert!(
+            app.world().resource::<GameLogicState>().trace.active,
+            "{:?}",
+            output
+        );
+        output_of(&mut app, "cloa");
+        assert!(app.world().resource::<Heat>().level < MAX_HEAT - 10.0);
+    }
+
+    #[test]
+    fn cloak_just_in_time_keeps_the_runner_on_the_host() {
+        let mut app = stepped(on_neotech());
+        download(&mut app);
+        advance(&mut app, Duration::from_millis(29_800));
+        let trace = &app.world().resource::<GameLogicState>().trace;
+        assert!(trace.active);
+        assert_eq!(trace.timer.remaining(), Duration::from_millis(200));
+        let output = output_of(&mut app, "cloak");
+        assert!(output[0].ends_with("Trace evaded"), "{:?}", output);
+        advance(&mut app, Duration::from_secs(10));
+        let game = app.world().resource::<GameLogicState>();
+        assert!(!game.trace.active);
+        assert_eq!(game.current_host, "neotechlabs.com");
+        let runner = app.world().resource::<RunnerStatus>();
+        assert_eq!(runner.integrity, MAX_INTEGRITY);
+    }
+
+    #[test]
+    fn a_job_that_finishes_before_the_trace_keeps_its_loot() {
+        let mut app = stepped(on_neotech());
+        output_of(&mut app, "wget data &");
+        advance(&mut app, Duration::from_millis(7_800));
+        let game = app.world().resource::<GameLogicState>();
+        assert!(!game.inventory.has("customer-records") && !game.trace.active);
+        // The job runs when its timer is up, and the download starts a trace
+        // once it has finished
+        advance(&mut app, Duration::from_secs(2));
+        let game = app.world().resource::<GameLogicState>();
+        assert!(game.jobs.is_empty() && game.download.is_some());
+        while app.world().resource::<GameLogicState>().download.is_some() {
+            advance(&mut app, STEP);
+        }
+        let game = app.world().resource::<GameLogicState>();
+        assert!(game.trace.active && game.inventory.has("customer-records"));
+        advance(&mut app, Duration::from_secs(30));
+        let game = app.world().resource::<GameLogicState>();
+        assert_eq!(game.current_host, HOME_HOST);
+        assert!(game.inventory.has("customer-records"));
+    }
+
+    #[test]
+    fn scripts_stop_at_the_line_that_fails() {
+        // Whether the verb is unknown, misused or refused
+        for failing in ["nope", "ssh", "ssh nowhere.net"] {
+            script_stops_at_line_3(failing);
+        }
+    }
+
+    fn script_stops_at_line_3(failing: &str) {
+        let mut app = headless();
+        let since = app.world().resource::<ScrollbackState>().pushed();
+        let script = Script::parse("broken.sh", &format!("# comment\npwd\n{}\npwd\n", failing));
+        app.world_mut()
+            .resource_mut::<ScriptQueue>()
+            .start(script, 0);
+        for _ in 0..4 {
+            app.update();
+            flush(&mut app);
+        }
+        let output = lines_since(&app, since);
+        assert!(!app.world().resource::<ScriptQueue>().is_running());
+        let failed = output
+            .iter()
+            .position(|line| line.ends_with("run: broken.sh: line 3 failed, stopping"))
+            .unwrap_or_else(|| panic!("{:?}", output));
+        assert_eq!(
+            output.iter().filter(|line| line.ends_with("+ pwd")).count(),
+            1,
+            "{:?}",
+            output
+        );
+        assert_eq!(failed, output.len() - 1, "{:?}", output);
+    }
+
+    #[test]
+    fn opacity_is_set_clamped_and_reported() {
+        let mut app = headless();
+        // The player's own settings are left alone
+        assert_eq!(app.world().resource::<TerminalConfig>().settings_path, None);
+        let output = output_of(&mut app, "opacity");
+        assert!(output[0].ends_with("] > Opacity: 100%"), "{:?}", output);
+        output_of(&mut app, "opacity 60");
+        assert_eq!(app.world().resource::<TerminalConfig>().opacity, 60);
+        let output = output_of(&mut app, "opacity 5");
+        assert!(output[0].ends_with("] > Opacity: 30%"), "{:?}", output);
+        let output = output_of(&mut app, "opacity dim");
+        assert!(output[0].contains("expected a percentage"), "{:?}", output);
+        assert_eq!(app.world().resource::<TerminalConfig>().opacity, 30);
+    }
+
+    #[test]
+    fn finishing_the_last_objective_wins_the_run() {
+        let mut app = headless();
+        {
+            let mut game = app.world_mut().resource_mut::<GameLogicState>();
+            for objective in &mut game.mission.objectives {
+                objective.done = objective.goal != Goal::EvadeTrace;
+            }
+        }
+        let output = output_of(&mut app, "objectives");
+        assert!(
+            output[4].ends_with("] >   [ ] Shake off a trace"),
+            "{:?}",
+            output
+        );
+        let since = app.world().resource::<ScrollbackState>().pushed();
+        app.world_mut().send_event(TraceEvaded {
+            remaining_secs: 5.0,
+        });
+        app.update();
+        app.update();
+        let output = lines_since(&app, since);
+        assert!(
+            output[0].ends_with("Objective complete: Shake off a trace"),
+            "{:?}",
+            output
+        );
+        assert!(output.iter().any(|line| line.contains("MISSION COMPLETE")));
+        assert_eq!(
+            *app.world().resource::<State<RunState>>().get(),
+            RunState::Victory
+        );
+    }
+
+    #[test]
+    fn reset_starts_a_fresh_run() {
+        let mut app = headless();
+        output_of(&mut app, "export TARGET=neotechlabs.com");
+        output_of(&mut app, "pwd &");
+        output_of(&mut app, "reset");
+        app.update();
+        let game = app.world().resource::<GameLogicState>();
+        assert!(game.jobs.is_empty());
+        assert!(!game.env.0.contains_key("TARGET"));
+        assert_eq!(app.world().resource::<ScrollbackState>().pushed(), 0);
+        let output = output_of(&mut app, "pwd");
+        assert!(output[0].ends_with("] > /"), "{:?}", output);
+    }
+
+    #[test]
+    fn background_jobs_report_when_done() {
+        let mut app = headless();
+        let output = output_of(&mut app, "pwd &");
+        assert!(output[0].ends_with("] > [1] pwd"), "{:?}", output);
+        let output = output_of(&mut app, "jobs");
+        assert!(output[0].ends_with("] > [1] 0%  pwd"), "{:?}", output);
+        // Mistakes aren't put off until later
+        let output = output_of(&mut app, "nope &");
+        assert!(output[0].contains("nope"), "{:?}", output);
+        assert!(!output[0].contains("[2]"), "{:?}", output);
+        // It still runs where it was started, and leaves the runner be
+        output_of(&mut app, "cd /home");
+
+        app.world_mut()
+            .resource_mut::<GameLogicState>()
+            .jobs
+            .tick(Duration::from_secs(60));
+        let since = app.world().resource::<ScrollbackState>().pushed();
+        app.update();
+        flush(&mut app);
+        let output = lines_since(&app, since);
+        assert!(output[0].ends_with("] > [done] pwd"), "{:?}", output);
+        assert!(output[1].ends_with("] > /"), "{:?}", output);
+        let game = app.world().resource::<GameLogicState>();
+        assert_eq!(game.cwd, "/home");
+        assert!(game.jobs.is_empty());
+    }
+
+    #[test]
+    fn a_gamepad_reruns_the_last_command_on_an_empty_line() {
+        let mut app = stepped(GameLogicState::default());
+        let pad = Gamepad::new(0);
+        app.world_mut()
+            .send_event(GamepadEvent::Connection(GamepadConnectionEvent::new(
+                pad,
+                GamepadConnection::Connected(GamepadInfo {
+                    name: "Test Pad".to_string(),
+                }),
+            )));
+        advance(&mut app, STEP);
+        let scrollback = app.world().resource::<ScrollbackState>();
+        assert_eq!(
+            scrollback.iter().last().map(markup::strip).as_deref(),
+            Some("> Controller connected: Test Pad")
+        );
+        output_of(&mut app, "echo again");
+        let since = app.world().resource::<ScrollbackState>().pushed();
+        app.world_mut()
+            .send_event(GamepadEvent::Button(GamepadButtonChangedEvent::new(
+                pad,
+                GamepadButtonType::South,
+                1.0,
+            )));
+        advance(&mut app, STEP);
+        let output = lines_since(&app, since);
+        assert!(
+            output.iter().any(|line| line.ends_with("> again")),
+            "{:?}",
+            output
+        );
+    }
+
+    #[test]
+    fn replay_plays_the_session_back_until_escape() {
+        let mut app = stepped(GameLogicState::default());
+        output_of(&mut app, "echo one");
+        advance(&mut app, Duration::from_secs(1));
+        output_of(&mut app, "echo two");
+        let since = app.world().resource::<ScrollbackState>().pushed();
+        let started = output_of(&mut app, "replay 4x");
+        assert_eq!(
+            started,
+            ["> Replaying 2 commands at 4x. Press Escape to stop."]
+        );
+        assert!(app.world().resource::<TerminalState>().is_replaying());
+        advance(&mut app, Duration::from_secs(2));
+        let output = lines_since(&app, since);
+        assert!(output[1].ends_with("> one"), "{:?}", output);
+        assert!(output[2].ends_with("> two"), "{:?}", output);
+        assert_eq!(output.last().map(String::as_str), Some("> Replay finished"));
+        let state = app.world().resource::<TerminalState>();
+        assert!(!state.is_replaying() && state.input().is_empty());
+
+        // The replay itself is part of the session now, so there's more to play
+        output_of(&mut app, "replay 0.5");
+        advance(&mut app, STEP * 3);
+        assert!(!app.world().resource::<TerminalState>().input().is_empty());
+        app.world_mut().send_event(KeyboardInput {
+            key_code: KeyCode::Escape,
+            logical_key: Key::Escape,
+            state: ButtonState::Pressed,
+            window: Entity::PLACEHOLDER,
+        });
+        let since = app.world().resource::<ScrollbackState>().pushed();
+        advance(&mut app, STEP);
+        assert_eq!(lines_since(&app, since), ["> Replay stopped"]);
+        let state = app.world().resource::<TerminalState>();
+        assert!(!state.is_replaying() && state.input().is_empty());
+        assert!(output_of(&mut app, "replay 0x")[0]
+            .ends_with("> replay: invalid speed '0x'; try e.g. 2x"));
+        // Speeds its timers can't be built for are turned away too
+        for speed in ["1e-21x", "1000x"] {
+            let output = output_of(&mut app, &format!("replay {}", speed));
+            assert!(output[0].contains("invalid speed"), "{:?}", output);
+        }
+    }
+
+    /// Feeds `lines` to a `stepped` app as if piped in, with stdin closing
+    /// after them, and runs it until it quits or `limit` has passed.
+    fn pipe(app: &mut App, lines: &[&str], limit: Duration) {
+        let (sender, receiver) = mpsc::channel();
+        for line in lines {
+            sender.send(line.to_string()).unwrap();
+        }
+        drop(sender);
+        app.insert_resource(StdinLines(Mutex::new(receiver)))
+            .add_systems(Update, read_stdin);
+        for _ in 0..limit.as_millis() / STEP.as_millis() {
+            if app.should_exit().is_some() {
+                break;
+            }
+            advance(app, STEP);
+        }
+    }
+
+    #[test]
+    fn a_piped_run_that_flatlines_still_exits() {
+        let mut app = stepped(GameLogicState::default());
+        app.world_mut().resource_mut::<RunnerStatus>().integrity = 10;
+        // Stdin has closed with the ping still to come when the run ends
+        let lines = [
+            "ssh neotechlabs.com",
+            "ssh mail.neotechlabs.com guess",
+            "ping neotechlabs.com",
+        ];
+        pipe(&mut app, &lines, Duration::from_secs(5));
+        assert_eq!(
+            *app.world().resource::<State<RunState>>().get(),
+            RunState::GameOver
+        );
+        assert_eq!(app.should_exit(), Some(AppExit::Success));
+    }
+
+    #[test]
+    fn a_piped_download_comes_all_the_way_down_before_exiting() {
+        let mut app = stepped(on_neotech());
+        pipe(&mut app, &["wget data"], Duration::from_secs(10));
+        assert_eq!(app.should_exit(), Some(AppExit::Success));
+        let game = app.world().resource::<GameLogicState>();
+        assert!(game.inventory.has("customer-records") && game.trace.active);
+    }
+
+    #[test]
+    fn logging_in_plays_a_transition_before_the_greeting() {
+        let mut app = stepped(GameLogicState::default());
+        app.world_mut()
+            .resource_mut::<TerminalConfig>()
+            .connect_transition
+            .seconds = 0.3;
+        let since = app.world().resource::<ScrollbackState>().pushed();
+        output_of(&mut app, "ssh neotechlabs.com");
+        // The host's own 0.6s pass quietly first
+        advance(&mut app, Duration::from_millis(500));
+        assert!(lines_since(&app, since).is_empty());
+        advance(&mut app, Duration::from_secs(1));
+        let output = lines_since(&app, since);
+        assert_eq!(output.len(), 5, "{:?}", output);
+        assert!(
+            output[0].contains("> Routing through proxy "),
+            "{:?}",
+            output
+        );
+        assert!(
+            output[1].contains("> Routing through proxy "),
+            "{:?}",
+            output
+        );
+        assert!(output[2].ends_with("> Negotiating a session with neotechlabs.com..."));
+        assert!(output[3].ends_with("> Connected to neotechlabs.com"));
+        assert_eq!(app.world().resource::<ScrollbackState>().live(), None);
+
+        // Enter on an empty line skips to the greeting
+        let mut app = stepped(GameLogicState::default());
+        app.world_mut()
+            .resource_mut::<TerminalConfig>()
+            .connect_transition
+            .seconds = 10.0;
+        output_of(&mut app, "ssh neotechlabs.com");
+        advance(&mut app, Duration::from_secs(1));
+        assert!(app.world().resource::<ScrollbackState>().live().is_some());
+        app.world_mut().send_event(KeyboardInput {
+            key_code: KeyCode::Enter,
+            logical_key: Key::Enter,
+            state: ButtonState::Pressed,
+            window: Entity::PLACEHOLDER,
+        });
+        let since = app.world().resource::<ScrollbackState>().pushed();
+        advance(&mut app, STEP * 2);
+        let output = lines_since(&app, since);
+        assert!(
+            output[0].ends_with("> Connected to neotechlabs.com"),
+            "{:?}",
+            output
+        );
+        assert_eq!(app.world().resource::<ScrollbackState>().live(), None);
+    }
+
+    #[test]
+    fn a_command_or_pad_press_during_a_transition_does_not_skip_it() {
+        let mut app = stepped(GameLogicState::default());
+        app.world_mut()
+            .resource_mut::<TerminalConfig>()
+            .connect_transition
+            .seconds = 10.0;
+        output_of(&mut app, "ssh neotechlabs.com");
+        advance(&mut app, Duration::from_secs(1));
+        assert!(app.world().resource::<ScrollbackState>().live().is_some());
+        // A command typed meanwhile waits for the greeting
+        app.world_mut()
+            .resource_mut::<TerminalState>()
+            .set_input("echo after".to_string());
+        app.world_mut().send_event(KeyboardInput {
+            key_code: KeyCode::Enter,
+            logical_key: Key::Enter,
+            state: ButtonState::Pressed,
+            window: Entity::PLACEHOLDER,
+        });
+        advance(&mut app, STEP * 2);
+        assert!(app.world().resource::<ScrollbackState>().live().is_some());
+        assert_eq!(
+            app.world().resource::<ResponseDelay>().queued,
+            ["echo after"]
+        );
+        // A pad runs the last command again rather than skipping
+        let pad = Gamepad::new(0);
+        app.world_mut()
+            .send_event(GamepadEvent::Connection(GamepadConnectionEvent::new(
+                pad,
+                GamepadConnection::Connected(GamepadInfo {
+                    name: "Test Pad".to_string(),
+                }),
+            )));
+        advance(&mut app, STEP);
+        app.world_mut()
+            .send_event(GamepadEvent::Button(GamepadButtonChangedEvent::new(
+                pad,
+                GamepadButtonType::South,
+                1.0,
+            )));
+        advance(&mut app, STEP * 2);
+        assert!(app.world().resource::<ScrollbackState>().live().is_some());
+        assert_eq!(
+            app.world().resource::<ResponseDelay>().queued,
+            ["echo after", "echo after"]
+        );
+        let since = app.world().resource::<ScrollbackState>().pushed();
+        advance(&mut app, Duration::from_secs(10));
+        let output = lines_since(&app, since);
+        let greeting = output
+            .iter()
+            .position(|line| line.ends_with("> Connected to neotechlabs.com"))
+            .unwrap_or_else(|| panic!("{:?}", output));
+        assert!(
+            output[greeting..]
+                .iter()
+                .any(|line| line.ends_with("> after")),
+            "{:?}",
+            output
+        );
+    }
+
+    #[test]
+    fn an_interrupt_stops_scans_jobs_and_held_back_output() {
+        let mut app = stepped(GameLogicState::default());
+        output_of(&mut app, "echo later &");
+        assert!(output_of(&mut app, "nmap neotechlabs.com").is_empty());
+        assert!(app.world().resource::<GameLogicState>().scan.is_some());
+        // Typed while the response is held back, so dropped along with it
+        assert!(output_of(&mut app, "echo queued").is_empty());
+        let since = app.world().resource::<ScrollbackState>().pushed();
+        app.world_mut().send_event(InterruptRequested);
+        advance(&mut app, STEP);
+        assert_eq!(lines_since(&app, since), ["^C"]);
+        let game = app.world().resource::<GameLogicState>();
+        assert!(game.scan.is_none() && game.jobs.is_empty());
+        let delay = app.world().resource::<ResponseDelay>();
+        assert!(!delay.is_waiting() && delay.queued.is_empty());
+        // Nothing comes back later, and the prompt works as before
+        advance(&mut app, Duration::from_secs(10));
+        assert_eq!(lines_since(&app, since), ["^C"]);
+        assert!(output_of(&mut app, "pwd")[0].ends_with("> /"));
+
+        // With nothing running it just starts a fresh line
+        app.world_mut()
+            .resource_mut::<TerminalState>()
+            .set_input("half typed".to_string());
+        app.world_mut().send_event(InterruptRequested);
+        advance(&mut app, STEP);
+        let state = app.world().resource::<TerminalState>();
+        assert!(state.input().is_empty() && !state.is_replaying());
+        let scrollback = app.world().resource::<ScrollbackState>();
+        assert_eq!(scrollback.iter().last(), Some("^C"));
+    }
+}
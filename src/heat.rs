@@ -0,0 +1,175 @@
+use bevy::prelude::*;
+
+use crate::commands::{CommandRegistry, Effect, GameLogicState};
+use crate::events::{CommandSubmitted, TraceStarted};
+use crate::layout::TerminalLayout;
+use crate::terminal::{PendingOutput, TerminalState};
+use crate::tokenize;
+
+pub const MAX_HEAT: f32 = 100.0;
+
+/// How much noise each thing the runner does makes, and how quickly it
+/// dies down again.
+#[derive(Resource)]
+pub struct HeatSettings {
+    /// Heat each key pressed at the terminal adds.
+    pub per_keystroke: f32,
+    /// Heat each command run adds, unless it is one of the loud ones.
+    pub per_command: f32,
+    /// Commands that stand out on the target's logs.
+    pub loud_commands: Vec<String>,
+    /// Heat a loud command adds instead.
+    pub per_loud_command: f32,
+    /// Heat `cloak` takes away.
+    pub cloak_relief: f32,
+    /// Seconds without a keystroke before heat starts to fall.
+    pub idle_seconds: f32,
+    /// Heat lost per second once idle.
+    pub decay_per_second: f32,
+}
+
+impl Default for HeatSettings {
+    fn default() -> Self {
+        HeatSettings {
+            per_keystroke: 0.3,
+            per_command: 2.0,
+            loud_commands: vec!["exploit".to_string(), "nmap".to_string()],
+            per_loud_command: 20.0,
+            cloak_relief: 40.0,
+            idle_seconds: 2.0,
+            decay_per_second: 4.0,
+        }
+    }
+}
+
+impl HeatSettings {
+    /// Heat running `verb` changes, which for `cloak` is a loss.
+    fn for_command(&self, verb: &str) -> f32 {
+        if verb == "cloak" {
+            -self.cloak_relief
+        } else if self.loud_commands.iter().any(|loud| loud == verb) {
+            self.per_loud_command
+        } else {
+            self.per_command
+        }
+    }
+}
+
+/// How much attention the runner has drawn, from 0 to `MAX_HEAT`. At the
+/// top it sets off a trace.
+#[derive(Resource, Default)]
+pub struct Heat {
+    pub level: f32,
+    /// Seconds since the runner last made any noise.
+    quiet_for: f32,
+}
+
+impl Heat {
+    /// Adds `amount`, which may be negative, and says whether that took
+    /// heat to the top from below it.
+    fn add(&mut self, amount: f32) -> bool {
+        let was_maxed = self.level >= MAX_HEAT;
+        self.level = (self.level + amount).clamp(0.0, MAX_HEAT);
+        !was_maxed && self.level >= MAX_HEAT
+    }
+
+    /// Lets `seconds` pass without noise, cooling off once idle long enough.
+    fn cool(&mut self, seconds: f32, settings: &HeatSettings) {
+        self.quiet_for += seconds;
+        let idle = (self.quiet_for - settings.idle_seconds).clamp(0.0, seconds);
+        self.level = (self.level - idle * settings.decay_per_second).max(0.0);
+    }
+
+    /// How full the meter is, from 0 to 1.
+    pub fn fraction(&self) -> f32 {
+        self.level / MAX_HEAT
+    }
+}
+
+/// Raises heat as the runner types and runs commands, loud ones most of
+/// all, and lets it fall while they sit still. Heat reaching the top sets
+/// off a trace.
+pub fn update_heat(
+    time: Res<Time>,
+    keys: Res<ButtonInput<KeyCode>>,
+    settings: Res<HeatSettings>,
+    registry: Res<CommandRegistry>,
+    layout: Res<TerminalLayout>,
+    state: Res<TerminalState>,
+    mut submitted: EventReader<CommandSubmitted>,
+    mut heat: ResMut<Heat>,
+    mut game: ResMut<GameLogicState>,
+    mut pending: ResMut<PendingOutput>,
+    mut started: EventWriter<TraceStarted>,
+) {
+    // A replay's typing isn't the runner's
+    let keystrokes = if state.is_replaying() {
+        0
+    } else {
+        keys.get_just_pressed().count()
+    };
+    let mut noise = keystrokes as f32 * settings.per_keystroke;
+    for ev in submitted.read() {
+        let line = game
+            .expand_line(&ev.command)
+            .unwrap_or_else(|_| ev.command.clone());
+        let typed = tokenize::verb(&line).unwrap_or_default();
+        // `nm` is as loud as `nmap` when prefix matching runs it as one
+        let verb = registry.resolve(&typed, &game).unwrap_or(&typed);
+        noise += settings.for_command(verb);
+    }
+    if noise == 0.0 {
+        heat.cool(time.delta_seconds(), &settings);
+        return;
+    }
+    heat.quiet_for = 0.0;
+    if !heat.add(noise) {
+        return;
+    }
+    // A trace already closing in doesn't need noticing again
+    let tracing = game.trace.active;
+    if game.start_trace() && !tracing {
+        pending.push(game.locale.get("heat.alarm"), &layout);
+    }
+    // No command is being evaluated to hand the trace starting on, and
+    // any other effects are still some command's to apply
+    game.effects.retain(|effect| match effect {
+        Effect::TraceStarted(seconds) => {
+            started.send(TraceStarted { seconds: *seconds });
+            false
+        }
+        _ => true,
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn heat_tops_out_once_and_cools_only_when_idle() {
+        let settings = HeatSettings::default();
+        let mut heat = Heat::default();
+        assert!(!heat.add(settings.for_command("nmap") * 4.0));
+        assert!(heat.add(settings.for_command("exploit")));
+        assert_eq!(heat.level, MAX_HEAT);
+        assert!(!heat.add(settings.for_command("ls")));
+        // Nothing comes off until the runner has been idle a while
+        heat.cool(settings.idle_seconds, &settings);
+        assert_eq!(heat.level, MAX_HEAT);
+        // And then the same however the time is split into frames
+        heat.cool(0.5, &settings);
+        heat.cool(0.5, &settings);
+        let mut smooth = Heat {
+            level: MAX_HEAT,
+            quiet_for: settings.idle_seconds,
+        };
+        smooth.cool(1.0, &settings);
+        assert!((heat.level - smooth.level).abs() < 1e-4);
+        assert!(heat.level < MAX_HEAT);
+        heat.add(settings.for_command("cloak"));
+        assert!(heat.level < MAX_HEAT - settings.cloak_relief);
+        heat.add(-MAX_HEAT);
+        assert_eq!(heat.fraction(), 0.0);
+    }
+}
@@ -0,0 +1,157 @@
+use bevy::prelude::*;
+
+use crate::theme::Theme;
+
+/// Colour of a command's arguments, which are neither right nor wrong.
+const ARGUMENT_COLOR: Color = Color::srgb(0.75, 0.75, 0.75);
+
+/// Which part of a command line some of its text is, for colouring the
+/// input line as it is typed.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Part {
+    /// A verb that names a command.
+    Verb,
+    /// A verb that names nothing, so the line would fail.
+    UnknownVerb,
+    Argument,
+    /// A `$NAME` to be expanded.
+    Variable,
+    /// Whitespace between words.
+    Space,
+}
+
+impl Part {
+    pub fn color(self, theme: &Theme) -> Color {
+        match self {
+            Part::Verb | Part::Space => theme.foreground,
+            Part::UnknownVerb => theme.error,
+            Part::Argument => ARGUMENT_COLOR,
+            Part::Variable => theme.highlight,
+        }
+    }
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+/// Splits `line` into runs of the parts of the command they are, the verb
+/// being `Part::Verb` if `known` and `Part::UnknownVerb` otherwise. The
+/// runs cover the line exactly as typed, quotes and all. Words are found
+/// the way `tokenize` finds them, and `$NAME` is picked out except inside
+/// single quotes, where it isn't expanded either.
+pub fn classify(line: &str, known: bool) -> Vec<(String, Part)> {
+    let mut runs: Vec<(String, Part)> = Vec::new();
+    let mut push = |c: char, part: Part| match runs.last_mut() {
+        Some((run, last)) if *last == part => run.push(c),
+        _ => runs.push((c.to_string(), part)),
+    };
+    let verb = if known { Part::Verb } else { Part::UnknownVerb };
+    let mut word = verb;
+    let mut in_word = false;
+    let mut quote = None;
+    let mut chars = line.chars().peekable();
+    while let Some(c) = chars.next() {
+        if quote.is_none() && c.is_whitespace() {
+            if in_word {
+                // Only the first word is the verb
+                word = Part::Argument;
+                in_word = false;
+            }
+            push(c, Part::Space);
+            continue;
+        }
+        in_word = true;
+        match (quote, c) {
+            (Some(open), c) if c == open => quote = None,
+            (None, '"' | '\'') => quote = Some(c),
+            (Some('\''), _) => {}
+            (_, '\\') => {
+                push(c, word);
+                if let Some(escaped) = chars.next() {
+                    push(escaped, word);
+                }
+                continue;
+            }
+            (_, '$') if chars.peek().is_some_and(|&next| is_name_char(next)) => {
+                push(c, Part::Variable);
+                while let Some(next) = chars.next_if(|&next| is_name_char(next)) {
+                    push(next, Part::Variable);
+                }
+                continue;
+            }
+            _ => {}
+        }
+        push(c, word);
+    }
+    runs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_parts(line: &str, known: bool, expected: &[(&str, Part)]) {
+        let expected: Vec<(String, Part)> = expected
+            .iter()
+            .map(|(text, part)| (text.to_string(), *part))
+            .collect();
+        assert_eq!(classify(line, known), expected);
+    }
+
+    #[test]
+    fn the_verb_arguments_and_variables_are_told_apart() {
+        assert_parts(
+            "ssh $TARGET  hunter2",
+            true,
+            &[
+                ("ssh", Part::Verb),
+                (" ", Part::Space),
+                ("$TARGET", Part::Variable),
+                ("  ", Part::Space),
+                ("hunter2", Part::Argument),
+            ],
+        );
+        assert_parts(
+            "sshh x",
+            false,
+            &[
+                ("sshh", Part::UnknownVerb),
+                (" ", Part::Space),
+                ("x", Part::Argument),
+            ],
+        );
+        assert_parts("", true, &[]);
+    }
+
+    #[test]
+    fn quotes_are_kept_and_single_quotes_hide_variables() {
+        assert_parts(
+            r#"echo "a $USER" '$HOME' $"#,
+            true,
+            &[
+                ("echo", Part::Verb),
+                (" ", Part::Space),
+                ("\"a ", Part::Argument),
+                ("$USER", Part::Variable),
+                ("\"", Part::Argument),
+                (" ", Part::Space),
+                ("'$HOME'", Part::Argument),
+                (" ", Part::Space),
+                ("$", Part::Argument),
+            ],
+        );
+        assert_parts(
+            r"echo \$x\ y",
+            true,
+            &[
+                ("echo", Part::Verb),
+                (" ", Part::Space),
+                (r"\$x\ y", Part::Argument),
+            ],
+        );
+        let line = r#"echo "unclosed $x"#;
+        let typed: String = classify(line, true).into_iter().map(|(t, _)| t).collect();
+        assert_eq!(typed, line);
+    }
+}
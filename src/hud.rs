@@ -0,0 +1,323 @@
+use bevy::color::Mix;
+use bevy::prelude::*;
+
+use crate::a11y::A11ySettings;
+use crate::commands::GameLogicState;
+use crate::heat::Heat;
+use crate::inventory::format_size;
+use crate::keybindings::{Action, Controls};
+use crate::markup;
+use crate::network::HostStatus;
+use crate::status::{RunnerStatus, MAX_INTEGRITY};
+use crate::terminal::TerminalUi;
+
+/// Marks the corner panel listing every host the runner has discovered.
+#[derive(Component)]
+pub struct NetworkHud;
+
+/// Marks the total size of the loot collected, beside the integrity bar.
+#[derive(Component)]
+pub struct LootHud;
+
+/// Marks the mission's checklist, below the trace countdown.
+#[derive(Component)]
+pub struct ObjectivesHud;
+
+/// Marks the fill of the integrity bar, sized to the runner's integrity.
+#[derive(Component)]
+pub struct IntegrityBar;
+
+/// Marks the fill of the heat meter, sized to how much heat there is.
+#[derive(Component)]
+pub struct HeatBar;
+
+/// The heat meter's colour when cold and when about to set off a trace.
+const COOL: LinearRgba = LinearRgba::rgb(0.0, 0.6, 1.0);
+const HOT: LinearRgba = LinearRgba::rgb(1.0, 0.1, 0.0);
+
+/// Integrity below which the bar turns red.
+const LOW_INTEGRITY: u8 = 30;
+
+/// How a host in the list is coloured and marked. The mark says the same
+/// as the colour, for players who can't tell the colours apart.
+fn status_style(status: HostStatus, colorblind: bool) -> (Color, &'static str) {
+    let (tag, symbol) = match status {
+        HostStatus::Unknown => ("gray", "[?]"),
+        HostStatus::Scanned => ("yellow", "[~]"),
+        HostStatus::Breached => ("green", "[✓]"),
+    };
+    let color = markup::color_named(tag, colorblind).expect("status colours are named tags");
+    (color, symbol)
+}
+
+pub fn setup_hud(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                top: Val::Px(10.0),
+                left: Val::Px(10.0),
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::all(Val::Px(6.0)),
+                ..default()
+            },
+            background_color: Color::srgba(0.0, 0.0, 0.0, 0.6).into(),
+            ..default()
+        },
+        NetworkHud,
+        TerminalUi,
+    ));
+
+    commands.spawn((
+        TextBundle::default().with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(44.0),
+            right: Val::Px(10.0),
+            padding: UiRect::all(Val::Px(6.0)),
+            ..default()
+        }),
+        BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.6)),
+        ObjectivesHud,
+        TerminalUi,
+    ));
+
+    // Loot total and integrity bar in the bottom-right corner
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Px(10.0),
+                    right: Val::Px(10.0),
+                    align_items: AlignItems::Center,
+                    column_gap: Val::Px(6.0),
+                    ..default()
+                },
+                ..default()
+            },
+            TerminalUi,
+        ))
+        .with_children(|hud| {
+            hud.spawn((
+                TextBundle::from_section(
+                    "",
+                    TextStyle {
+                        font: asset_server.load("fonts/FiraMono-Regular.ttf"),
+                        font_size: 16.0,
+                        color: Color::srgb(0.2, 0.9, 1.0),
+                    },
+                ),
+                LootHud,
+            ));
+            hud.spawn(TextBundle::from_section(
+                "HEAT",
+                TextStyle {
+                    font: asset_server.load("fonts/FiraMono-Regular.ttf"),
+                    font_size: 16.0,
+                    color: COOL.into(),
+                },
+            ));
+            hud.spawn(NodeBundle {
+                style: Style {
+                    width: Val::Px(80.0),
+                    height: Val::Px(12.0),
+                    border: UiRect::all(Val::Px(1.0)),
+                    ..default()
+                },
+                border_color: COOL.into(),
+                ..default()
+            })
+            .with_children(|bar| {
+                bar.spawn((
+                    NodeBundle {
+                        style: Style {
+                            width: Val::Percent(0.0),
+                            height: Val::Percent(100.0),
+                            ..default()
+                        },
+                        background_color: COOL.into(),
+                        ..default()
+                    },
+                    HeatBar,
+                ));
+            });
+            hud.spawn(TextBundle::from_section(
+                "INTEGRITY",
+                TextStyle {
+                    font: asset_server.load("fonts/FiraMono-Regular.ttf"),
+                    font_size: 16.0,
+                    color: Color::srgb(0.0, 1.0, 0.0),
+                },
+            ));
+            hud.spawn(NodeBundle {
+                style: Style {
+                    width: Val::Px(120.0),
+                    height: Val::Px(12.0),
+                    border: UiRect::all(Val::Px(1.0)),
+                    ..default()
+                },
+                border_color: Color::srgb(0.0, 1.0, 0.0).into(),
+                ..default()
+            })
+            .with_children(|bar| {
+                bar.spawn((
+                    NodeBundle {
+                        style: Style {
+                            width: Val::Percent(100.0),
+                            height: Val::Percent(100.0),
+                            ..default()
+                        },
+                        background_color: Color::srgb(0.0, 1.0, 0.0).into(),
+                        ..default()
+                    },
+                    IntegrityBar,
+                ));
+            });
+        });
+}
+
+/// Rebuilds the host list whenever the game state, and so possibly the
+/// network, has changed.
+pub fn update_hud(
+    mut commands: Commands,
+    game: Res<GameLogicState>,
+    a11y: Res<A11ySettings>,
+    asset_server: Res<AssetServer>,
+    panel_query: Query<Entity, With<NetworkHud>>,
+) {
+    if !game.is_changed() && !a11y.is_changed() {
+        return;
+    }
+    let Ok(panel) = panel_query.get_single() else {
+        warn_once!("No network HUD to list hosts in");
+        return;
+    };
+    let font = asset_server.load("fonts/FiraMono-Regular.ttf");
+    commands
+        .entity(panel)
+        .despawn_descendants()
+        .with_children(|panel| {
+            for (host, status) in &game.network.discovered {
+                let marker = if *host == game.current_host { ">" } else { "*" };
+                let (color, symbol) = status_style(*status, a11y.colorblind);
+                panel.spawn(TextBundle::from_section(
+                    format!("{} {} {}", marker, symbol, host),
+                    TextStyle {
+                        font: font.clone(),
+                        font_size: 16.0,
+                        color,
+                    },
+                ));
+            }
+        });
+}
+
+pub fn toggle_network_hud(
+    controls: Controls,
+    mut panel_query: Query<&mut Visibility, With<NetworkHud>>,
+) {
+    if controls.just_pressed(Action::ToggleNetworkMap) {
+        let Ok(mut visibility) = panel_query.get_single_mut() else {
+            warn_once!("No network HUD to toggle");
+            return;
+        };
+        *visibility = match *visibility {
+            Visibility::Hidden => Visibility::Inherited,
+            _ => Visibility::Hidden,
+        };
+    }
+}
+
+/// Shows how much loot has been collected, once there is any.
+pub fn update_loot_hud(game: Res<GameLogicState>, mut text_query: Query<&mut Text, With<LootHud>>) {
+    if !game.is_changed() {
+        return;
+    }
+    let Ok(mut text) = text_query.get_single_mut() else {
+        warn_once!("No loot HUD to show the total in");
+        return;
+    };
+    let label = match game.inventory.total_kb() {
+        0 => String::new(),
+        total => format!("LOOT {}", format_size(total)),
+    };
+    if text.sections[0].value != label {
+        text.sections[0].value = label;
+    }
+}
+
+/// Lists the objectives, ticking each off in green once it is done.
+pub fn update_objectives_hud(
+    game: Res<GameLogicState>,
+    asset_server: Res<AssetServer>,
+    mut text_query: Query<&mut Text, With<ObjectivesHud>>,
+) {
+    if !game.is_changed() {
+        return;
+    }
+    let Ok(mut text) = text_query.get_single_mut() else {
+        warn_once!("No objectives HUD to list the mission in");
+        return;
+    };
+    let font = asset_server.load("fonts/FiraMono-Regular.ttf");
+    let objectives = &game.mission.objectives;
+    let sections = game
+        .mission
+        .checklist()
+        .into_iter()
+        .zip(objectives)
+        .enumerate()
+        .map(|(i, (line, objective))| {
+            let newline = if i + 1 < objectives.len() { "\n" } else { "" };
+            TextSection::new(
+                format!("{}{}", line, newline),
+                TextStyle {
+                    font: font.clone(),
+                    font_size: 16.0,
+                    color: if objective.done {
+                        Color::srgb(0.0, 1.0, 0.0)
+                    } else {
+                        Color::srgb(0.7, 0.7, 0.7)
+                    },
+                },
+            )
+        })
+        .collect();
+    text.sections = sections;
+}
+
+/// Fills the heat meter as heat builds, shading it from cool blue to red.
+pub fn update_heat_hud(
+    heat: Res<Heat>,
+    mut bar_query: Query<(&mut Style, &mut BackgroundColor), With<HeatBar>>,
+) {
+    if !heat.is_changed() {
+        return;
+    }
+    let Ok((mut style, mut color)) = bar_query.get_single_mut() else {
+        warn_once!("No heat meter to update");
+        return;
+    };
+    style.width = Val::Percent(100.0 * heat.fraction());
+    *color = Color::from(COOL.mix(&HOT, heat.fraction())).into();
+}
+
+pub fn update_integrity_hud(
+    runner: Res<RunnerStatus>,
+    mut bar_query: Query<(&mut Style, &mut BackgroundColor), With<IntegrityBar>>,
+) {
+    if !runner.is_changed() {
+        return;
+    }
+    let Ok((mut style, mut color)) = bar_query.get_single_mut() else {
+        warn_once!("No integrity bar to update");
+        return;
+    };
+    style.width = Val::Percent(100.0 * f32::from(runner.integrity) / f32::from(MAX_INTEGRITY));
+    *color = if runner.integrity < LOW_INTEGRITY {
+        Color::srgb(1.0, 0.0, 0.0)
+    } else {
+        Color::srgb(0.0, 1.0, 0.0)
+    }
+    .into();
+}
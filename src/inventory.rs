@@ -0,0 +1,104 @@
+use serde::{Deserialize, Serialize};
+
+/// Something the runner has pulled off the network.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct LootItem {
+    pub name: String,
+    pub size_kb: u64,
+    pub description: String,
+}
+
+/// Loot collected this run.
+#[derive(Default, Clone, PartialEq, Debug)]
+pub struct Inventory {
+    pub items: Vec<LootItem>,
+}
+
+/// Items the client is paying for; holding them all completes the mission.
+pub const MISSION_LOOT: &[&str] = &["customer-records"];
+
+/// Secret files worth keeping, by host and absolute path, and the item
+/// reading one yields.
+const SECRET_FILES: &[(&str, &str, &str, u64, &str)] = &[
+    (
+        "dev.neotechlabs.com",
+        "/home/dev/todo.txt",
+        "dev-todo",
+        2,
+        "Dev notes with the mail admin password",
+    ),
+    (
+        "mail.neotechlabs.com",
+        "/var/mail/admin",
+        "vault-memo",
+        4,
+        "IT memo with the vault password",
+    ),
+    (
+        "vault.neotechlabs.com",
+        "/data/archive.cred",
+        "archive-creds",
+        1,
+        "Decrypted login for the NeoTech archive",
+    ),
+];
+
+/// What `wget data` brings down.
+pub fn customer_records() -> LootItem {
+    LootItem {
+        name: "customer-records".to_string(),
+        size_kb: 500 * 1024,
+        description: "NeoTech customer records from the vault".to_string(),
+    }
+}
+
+/// The item hidden in the file at `path` on `host`, if it is a secret one.
+pub fn secret_file(host: &str, path: &str) -> Option<LootItem> {
+    SECRET_FILES
+        .iter()
+        .find(|(file_host, file_path, ..)| *file_host == host && *file_path == path)
+        .map(|(_, _, name, size_kb, description)| LootItem {
+            name: name.to_string(),
+            size_kb: *size_kb,
+            description: description.to_string(),
+        })
+}
+
+/// `2 KB`, or `500.0 MB` from a megabyte up.
+pub fn format_size(size_kb: u64) -> String {
+    if size_kb < 1024 {
+        format!("{} KB", size_kb)
+    } else {
+        format!("{:.1} MB", size_kb as f64 / 1024.0)
+    }
+}
+
+impl Inventory {
+    /// Adds `item` unless one with its name is already held. Returns
+    /// whether it was new.
+    pub fn add(&mut self, item: LootItem) -> bool {
+        if self.has(&item.name) {
+            return false;
+        }
+        self.items.push(item);
+        true
+    }
+
+    pub fn has(&self, name: &str) -> bool {
+        self.items.iter().any(|item| item.name == name)
+    }
+
+    pub fn total_kb(&self) -> u64 {
+        self.items.iter().map(|item| item.size_kb).sum()
+    }
+
+    pub fn mission_complete(&self) -> bool {
+        MISSION_LOOT.iter().all(|name| self.has(name))
+    }
+
+    /// Whether every item there is to find, the mission's and every secret
+    /// file's, has been collected.
+    pub fn has_everything(&self) -> bool {
+        self.mission_complete() && SECRET_FILES.iter().all(|(_, _, name, ..)| self.has(name))
+    }
+}
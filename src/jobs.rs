@@ -0,0 +1,92 @@
+use bevy::prelude::*;
+use std::time::Duration;
+
+use crate::locale::{tr, Locale};
+use crate::output::OutputLine;
+
+/// Seconds a command takes when run in the background with `&`. Anything
+/// not listed takes `DEFAULT_JOB_SECONDS`.
+const JOB_SECONDS: &[(&str, f32)] = &[("wget", 8.0), ("exploit", 4.0)];
+const DEFAULT_JOB_SECONDS: f32 = 2.0;
+
+fn job_seconds(verb: &str) -> f32 {
+    JOB_SECONDS
+        .iter()
+        .find(|(name, _)| *name == verb)
+        .map_or(DEFAULT_JOB_SECONDS, |(_, seconds)| *seconds)
+}
+
+/// A command running in the background. It runs for real once its timer
+/// finishes, and its output is shown then.
+pub struct Job {
+    pub id: usize,
+    pub command: String,
+    pub timer: Timer,
+    /// The host it was started on, which it runs against however far the
+    /// runner has gone since.
+    pub host: String,
+    /// The directory it was started in, likewise.
+    pub cwd: String,
+}
+
+impl Job {
+    pub fn percent(&self) -> u32 {
+        (self.timer.fraction() * 100.0) as u32
+    }
+}
+
+/// Jobs started with `&`, oldest first.
+#[derive(Default)]
+pub struct BackgroundJobs(pub Vec<Job>);
+
+impl BackgroundJobs {
+    /// Starts `command`, whose verb is `verb`, on `host` in `cwd`, and
+    /// returns its job number. Numbers count up from 1 and start over once
+    /// no jobs are left.
+    pub fn start(&mut self, command: &str, verb: &str, host: &str, cwd: &str) -> usize {
+        let id = self.0.iter().map(|job| job.id).max().unwrap_or(0) + 1;
+        self.0.push(Job {
+            id,
+            command: command.to_string(),
+            timer: Timer::from_seconds(job_seconds(verb), TimerMode::Once),
+            host: host.to_string(),
+            cwd: cwd.to_string(),
+        });
+        id
+    }
+
+    pub fn tick(&mut self, delta: Duration) {
+        for job in &mut self.0 {
+            job.timer.tick(delta);
+        }
+    }
+
+    /// Removes and returns the oldest job that has finished, if any.
+    pub fn take_finished(&mut self) -> Option<Job> {
+        let index = self.0.iter().position(|job| job.timer.finished())?;
+        Some(self.0.remove(index))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Handles `jobs`, listing every job with how far along it is.
+    pub fn list(&self, locale: &Locale) -> Vec<OutputLine> {
+        if self.0.is_empty() {
+            return vec![OutputLine::info(locale.get("jobs.none"))];
+        }
+        self.0
+            .iter()
+            .map(|job| {
+                OutputLine::Info(tr!(
+                    locale,
+                    "jobs.entry",
+                    id = job.id,
+                    percent = job.percent(),
+                    command = job.command
+                ))
+            })
+            .collect()
+    }
+}
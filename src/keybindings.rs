@@ -0,0 +1,154 @@
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+
+use crate::assets::asset_path;
+use crate::gamepad::{PadAction, PadBindings};
+
+/// The Ctrl chords and what each does, as a locale key. They are fixed
+/// rather than bound in `keybindings.ron`, which maps single keys. Ctrl+C
+/// interrupts as in any terminal, so copying the input line is Ctrl+Shift+C.
+pub const CHORDS: &[(&str, &str)] = &[
+    ("Ctrl+C", "keys.interrupt"),
+    ("Ctrl+Shift+C", "keys.copy"),
+    ("Ctrl+V", "keys.paste"),
+    ("Ctrl+L", "keys.clear"),
+    ("Ctrl+1..9, Ctrl+Tab", "keys.sessions"),
+];
+
+/// Everything the terminal can be told to do from the keyboard, other than typing.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    Submit,
+    Backspace,
+    Delete,
+    CursorLeft,
+    CursorRight,
+    LineStart,
+    LineEnd,
+    HistoryPrev,
+    HistoryNext,
+    Complete,
+    ScrollUp,
+    ScrollDown,
+    ToggleNetworkMap,
+    ToggleHighContrast,
+    ToggleFpsOverlay,
+    ToggleColorblindMode,
+    ToggleFullscreen,
+}
+
+impl Action {
+    /// What a gamepad does the same with, if a pad can do it at all.
+    fn on_pad(self) -> Option<PadAction> {
+        match self {
+            Action::Submit => Some(PadAction::Submit),
+            Action::HistoryPrev => Some(PadAction::HistoryPrev),
+            Action::HistoryNext => Some(PadAction::HistoryNext),
+            Action::ToggleNetworkMap => Some(PadAction::ToggleNetworkMap),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Resource)]
+pub struct KeyBindings {
+    bindings: HashMap<Action, KeyCode>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        KeyBindings {
+            bindings: HashMap::from([
+                (Action::Submit, KeyCode::Enter),
+                (Action::Backspace, KeyCode::Backspace),
+                (Action::Delete, KeyCode::Delete),
+                (Action::CursorLeft, KeyCode::ArrowLeft),
+                (Action::CursorRight, KeyCode::ArrowRight),
+                (Action::LineStart, KeyCode::Home),
+                (Action::LineEnd, KeyCode::End),
+                (Action::HistoryPrev, KeyCode::ArrowUp),
+                (Action::HistoryNext, KeyCode::ArrowDown),
+                (Action::Complete, KeyCode::Tab),
+                (Action::ScrollUp, KeyCode::PageUp),
+                (Action::ScrollDown, KeyCode::PageDown),
+                (Action::ToggleNetworkMap, KeyCode::F2),
+                (Action::ToggleHighContrast, KeyCode::F4),
+                (Action::ToggleFpsOverlay, KeyCode::F3),
+                (Action::ToggleColorblindMode, KeyCode::F5),
+                (Action::ToggleFullscreen, KeyCode::F11),
+            ]),
+        }
+    }
+}
+
+impl KeyBindings {
+    pub fn key(&self, action: Action) -> KeyCode {
+        self.bindings[&action]
+    }
+
+    /// Whether `key` triggers an action, and so shouldn't also type a character.
+    pub fn is_bound(&self, key: KeyCode) -> bool {
+        self.bindings.values().any(|bound| *bound == key)
+    }
+
+    pub fn just_pressed(&self, keys: &ButtonInput<KeyCode>, action: Action) -> bool {
+        keys.just_pressed(self.key(action))
+    }
+
+    /// Defaults overridden by whatever `assets/keybindings.ron` specifies.
+    /// The file is optional and may bind only some actions, e.g.
+    /// `{ submit: NumpadEnter, history_prev: F1 }`.
+    pub fn load() -> Self {
+        let mut keybindings = KeyBindings::default();
+        let path = asset_path("keybindings.ron");
+        if let Ok(contents) = fs::read_to_string(&path) {
+            match ron::from_str::<HashMap<Action, KeyCode>>(&contents) {
+                Ok(overrides) => keybindings.bindings.extend(overrides),
+                Err(err) => warn!("Ignoring invalid {}: {}", path.display(), err),
+            }
+        }
+        keybindings.warn_on_conflicts();
+        keybindings
+    }
+
+    fn warn_on_conflicts(&self) {
+        let mut by_key: HashMap<KeyCode, Vec<Action>> = HashMap::new();
+        for (action, key) in &self.bindings {
+            by_key.entry(*key).or_default().push(*action);
+        }
+        for (key, actions) in by_key {
+            if actions.len() > 1 {
+                warn!("{:?} is bound to more than one action: {:?}", key, actions);
+            }
+        }
+    }
+}
+
+/// The keyboard and any gamepads, each read through its bindings, so an
+/// action fires whichever of them the player reached for.
+#[derive(SystemParam)]
+pub struct Controls<'w> {
+    pub keys: Res<'w, ButtonInput<KeyCode>>,
+    pub bindings: Res<'w, KeyBindings>,
+    buttons: Res<'w, ButtonInput<GamepadButton>>,
+    pad_bindings: Res<'w, PadBindings>,
+}
+
+impl Controls<'_> {
+    /// Whether `action` was just pressed on the keyboard or on a pad. Both
+    /// at once still count once.
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.bindings.just_pressed(&self.keys, action)
+            || action
+                .on_pad()
+                .is_some_and(|action| self.pad_just_pressed(action))
+    }
+
+    pub fn pad_just_pressed(&self, action: PadAction) -> bool {
+        self.pad_bindings.just_pressed(&self.buttons, action)
+    }
+}
@@ -0,0 +1,192 @@
+use bevy::prelude::*;
+
+use crate::markup::{split_at_width, visible_len, FONT_SIZE};
+
+/// Height of the terminal background quad, in world units; its width
+/// follows the window's aspect ratio.
+pub const TERMINAL_HEIGHT: f32 = 3.0;
+/// Centre of the terminal background quad.
+pub const TERMINAL_CENTER: Vec3 = Vec3::new(0.0, 1.5, 0.0);
+/// Gap between the edge of the quad and the text, in world units.
+pub const TERMINAL_MARGIN: f32 = 0.2;
+/// Window size the app opens with.
+pub const DEFAULT_WINDOW: Vec2 = Vec2::new(800.0, 600.0);
+/// How much taller than the quad the camera's view is, so it never touches the edges.
+const FRAME_PADDING: f32 = 1.1;
+/// Advance width of a FiraMono glyph as a fraction of the font size.
+const GLYPH_ASPECT: f32 = 0.6;
+/// Share of the text area the terminal gets; the side log has the rest.
+pub const DEFAULT_SPLIT: f32 = 0.7;
+/// The side log is ambient, so it is set smaller than the terminal.
+pub const LOG_FONT_SIZE: f32 = 16.0;
+
+/// Where the terminal sits and how much text fits in it for the current
+/// window size.
+#[derive(Resource)]
+pub struct TerminalLayout {
+    /// Size of the background quad, in world units.
+    pub size: Vec2,
+    /// Fraction of the width between the margins given to the terminal,
+    /// left of the side log.
+    pub split: f32,
+    /// How many characters of output fit across the terminal.
+    pub columns: usize,
+    /// How many characters fit across the side log.
+    pub log_columns: usize,
+    /// Multiplies the terminal and side log font sizes.
+    pub font_scale: f32,
+}
+
+impl Default for TerminalLayout {
+    fn default() -> Self {
+        TerminalLayout::new(DEFAULT_WINDOW, 1.0, DEFAULT_SPLIT)
+    }
+}
+
+impl TerminalLayout {
+    pub fn new(window: Vec2, font_scale: f32, split: f32) -> Self {
+        let aspect = window.x / window.y.max(1.0);
+        let size = Vec2::new(TERMINAL_HEIGHT * aspect, TERMINAL_HEIGHT);
+        let pixels_per_unit = pixels_per_unit(window.y);
+        let columns_in = |width: f32, font_size: f32| {
+            ((width * pixels_per_unit / (font_size * GLYPH_ASPECT)) as usize).max(1)
+        };
+        let split = split.clamp(0.0, 1.0);
+        let text_width = size.x - 3.0 * TERMINAL_MARGIN;
+        TerminalLayout {
+            size,
+            split,
+            columns: columns_in(text_width * split, FONT_SIZE * font_scale),
+            log_columns: columns_in(text_width * (1.0 - split), LOG_FONT_SIZE * font_scale),
+            font_scale,
+        }
+    }
+
+    pub fn font_size(&self) -> f32 {
+        FONT_SIZE * self.font_scale
+    }
+
+    pub fn log_font_size(&self) -> f32 {
+        LOG_FONT_SIZE * self.font_scale
+    }
+
+    /// Camera placement that frames the whole quad with a little padding,
+    /// for a perspective camera with the default field of view.
+    pub fn camera_transform(&self) -> Transform {
+        let fov = PerspectiveProjection::default().fov;
+        let distance = self.size.y * FRAME_PADDING / 2.0 / (fov / 2.0).tan();
+        Transform::from_translation(TERMINAL_CENTER + Vec3::Z * distance)
+            .looking_at(TERMINAL_CENTER, Vec3::Y)
+    }
+
+    /// Size in pixels of a texture covering the quad as sharply as a
+    /// window `window_height` pixels tall shows it.
+    pub fn screen_resolution(&self, window_height: f32) -> UVec2 {
+        (self.size * pixels_per_unit(window_height))
+            .as_uvec2()
+            .max(UVec2::ONE)
+    }
+
+    /// Placement of the unit background quad, scaled to `size`.
+    pub fn background_transform(&self) -> Transform {
+        Transform::from_translation(TERMINAL_CENTER).with_scale(self.size.extend(1.0))
+    }
+
+    /// Bottom-left corner of the text area, where the prompt line sits.
+    pub fn text_origin(&self) -> Vec3 {
+        TERMINAL_CENTER
+            + Vec3::new(
+                -self.size.x / 2.0 + TERMINAL_MARGIN,
+                -self.size.y / 2.0 + TERMINAL_MARGIN,
+                0.1,
+            )
+    }
+
+    /// Bottom-left corner of the side log, one margin right of the terminal.
+    pub fn log_origin(&self) -> Vec3 {
+        let text_width = self.size.x - 3.0 * TERMINAL_MARGIN;
+        self.text_origin() + Vec3::X * (text_width * self.split + TERMINAL_MARGIN)
+    }
+
+    /// Breaks one line of markup into terminal lines.
+    pub fn wrap(&self, line: &str) -> Vec<String> {
+        wrap(line, self.columns)
+    }
+
+    /// Breaks one line of markup into side log lines.
+    pub fn wrap_log(&self, line: &str) -> Vec<String> {
+        wrap(line, self.log_columns)
+    }
+}
+
+/// Screen pixels per world unit at the quad's depth.
+fn pixels_per_unit(window_height: f32) -> f32 {
+    window_height / (TERMINAL_HEIGHT * FRAME_PADDING)
+}
+
+/// Breaks one line of markup into lines no wider than `columns`, preferring
+/// spaces and hard-breaking words that can't fit on any line.
+fn wrap(line: &str, columns: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut current = String::new();
+    let mut width = 0;
+    for (i, word) in line.split(' ').enumerate() {
+        let word_width = visible_len(word);
+        if i > 0 {
+            // A word too long for any line is split here rather than
+            // leaving a stub like `>` behind on its own
+            if width < columns && (width + 1 + word_width <= columns || word_width > columns) {
+                current.push(' ');
+                width += 1;
+            } else {
+                lines.push(std::mem::take(&mut current));
+                width = 0;
+            }
+        }
+        let mut rest = word;
+        while width + visible_len(rest) > columns {
+            let (head, tail) = split_at_width(rest, columns - width);
+            current += head;
+            lines.push(std::mem::take(&mut current));
+            width = 0;
+            rest = tail;
+        }
+        current += rest;
+        width += visible_len(rest);
+    }
+    lines.push(current);
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Pixels the quad covers in a window of `window`, as the camera
+    /// `camera_transform` places sees it.
+    fn quad_on_screen(layout: &TerminalLayout, window: Vec2) -> Vec2 {
+        let fov = PerspectiveProjection::default().fov;
+        let distance = layout
+            .camera_transform()
+            .translation
+            .distance(TERMINAL_CENTER);
+        let visible_height = 2.0 * distance * (fov / 2.0).tan();
+        layout.size * window.y / visible_height
+    }
+
+    #[test]
+    fn the_screen_texture_matches_the_quad_on_screen() {
+        // 16:9 and 21:9
+        for window in [Vec2::new(1920.0, 1080.0), Vec2::new(2560.0, 1080.0)] {
+            let layout = TerminalLayout::new(window, 1.0, DEFAULT_SPLIT);
+            let texture = layout.screen_resolution(window.y).as_vec2();
+            let quad = quad_on_screen(&layout, window);
+            assert!(
+                (texture - quad).abs().max_element() <= 1.0,
+                "{} vs {}",
+                texture,
+                quad
+            );
+        }
+    }
+}
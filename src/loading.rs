@@ -0,0 +1,102 @@
+use bevy::asset::LoadState;
+use bevy::prelude::*;
+use std::path::Path;
+
+use crate::commands::GameLogicState;
+use crate::layout::TerminalLayout;
+use crate::locale::tr;
+use crate::terminal::PendingOutput;
+use crate::world::DEFAULT_SCENE;
+
+/// The environment scene while it streams in. Removed once it has loaded or
+/// failed, which also stops `track_environment_load`.
+#[derive(Resource)]
+pub struct AssetLoadState {
+    pub scene: Handle<Scene>,
+}
+
+/// Marks the scene entity, hidden until its asset is ready so it never
+/// pops in half-built.
+#[derive(Component)]
+pub struct Environment;
+
+/// Marks the full-screen "LOADING ENVIRONMENT..." overlay.
+#[derive(Component)]
+pub struct LoadingOverlay;
+
+pub fn setup_loading_overlay(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: Color::srgba(0.0, 0.0, 0.0, 0.9).into(),
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+            LoadingOverlay,
+        ))
+        .with_children(|overlay| {
+            overlay.spawn(TextBundle::from_section(
+                "LOADING ENVIRONMENT...",
+                TextStyle {
+                    font: asset_server.load("fonts/FiraMono-Regular.ttf"),
+                    font_size: 28.0,
+                    color: Color::srgb(0.0, 1.0, 0.0),
+                },
+            ));
+        });
+}
+
+/// Reveals the scene once it has loaded. If a configured scene can't be
+/// loaded the hideout is tried instead, and if that fails too the
+/// terminal says so instead of leaving an empty room.
+pub fn track_environment_load(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    load: Res<AssetLoadState>,
+    layout: Res<TerminalLayout>,
+    game: Res<GameLogicState>,
+    mut pending: ResMut<PendingOutput>,
+    overlays: Query<Entity, With<LoadingOverlay>>,
+    mut environments: Query<(&mut Visibility, &mut Handle<Scene>), With<Environment>>,
+) {
+    match asset_server.get_load_state(&load.scene) {
+        Some(LoadState::Loaded) => {
+            for (mut visibility, _) in &mut environments {
+                *visibility = Visibility::Inherited;
+            }
+        }
+        Some(LoadState::Failed(err))
+            if load.scene.path().map(|path| path.path()) != Some(Path::new(DEFAULT_SCENE)) =>
+        {
+            error!(
+                "Failed to load {:?}, falling back to {}: {}",
+                load.scene.path(),
+                DEFAULT_SCENE,
+                err
+            );
+            let fallback: Handle<Scene> = asset_server.load(DEFAULT_SCENE);
+            for (_, mut scene) in &mut environments {
+                *scene = fallback.clone();
+            }
+            commands.insert_resource(AssetLoadState { scene: fallback });
+            return;
+        }
+        Some(LoadState::Failed(err)) => {
+            error!("Failed to load environment: {}", err);
+            pending.push(&tr!(game.locale, "loading.failed", error = err), &layout);
+        }
+        _ => return,
+    }
+    for overlay in &overlays {
+        commands.entity(overlay).despawn_recursive();
+    }
+    commands.remove_resource::<AssetLoadState>();
+}
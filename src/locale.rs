@@ -0,0 +1,110 @@
+use bevy::prelude::*;
+use std::collections::HashMap;
+use std::fs;
+use std::sync::OnceLock;
+
+use crate::assets::asset_path;
+
+/// The canonical strings, built in so English always works. Every key a
+/// translation can set is in here.
+const ENGLISH: &str = include_str!("../assets/locales/en.ron");
+
+fn english() -> &'static HashMap<String, String> {
+    static STRINGS: OnceLock<HashMap<String, String>> = OnceLock::new();
+    STRINGS.get_or_init(|| ron::from_str(ENGLISH).expect("assets/locales/en.ron is valid RON"))
+}
+
+/// Why a language couldn't be selected.
+pub enum LocaleError {
+    Unknown,
+    Invalid { path: String, error: String },
+}
+
+/// User-facing text in the selected language. Keys a translation leaves
+/// out fall back to English.
+pub struct Locale {
+    pub code: String,
+    strings: HashMap<String, String>,
+}
+
+impl Default for Locale {
+    fn default() -> Self {
+        Locale {
+            code: "en".to_string(),
+            strings: HashMap::new(),
+        }
+    }
+}
+
+impl Locale {
+    /// The translation in `assets/locales/<code>.ron`. Keys it is missing
+    /// are logged for translators.
+    pub fn load(code: &str) -> Result<Self, LocaleError> {
+        if code == "en" {
+            return Ok(Locale::default());
+        }
+        // Keep the code from reaching outside the locales directory
+        if code.is_empty() || !code.chars().all(|c| c.is_ascii_alphanumeric() || c == '-') {
+            return Err(LocaleError::Unknown);
+        }
+        let path = asset_path(&format!("locales/{}.ron", code));
+        let contents = fs::read_to_string(&path).map_err(|_| LocaleError::Unknown)?;
+        let strings: HashMap<String, String> =
+            ron::from_str(&contents).map_err(|err| LocaleError::Invalid {
+                path: path.display().to_string(),
+                error: err.to_string(),
+            })?;
+        let mut missing: Vec<&str> = english()
+            .keys()
+            .filter(|key| !strings.contains_key(*key))
+            .map(String::as_str)
+            .collect();
+        if !missing.is_empty() {
+            missing.sort_unstable();
+            warn!(
+                "{} has no translation for {} keys, using English: {}",
+                path.display(),
+                missing.len(),
+                missing.join(", ")
+            );
+        }
+        Ok(Locale {
+            code: code.to_string(),
+            strings,
+        })
+    }
+
+    /// The text for `key`, if this language or English has one.
+    pub fn try_get(&self, key: &str) -> Option<&str> {
+        self.strings
+            .get(key)
+            .or_else(|| english().get(key))
+            .map(String::as_str)
+    }
+
+    /// The text for `key`. A key missing even from English is a bug, and
+    /// shows up as the key itself.
+    pub fn get<'a>(&'a self, key: &'a str) -> &'a str {
+        self.try_get(key).unwrap_or_else(|| {
+            error!("No string for locale key {}", key);
+            key
+        })
+    }
+
+    /// The text for `key` with each `{name}` replaced by its value.
+    pub fn format(&self, key: &str, args: &[(&str, String)]) -> String {
+        let mut text = self.get(key).to_string();
+        for (name, value) in args {
+            text = text.replace(&format!("{{{}}}", name), value);
+        }
+        text
+    }
+}
+
+/// Looks up a message, e.g. `tr!(game.locale, "ssh.connected", host = host)`.
+macro_rules! tr {
+    ($locale:expr, $key:expr $(, $name:ident = $value:expr)* $(,)?) => {
+        $locale.format($key, &[$((stringify!($name), $value.to_string())),*])
+    };
+}
+pub(crate) use tr;
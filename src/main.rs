@@ -1,45 +1,219 @@
+use bevy::ecs::system::RunSystemOnce;
 use bevy::prelude::*;
+use boot::BootState;
+use pause::RunState;
+mod a11y;
+mod achievements;
+mod aliases;
+mod assets;
+mod audio;
+mod banner;
+mod boot;
+mod camera;
+mod clipboard;
+mod commands;
+mod config;
+mod crt;
+mod difficulty;
+mod events;
+mod filesystem;
+mod fps;
+mod gamepad;
+mod glitch;
+mod headless;
+mod heat;
+mod highlight;
+mod hud;
+mod inventory;
+mod jobs;
+mod keybindings;
+mod layout;
+mod loading;
+mod locale;
+mod markup;
+mod missions;
+mod network;
+mod output;
+mod pause;
+mod pipes;
+mod puzzle;
+mod replay;
+mod rng;
+mod save;
+mod screen;
+mod scripts;
+mod search;
+mod selection;
+mod sessions;
+mod settings;
+mod shellenv;
+mod sidelog;
+mod status;
 mod terminal;
+mod theme;
+mod tokenize;
+mod transcript;
+mod transition;
+mod visuals;
+mod window;
+mod world;
 
 fn main() {
-    App::new()
-        .add_plugins(DefaultPlugins.set(WindowPlugin {
-            primary_window: Some(Window {
-                title: "Data Heist at NeoTech Labs".into(),
-                resolution: (800., 600.).into(),
-                ..default()
-            }),
+    let loaded = config::LoadedConfig::load();
+    loaded.log_overrides();
+    let transcript_enabled =
+        loaded.config.game.transcript && std::env::var_os(transcript::DISABLE_ENV).is_none();
+    let transcript = if transcript_enabled {
+        transcript::TranscriptLogger::open()
+    } else {
+        transcript::TranscriptLogger::default()
+    };
+    let settings_path = settings::settings_path();
+    let settings = settings::Settings::load(settings_path.as_deref());
+    let mut registry = commands::CommandRegistry::default();
+    transcript::register_command(&mut registry, transcript.path());
+    let game = commands::GameLogicState {
+        difficulty: loaded.config.game.difficulty.into(),
+        locale: loaded.config.locale(),
+        rng: rng::GameRng::from_seed(loaded.config.game.seed),
+        conditions: network::NetworkConditions::load(),
+        mission: missions::Mission::load(),
+        prefix_matching: settings.prefix_matching,
+        config: loaded,
+        ..default()
+    };
+
+    if std::env::args().any(|arg| arg == "--headless") {
+        headless::run(registry, transcript, game);
+        return;
+    }
+
+    let mut app = App::new();
+    app.add_plugins(DefaultPlugins.set(WindowPlugin {
+        primary_window: Some(Window {
+            title: window::DEFAULT_TITLE.into(),
+            resolution: layout::DEFAULT_WINDOW.into(),
+            mode: settings.display_mode.window_mode(),
             ..default()
-        }))
-        .add_plugins(bevy::gltf::GltfPlugin) // Explicitly add GLTF support
-        .add_systems(Startup, (setup_camera, setup_world, terminal::setup_terminal))
-        .add_systems(Update, (terminal::handle_input, terminal::update_terminal))
-        .run();
+        }),
+        // Closing is an exit like any other, so the session is saved first
+        close_when_requested: false,
+        ..default()
+    }))
+    .add_plugins((crt::CrtPlugin, pause::PausePlugin, fps::FpsOverlayPlugin))
+    .insert_resource(terminal::TerminalConfig {
+        output_speed: settings.output_speed,
+        display_mode: settings.display_mode,
+        // A hand-edited file could otherwise hide the text altogether
+        opacity: settings.opacity.clamp(commands::MIN_OPACITY, 100),
+        settings_path,
+        connect_transition: game.config.config.transition(),
+        ..default()
+    })
+    .insert_resource(registry)
+    .insert_resource(transcript)
+    .insert_resource(game)
+    .add_plugins(terminal::TerminalPlugin::default())
+    .insert_resource(achievements::Achievements::load())
+    .insert_resource(world::WorldConfig::load())
+    .add_systems(
+        Startup,
+        (
+            setup_camera,
+            world::setup_world,
+            world::setup_light,
+            audio::load_audio,
+            hud::setup_hud,
+            loading::setup_loading_overlay,
+        ),
+    )
+    .add_systems(
+        Update,
+        (
+            // Input stops while paused, the terminal's as well
+            (
+                gamepad::scroll_with_stick,
+                hud::toggle_network_hud,
+                a11y::adjust_a11y,
+            )
+                .run_if(in_state(RunState::Running)),
+            loading::track_environment_load.run_if(resource_exists::<loading::AssetLoadState>),
+            camera::animate_camera_intro,
+            visuals::sync_visual_settings,
+            a11y::apply_font_scale,
+            (
+                hud::update_hud,
+                hud::update_integrity_hud,
+                hud::update_heat_hud,
+                hud::update_loot_hud,
+                hud::update_objectives_hud,
+            ),
+            (
+                window::update_window_title,
+                window::set_window_icon,
+                window::toggle_fullscreen,
+                window::exit_on_close,
+                window::apply_display_mode.after(window::toggle_fullscreen),
+            ),
+            (audio::play_command_sounds, audio::update_trace_alarm),
+            (
+                achievements::award_achievements,
+                achievements::animate_toasts,
+            ),
+            respawn_terminal_ui.after(terminal::reset_run),
+            (
+                world::tag_scene_nodes,
+                world::wire_neon_signs,
+                world::spin_fans,
+                world::flicker_neon,
+            )
+                .chain(),
+        ),
+    )
+    .run();
 }
 
-fn setup_camera(mut commands: Commands) {
-    commands.spawn(Camera3dBundle {
-        transform: Transform::from_xyz(0.0, 1.5, 5.0).looking_at(Vec3::ZERO, Vec3::Y),
-        ..default()
+/// Replaces the terminal and HUD with fresh ones after a reset, and plays
+/// the boot sequence again.
+fn respawn_terminal_ui(
+    mut commands: Commands,
+    mut resets: EventReader<events::ResetRequested>,
+    ui: Query<Entity, With<terminal::TerminalUi>>,
+    mut boot: ResMut<NextState<BootState>>,
+) {
+    if resets.read().count() == 0 {
+        return;
+    }
+    for entity in &ui {
+        commands.entity(entity).despawn_recursive();
+    }
+    commands.remove_resource::<terminal::TerminalInitialized>();
+    commands.insert_resource(search::SearchState::default());
+    commands.add(|world: &mut World| {
+        world.run_system_once(terminal::setup_terminal);
+        world.run_system_once(sidelog::setup_side_log);
+        world.run_system_once(hud::setup_hud);
+        let rows = world.resource::<a11y::A11ySettings>().visible_rows();
+        world
+            .resource_mut::<terminal::ScrollbackState>()
+            .set_rows(rows);
     });
+    boot.set(BootState::Booting);
 }
 
-fn setup_world(mut commands: Commands, asset_server: Res<AssetServer>) {
-    info!("Loading hideout.glb...");
-    let scene_handle = asset_server.load("models/hideout.glb");
-    info!("Scene handle: {:?}", scene_handle.path());
-    commands.spawn(SceneBundle {
-        scene: scene_handle,
-        transform: Transform::from_xyz(0.0, 0.0, 0.0),
-        ..default()
-    });
-    commands.spawn(PointLightBundle {
-        transform: Transform::from_xyz(0.0, 5.0, 0.0),
-        point_light: PointLight {
-            intensity: 1500.0,
-            shadows_enabled: true,
+fn setup_camera(mut commands: Commands) {
+    let intro = camera::CameraIntro::new(layout::TerminalLayout::default().camera_transform());
+    commands.spawn((
+        Camera3dBundle {
+            camera: Camera {
+                // HDR lets over-bright text bloom
+                hdr: true,
+                ..default()
+            },
+            transform: intro.start,
             ..default()
         },
-        ..default()
-    });
-}
\ No newline at end of file
+        crt::CrtUniform::default(),
+        intro,
+    ));
+}
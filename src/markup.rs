@@ -0,0 +1,159 @@
+use bevy::prelude::*;
+
+use crate::theme::Theme;
+
+/// Colour of menu text, and of output text under the classic theme.
+pub const DEFAULT_COLOR: Color = Color::srgb(0.0, 1.0, 0.0);
+pub const FONT_SIZE: f32 = 24.0;
+
+/// The colour a tag names, from the Okabe-Ito palette under `colorblind`,
+/// whose colours stay distinct with any common colour blindness.
+pub fn color_named(name: &str, colorblind: bool) -> Option<Color> {
+    if colorblind {
+        return Some(match name {
+            "red" => Color::srgb(0.84, 0.37, 0.0),
+            "green" => Color::srgb(0.0, 0.62, 0.45),
+            "yellow" => Color::srgb(0.94, 0.89, 0.26),
+            "cyan" => Color::srgb(0.34, 0.71, 0.91),
+            "blue" => Color::srgb(0.0, 0.45, 0.7),
+            "magenta" => Color::srgb(0.8, 0.47, 0.65),
+            "white" => Color::WHITE,
+            "gray" => Color::srgb(0.5, 0.5, 0.5),
+            _ => return None,
+        });
+    }
+    Some(match name {
+        "red" => Color::srgb(1.0, 0.2, 0.2),
+        "green" => Color::srgb(0.0, 1.0, 0.0),
+        "yellow" => Color::srgb(1.0, 0.9, 0.2),
+        "cyan" => Color::srgb(0.2, 0.9, 1.0),
+        "blue" => Color::srgb(0.3, 0.5, 1.0),
+        "magenta" => Color::srgb(1.0, 0.3, 1.0),
+        "white" => Color::WHITE,
+        "gray" => Color::srgb(0.5, 0.5, 0.5),
+        _ => return None,
+    })
+}
+
+/// If `s` starts with a recognised tag, its length in bytes and the colour
+/// it switches to under `theme`.
+fn tag_at(s: &str, theme: &Theme) -> Option<(usize, Color)> {
+    let tag = s.strip_prefix('{')?;
+    let close = tag.find('}')?;
+    let color = match &tag[..close] {
+        "/" => theme.foreground,
+        "red" => theme.error,
+        "cyan" => theme.highlight,
+        name => color_named(name, theme.colorblind)?,
+    };
+    Some((close + 2, color))
+}
+
+/// Splits `markup` into (text, colour) runs. `{red}` switches colour and
+/// `{/}` switches back to the default; any other `{...}` is kept as text.
+pub fn parse(markup: &str, theme: &Theme) -> Vec<(String, Color)> {
+    parse_from(markup, theme, &mut theme.foreground.clone())
+}
+
+/// Like `parse`, but starting out in `color` the way a line a coloured
+/// span wrapped onto does, and leaving it at the colour `markup` ends in.
+pub fn parse_from(markup: &str, theme: &Theme, color: &mut Color) -> Vec<(String, Color)> {
+    let mut runs = Vec::new();
+    let mut current = String::new();
+    let mut rest = markup;
+    while let Some(open) = rest.find('{') {
+        current += &rest[..open];
+        rest = &rest[open..];
+        match tag_at(rest, theme) {
+            Some((len, tag_color)) => {
+                if !current.is_empty() {
+                    runs.push((std::mem::take(&mut current), *color));
+                }
+                *color = tag_color;
+                rest = &rest[len..];
+            }
+            None => {
+                current.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    current += rest;
+    if !current.is_empty() {
+        runs.push((current, *color));
+    }
+    runs
+}
+
+/// `markup` as plain text, with its colour tags removed.
+pub fn strip(markup: &str) -> String {
+    parse(markup, &Theme::default())
+        .into_iter()
+        .map(|(text, _)| text)
+        .collect()
+}
+
+/// Number of characters `markup` shows once its tags are removed.
+pub fn visible_len(markup: &str) -> usize {
+    parse(markup, &Theme::default())
+        .iter()
+        .map(|(text, _)| text.chars().count())
+        .sum()
+}
+
+/// Splits `markup` after `width` visible characters, never inside a tag.
+pub fn split_at_width(markup: &str, width: usize) -> (&str, &str) {
+    let theme = Theme::default();
+    let mut shown = 0;
+    let mut at = 0;
+    while at < markup.len() && shown < width {
+        match tag_at(&markup[at..], &theme) {
+            Some((len, _)) => at += len,
+            None => {
+                at += markup[at..].chars().next().map_or(1, char::len_utf8);
+                shown += 1;
+            }
+        }
+    }
+    markup.split_at(at)
+}
+
+/// Appends `markup` to `text` as one styled section per colour run.
+pub fn append_colored(
+    text: &mut Text,
+    markup: &str,
+    font: &Handle<Font>,
+    font_size: f32,
+    theme: &Theme,
+) {
+    append_colored_from(
+        text,
+        markup,
+        font,
+        font_size,
+        theme,
+        &mut theme.foreground.clone(),
+    );
+}
+
+/// Like `append_colored`, but carrying the colour on from `color` as
+/// `parse_from` does.
+pub fn append_colored_from(
+    text: &mut Text,
+    markup: &str,
+    font: &Handle<Font>,
+    font_size: f32,
+    theme: &Theme,
+    color: &mut Color,
+) {
+    for (value, color) in parse_from(markup, theme, color) {
+        text.sections.push(TextSection {
+            value,
+            style: TextStyle {
+                font: font.clone(),
+                font_size,
+                color,
+            },
+        });
+    }
+}
@@ -1 +1,222 @@
-// Mission logic
\ No newline at end of file
+use bevy::prelude::*;
+use serde::Deserialize;
+use std::fs;
+
+use crate::assets::asset_path;
+use crate::commands::GameLogicState;
+use crate::events::{HostBreached, HostScanned, LootCollected, TraceEvaded};
+use crate::layout::TerminalLayout;
+use crate::locale::{tr, Locale};
+use crate::output::OutputLine;
+use crate::pause::RunState;
+use crate::terminal::{PendingOutput, ScrollbackState, TerminalState};
+
+/// Something the runner can do that an objective may ask for.
+#[derive(Deserialize, Clone, PartialEq, Debug)]
+pub enum Goal {
+    /// Finish an `nmap` of this host.
+    Scan(String),
+    /// Break through this host's firewall.
+    Breach(String),
+    /// Collect the loot item of this name.
+    Download(String),
+    /// Shake off a trace with `cloak`.
+    EvadeTrace,
+}
+
+#[derive(Deserialize, Clone, PartialEq, Debug)]
+pub struct Objective {
+    pub description: String,
+    pub goal: Goal,
+    #[serde(skip)]
+    pub done: bool,
+}
+
+/// What the client wants done, read from `assets/mission.ron`.
+#[derive(Deserialize, Clone, PartialEq, Debug)]
+pub struct Mission {
+    pub objectives: Vec<Objective>,
+}
+
+impl Default for Mission {
+    /// The NeoTech job, for when `assets/mission.ron` is missing.
+    fn default() -> Self {
+        let objective = |description: &str, goal| Objective {
+            description: description.to_string(),
+            goal,
+            done: false,
+        };
+        Mission {
+            objectives: vec![
+                objective(
+                    "Scan neotechlabs.com",
+                    Goal::Scan("neotechlabs.com".to_string()),
+                ),
+                objective(
+                    "Breach the firewall on neotechlabs.com",
+                    Goal::Breach("neotechlabs.com".to_string()),
+                ),
+                objective(
+                    "Download the customer records",
+                    Goal::Download("customer-records".to_string()),
+                ),
+                objective("Shake off a trace", Goal::EvadeTrace),
+            ],
+        }
+    }
+}
+
+impl Mission {
+    /// The mission in `assets/mission.ron`, or the built-in one if the file
+    /// is missing or invalid.
+    pub fn load() -> Self {
+        let path = asset_path("mission.ron");
+        let Ok(contents) = fs::read_to_string(&path) else {
+            return Mission::default();
+        };
+        ron::from_str(&contents).unwrap_or_else(|err| {
+            warn!("Ignoring invalid {}: {}", path.display(), err);
+            Mission::default()
+        })
+    }
+
+    /// The same mission with nothing done yet.
+    pub fn restarted(&self) -> Self {
+        Mission {
+            objectives: self
+                .objectives
+                .iter()
+                .map(|objective| Objective {
+                    done: false,
+                    ..objective.clone()
+                })
+                .collect(),
+        }
+    }
+
+    /// Marks off every objective `achieved` meets. Returns whether any was
+    /// still open.
+    pub fn record(&mut self, achieved: &Goal) -> bool {
+        let mut progressed = false;
+        for objective in &mut self.objectives {
+            if !objective.done && objective.goal == *achieved {
+                objective.done = true;
+                progressed = true;
+            }
+        }
+        progressed
+    }
+
+    pub fn is_complete(&self) -> bool {
+        !self.objectives.is_empty() && self.objectives.iter().all(|objective| objective.done)
+    }
+
+    /// Every objective with a box that is ticked once it's done.
+    pub fn checklist(&self) -> Vec<String> {
+        self.objectives
+            .iter()
+            .map(|objective| {
+                let mark = if objective.done { 'x' } else { ' ' };
+                format!("[{}] {}", mark, objective.description)
+            })
+            .collect()
+    }
+
+    /// Handles `objectives`.
+    pub fn list(&self, locale: &Locale) -> Vec<OutputLine> {
+        let mut lines = vec![OutputLine::info(locale.get("objectives.header"))];
+        lines.extend(
+            self.checklist()
+                .into_iter()
+                .map(|line| OutputLine::Info(format!("  {}", line))),
+        );
+        lines
+    }
+}
+
+/// Marks objectives off as the terminal's events come in. Once the last
+/// one is done the mission is over and the runner has won.
+pub fn track_objectives(
+    layout: Res<TerminalLayout>,
+    mut scanned: EventReader<HostScanned>,
+    mut breached: EventReader<HostBreached>,
+    mut looted: EventReader<LootCollected>,
+    mut evaded: EventReader<TraceEvaded>,
+    mut game: ResMut<GameLogicState>,
+    mut state: ResMut<TerminalState>,
+    mut pending: ResMut<PendingOutput>,
+    mut scrollback: ResMut<ScrollbackState>,
+    mut next: ResMut<NextState<RunState>>,
+) {
+    let achieved: Vec<Goal> = scanned
+        .read()
+        .map(|ev| Goal::Scan(ev.host.clone()))
+        .chain(breached.read().map(|ev| Goal::Breach(ev.host.clone())))
+        .chain(looted.read().map(|ev| Goal::Download(ev.name.clone())))
+        .chain(evaded.read().map(|_| Goal::EvadeTrace))
+        .collect();
+    if achieved.is_empty() || game.mission.is_complete() {
+        return;
+    }
+    let mut done = Vec::new();
+    for goal in &achieved {
+        let before = game.mission.clone();
+        if game.mission.record(goal) {
+            done.extend(
+                game.mission
+                    .objectives
+                    .iter()
+                    .zip(&before.objectives)
+                    .filter(|(now, then)| now.done && !then.done)
+                    .map(|(now, _)| now.description.clone()),
+            );
+        }
+    }
+    for description in done {
+        pending.push(
+            &tr!(game.locale, "objectives.done", description = description),
+            &layout,
+        );
+    }
+    if game.mission.is_complete() {
+        // Output stops streaming outside `Running`, so show everything now
+        pending.flush(&mut scrollback);
+        scrollback.push(game.locale.get("objectives.mission_complete"), &layout);
+        state.lock();
+        next.set(RunState::Victory);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn objectives_tick_off_once_and_restart_clear() {
+        let mut mission = Mission::default();
+        assert!(!mission.record(&Goal::Scan("mail.neotechlabs.com".to_string())));
+        assert!(mission.record(&Goal::Scan("neotechlabs.com".to_string())));
+        assert!(!mission.record(&Goal::Scan("neotechlabs.com".to_string())));
+        assert_eq!(mission.checklist()[0], "[x] Scan neotechlabs.com");
+        assert_eq!(
+            mission.checklist()[1],
+            "[ ] Breach the firewall on neotechlabs.com"
+        );
+        mission.record(&Goal::Breach("neotechlabs.com".to_string()));
+        mission.record(&Goal::Download("customer-records".to_string()));
+        assert!(!mission.is_complete());
+        mission.record(&Goal::EvadeTrace);
+        assert!(mission.is_complete());
+        assert!(!mission.restarted().is_complete());
+        assert!(!Mission {
+            objectives: Vec::new()
+        }
+        .is_complete());
+    }
+
+    #[test]
+    fn the_mission_file_parses() {
+        let mission: Mission = ron::from_str(include_str!("../assets/mission.ron")).unwrap();
+        assert_eq!(mission, Mission::default());
+    }
+}
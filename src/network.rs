@@ -0,0 +1,291 @@
+use bevy::log::warn;
+use bevy::time::{Timer, TimerMode};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::time::Duration;
+
+use crate::assets::asset_path;
+use crate::rng::GameRng;
+
+/// The runner's own machine, where every session starts.
+pub const HOME_HOST: &str = "hideout";
+
+/// Wrong passwords a host accepts before it locks the runner out.
+const MAX_AUTH_ATTEMPTS: u32 = 3;
+pub const LOCKOUT_SECONDS: f32 = 60.0;
+
+#[derive(Clone)]
+pub struct Port {
+    pub number: u16,
+    pub service: &'static str,
+    pub vulnerable: bool,
+}
+
+pub struct HostNode {
+    pub ports: Vec<Port>,
+    /// Password needed to ssh in, if any.
+    pub credentials: Option<String>,
+    pub neighbors: Vec<String>,
+    /// How long the host takes to answer an `ssh` or `exploit`.
+    pub latency_seconds: f32,
+    /// Markup shown to whoever logs in, if the host has a message of the day.
+    pub motd: Option<String>,
+    /// Commands that don't work while logged in here, like `wget` at home,
+    /// where there's nothing to download.
+    pub unavailable: &'static [&'static str],
+}
+
+/// How the line between the runner and remote hosts behaves, on top of
+/// each host's own latency. Read from `assets/network.ron` at startup; by
+/// default the line is perfect.
+#[derive(Deserialize, Default, Clone, PartialEq, Debug)]
+#[serde(default)]
+pub struct NetworkConditions {
+    pub base_latency_ms: u32,
+    /// Up to this much more is added to each delay at random.
+    pub jitter_ms: u32,
+    /// Chance that a remote command's packets are lost and sent again.
+    pub loss_chance: f32,
+}
+
+impl NetworkConditions {
+    /// Defaults overridden by whatever `assets/network.ron` specifies.
+    pub fn load() -> Self {
+        let path = asset_path("network.ron");
+        let Ok(contents) = fs::read_to_string(&path) else {
+            return NetworkConditions::default();
+        };
+        ron::from_str(&contents).unwrap_or_else(|err| {
+            warn!("Ignoring invalid {}: {}", path.display(), err);
+            NetworkConditions::default()
+        })
+    }
+
+    /// One round trip to a host that answers in `host_seconds`, in seconds.
+    pub fn round_trip(&self, host_seconds: f32, rng: &mut GameRng) -> f32 {
+        let jitter_ms = if self.jitter_ms > 0 {
+            rng.up_to(self.jitter_ms)
+        } else {
+            0
+        };
+        let line_ms = self.base_latency_ms + jitter_ms;
+        host_seconds + line_ms as f32 / 1000.0
+    }
+}
+
+/// How much the runner knows about a discovered host, in increasing order.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Debug)]
+pub enum HostStatus {
+    Unknown,
+    Scanned,
+    Breached,
+}
+
+/// The simulated network the runner can scan and hop through.
+pub struct Network {
+    pub hosts: HashMap<String, HostNode>,
+    /// Hosts the runner has come across so far, sorted by name.
+    pub discovered: BTreeMap<String, HostStatus>,
+    /// The discovered hosts in the order they were found, which is how
+    /// `hosts` numbers them, so a number never changes once given.
+    discovery_order: Vec<String>,
+}
+
+impl Network {
+    pub fn host(&self, name: &str) -> Option<&HostNode> {
+        self.hosts.get(name)
+    }
+
+    pub fn is_neighbor(&self, from: &str, to: &str) -> bool {
+        self.host(from)
+            .is_some_and(|host| host.neighbors.iter().any(|n| n == to))
+    }
+
+    /// Records what the runner learned about `name`. Status only ever goes
+    /// up, so re-scanning a breached host leaves it breached.
+    pub fn discover(&mut self, name: &str, status: HostStatus) {
+        if self.hosts.contains_key(name) {
+            if !self.discovered.contains_key(name) {
+                self.discovery_order.push(name.to_string());
+            }
+            let known = self.discovered.entry(name.to_string()).or_insert(status);
+            *known = (*known).max(status);
+        }
+    }
+
+    /// Every discovered host with its number in `hosts`, counting from 1.
+    pub fn numbered(&self) -> impl Iterator<Item = (usize, &str, HostStatus)> {
+        self.discovery_order
+            .iter()
+            .enumerate()
+            .map(|(i, name)| (i + 1, name.as_str(), self.discovered[name]))
+    }
+
+    /// The host `hosts` lists as number `index`.
+    pub fn numbered_host(&self, index: usize) -> Option<&str> {
+        let i = index.checked_sub(1)?;
+        self.discovery_order.get(i).map(String::as_str)
+    }
+
+    /// Whether `command` works while logged in to `host`.
+    pub fn allows(&self, host: &str, command: &str) -> bool {
+        !self
+            .hosts
+            .get(host)
+            .is_some_and(|node| node.unavailable.contains(&command))
+    }
+
+    /// Marks `name` as breached and its neighbors as discovered.
+    pub fn enter(&mut self, name: &str) {
+        self.discover(name, HostStatus::Breached);
+        let neighbors = self
+            .host(name)
+            .map(|host| host.neighbors.clone())
+            .unwrap_or_default();
+        for neighbor in neighbors {
+            self.discover(&neighbor, HostStatus::Unknown);
+        }
+    }
+}
+
+/// Failed `ssh` passwords per host, and the cooldowns they triggered.
+#[derive(Default)]
+pub struct LockoutState {
+    attempts: HashMap<String, u32>,
+    locked: HashMap<String, Timer>,
+}
+
+impl LockoutState {
+    /// Seconds until `host` accepts logins again, if it is locked.
+    pub fn remaining(&self, host: &str) -> Option<f32> {
+        self.locked.get(host).map(Timer::remaining_secs)
+    }
+
+    /// Records a wrong password. Returns true if this one locked the host.
+    pub fn fail(&mut self, host: &str) -> bool {
+        let attempts = self.attempts.entry(host.to_string()).or_default();
+        *attempts += 1;
+        if *attempts < MAX_AUTH_ATTEMPTS {
+            return false;
+        }
+        self.attempts.remove(host);
+        self.locked.insert(
+            host.to_string(),
+            Timer::from_seconds(LOCKOUT_SECONDS, TimerMode::Once),
+        );
+        true
+    }
+
+    pub fn succeed(&mut self, host: &str) {
+        self.attempts.remove(host);
+    }
+
+    pub fn any_locked(&self) -> bool {
+        !self.locked.is_empty()
+    }
+
+    /// Counts down every cooldown, unlocking hosts whose time is up.
+    pub fn tick(&mut self, delta: Duration) {
+        self.locked.retain(|_, timer| !timer.tick(delta).finished());
+    }
+}
+
+impl Default for Network {
+    fn default() -> Self {
+        let mut hosts = HashMap::new();
+        let mut add = |name: &str,
+                       ports: Vec<Port>,
+                       credentials: Option<&str>,
+                       latency_seconds: f32,
+                       neighbors: &[&str]| {
+            hosts.insert(
+                name.to_string(),
+                HostNode {
+                    ports,
+                    credentials: credentials.map(str::to_string),
+                    neighbors: neighbors.iter().map(|n| n.to_string()).collect(),
+                    latency_seconds,
+                    motd: None,
+                    unavailable: &[],
+                },
+            );
+        };
+        let port = |number, service, vulnerable| Port {
+            number,
+            service,
+            vulnerable,
+        };
+        add(HOME_HOST, vec![], None, 0.0, &["neotechlabs.com"]);
+        add(
+            "neotechlabs.com",
+            vec![
+                port(22, "SSH", false),
+                port(80, "HTTP", true),
+                port(443, "HTTPS", false),
+            ],
+            None,
+            0.6,
+            &[HOME_HOST, "mail.neotechlabs.com", "dev.neotechlabs.com"],
+        );
+        add(
+            "mail.neotechlabs.com",
+            vec![port(25, "SMTP", false), port(143, "IMAP", true)],
+            Some("hunter2"),
+            0.9,
+            &["neotechlabs.com", "vault.neotechlabs.com"],
+        );
+        add(
+            "dev.neotechlabs.com",
+            vec![port(22, "SSH", false), port(8080, "HTTP-ALT", true)],
+            None,
+            0.8,
+            &["neotechlabs.com", "vault.neotechlabs.com"],
+        );
+        add(
+            "vault.neotechlabs.com",
+            vec![port(22, "SSH", false), port(5432, "POSTGRES", false)],
+            Some("n30t3ch"),
+            1.5,
+            &[
+                "mail.neotechlabs.com",
+                "dev.neotechlabs.com",
+                "archive.neotechlabs.com",
+            ],
+        );
+        add(
+            "archive.neotechlabs.com",
+            vec![port(22, "SSH", false)],
+            Some("c0ldst0r4ge"),
+            2.0,
+            &["vault.neotechlabs.com"],
+        );
+        // The front end's message of the day is its /etc/motd instead
+        for (name, motd) in [
+            (
+                "dev.neotechlabs.com",
+                "{yellow}Reminder: no personal notes on shared boxes. Yes, you.{/}",
+            ),
+            (
+                "vault.neotechlabs.com",
+                "{red}*** NEOTECH SECURE VAULT ***{/}\n\
+                 {red}All sessions are recorded. Intruders will be traced.{/}",
+            ),
+        ] {
+            if let Some(host) = hosts.get_mut(name) {
+                host.motd = Some(motd.to_string());
+            }
+        }
+        // The runner's own machine has no data to fetch
+        if let Some(home) = hosts.get_mut(HOME_HOST) {
+            home.unavailable = &["wget"];
+        }
+        let mut network = Network {
+            hosts,
+            discovered: BTreeMap::new(),
+            discovery_order: Vec::new(),
+        };
+        network.enter(HOME_HOST);
+        network
+    }
+}
@@ -0,0 +1,101 @@
+use crate::locale::{tr, Locale};
+
+/// One line of command output. Commands say what a line is, and `render`
+/// alone decides how it looks, so colours stay the same everywhere.
+#[derive(Clone, PartialEq, Debug)]
+pub enum OutputLine {
+    /// Ordinary output. Parts of it may still be highlighted with colour
+    /// tags, e.g. `{cyan}`.
+    Info(String),
+    /// Something failed or was refused.
+    Error(String),
+    /// A port revealed by `nmap`.
+    Port {
+        num: u16,
+        service: String,
+        vulnerable: bool,
+    },
+    /// A bare prompt marker with nothing after it.
+    Prompt,
+}
+
+impl OutputLine {
+    pub fn info(text: impl Into<String>) -> Self {
+        OutputLine::Info(text.into())
+    }
+
+    pub fn error(text: impl Into<String>) -> Self {
+        OutputLine::Error(text.into())
+    }
+
+    /// One `Info` per line of `text`, e.g. the contents of a file.
+    pub fn info_lines(text: &str) -> Vec<Self> {
+        text.lines().map(OutputLine::info).collect()
+    }
+
+    /// The line as terminal markup.
+    pub fn render(&self, locale: &Locale) -> String {
+        match self {
+            OutputLine::Info(text) => format!("> {}", text),
+            OutputLine::Error(text) => format!("> {{red}}{}{{/}}", text),
+            OutputLine::Port {
+                num,
+                service,
+                vulnerable: false,
+            } => format!(
+                "> {}",
+                tr!(locale, "scan.port", number = num, service = service)
+            ),
+            OutputLine::Port {
+                num,
+                service,
+                vulnerable: true,
+            } => format!(
+                "> {{red}}{}{{/}}",
+                tr!(
+                    locale,
+                    "scan.port_vulnerable",
+                    number = num,
+                    service = service
+                )
+            ),
+            OutputLine::Prompt => ">".to_string(),
+        }
+    }
+}
+
+/// `lines` rendered one per line.
+pub fn render(lines: &[OutputLine], locale: &Locale) -> String {
+    lines
+        .iter()
+        .map(|line| line.render(locale))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rendering_decides_the_colours() {
+        let locale = Locale::default();
+        let lines = [
+            OutputLine::info("Connected to {cyan}vault{/}"),
+            OutputLine::error("ssh: permission denied"),
+            OutputLine::Port {
+                num: 80,
+                service: "HTTP".to_string(),
+                vulnerable: true,
+            },
+            OutputLine::Prompt,
+        ];
+        assert_eq!(
+            render(&lines, &locale),
+            "> Connected to {cyan}vault{/}\n\
+             > {red}ssh: permission denied{/}\n\
+             > {red}Port 80: HTTP [!] vulnerable{/}\n\
+             >"
+        );
+    }
+}
@@ -0,0 +1,298 @@
+use bevy::prelude::*;
+
+use crate::audio::AudioSettings;
+use crate::crt::CrtSettings;
+use crate::events::ResetRequested;
+use crate::gamepad::{PadAction, PadBindings};
+use crate::markup::{DEFAULT_COLOR, FONT_SIZE};
+use crate::terminal::TerminalState;
+
+/// Whether the terminal is live, behind the pause menu, or waiting for a
+/// restart. Systems that take input or advance time only run while `Running`.
+#[derive(States, Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum RunState {
+    #[default]
+    Running,
+    Paused,
+    /// The runner flatlined; only restarting is possible.
+    GameOver,
+    /// Every objective is done; only restarting is possible.
+    Victory,
+}
+
+const VOLUME_STEPS: [f32; 5] = [0.0, 0.25, 0.5, 0.75, 1.0];
+
+/// Fill of the button a gamepad would press.
+const FOCUS_COLOR: Color = Color::srgb(0.0, 0.3, 0.0);
+
+/// Root node of the pause overlay; despawning it removes the whole menu.
+#[derive(Component)]
+struct PauseMenu;
+
+/// Where a button sits in its menu, top first, for moving through them with
+/// a gamepad.
+#[derive(Component)]
+struct MenuSlot(usize);
+
+/// The page the menu is on and which of its buttons a gamepad would press.
+#[derive(Resource, Default)]
+struct MenuFocus {
+    page: MenuPage,
+    slot: usize,
+}
+
+#[derive(Component, Clone, Copy)]
+enum MenuButton {
+    Resume,
+    Settings,
+    Reset,
+    Quit,
+    ToggleCrt,
+    Volume,
+    Back,
+}
+
+#[derive(Clone, Copy, PartialEq, Default)]
+enum MenuPage {
+    #[default]
+    Main,
+    Settings,
+}
+
+pub struct PausePlugin;
+
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_state::<RunState>()
+            .init_resource::<MenuFocus>()
+            .add_systems(Update, toggle_pause)
+            .add_systems(
+                Update,
+                handle_menu_buttons.run_if(in_state(RunState::Paused)),
+            )
+            .add_systems(OnEnter(RunState::Paused), open_menu)
+            .add_systems(OnExit(RunState::Paused), close_menu);
+    }
+}
+
+/// Escape, or a gamepad's start button, opens the menu, and pressing it
+/// again resumes.
+fn toggle_pause(
+    keys: Res<ButtonInput<KeyCode>>,
+    buttons: Res<ButtonInput<GamepadButton>>,
+    pad_bindings: Res<PadBindings>,
+    state: Res<State<RunState>>,
+    terminal: Res<TerminalState>,
+    mut next: ResMut<NextState<RunState>>,
+) {
+    let pressed =
+        keys.just_pressed(KeyCode::Escape) || pad_bindings.just_pressed(&buttons, PadAction::Pause);
+    // Escape ends a search or replay instead
+    if pressed && !terminal.is_searching() && !terminal.is_replaying() {
+        match state.get() {
+            RunState::Running => next.set(RunState::Paused),
+            RunState::Paused => next.set(RunState::Running),
+            RunState::GameOver | RunState::Victory => {}
+        }
+    }
+}
+
+fn open_menu(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    crt: Res<CrtSettings>,
+    audio: Res<AudioSettings>,
+    mut focus: ResMut<MenuFocus>,
+) {
+    *focus = MenuFocus::default();
+    let font = asset_server.load("fonts/FiraMono-Regular.ttf");
+    spawn_menu(&mut commands, &font, &focus, &crt, &audio);
+}
+
+fn close_menu(mut commands: Commands, menu: Query<Entity, With<PauseMenu>>) {
+    for entity in &menu {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+/// Presses whichever button was clicked, or the one a gamepad picked: the
+/// d-pad moves through them, and back leaves the page.
+fn handle_menu_buttons(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    pads: Res<ButtonInput<GamepadButton>>,
+    pad_bindings: Res<PadBindings>,
+    mut focus: ResMut<MenuFocus>,
+    buttons: Query<(&Interaction, &MenuButton), Changed<Interaction>>,
+    mut slots: Query<(&MenuButton, &MenuSlot, &mut BackgroundColor)>,
+    menu: Query<Entity, With<PauseMenu>>,
+    mut next: ResMut<NextState<RunState>>,
+    mut crt: ResMut<CrtSettings>,
+    mut audio: ResMut<AudioSettings>,
+    mut resets: EventWriter<ResetRequested>,
+    mut exit: EventWriter<AppExit>,
+) {
+    let count = slots.iter().count().max(1);
+    let moved = if pad_bindings.just_pressed(&pads, PadAction::MenuDown) {
+        Some(focus.slot + 1)
+    } else if pad_bindings.just_pressed(&pads, PadAction::MenuUp) {
+        Some(focus.slot + count - 1)
+    } else {
+        None
+    };
+    if let Some(slot) = moved {
+        focus.slot = slot % count;
+        for (_, slot, mut background) in &mut slots {
+            *background = button_fill(slot.0 == focus.slot).into();
+        }
+    }
+    let clicked = buttons
+        .iter()
+        .find(|(interaction, _)| **interaction == Interaction::Pressed)
+        .map(|(_, button)| *button);
+    let selected = pad_bindings
+        .just_pressed(&pads, PadAction::MenuSelect)
+        .then(|| {
+            slots
+                .iter()
+                .find(|(_, slot, _)| slot.0 == focus.slot)
+                .map(|(button, _, _)| *button)
+        })
+        .flatten();
+    let back = pad_bindings
+        .just_pressed(&pads, PadAction::MenuBack)
+        .then_some(match focus.page {
+            MenuPage::Main => MenuButton::Resume,
+            MenuPage::Settings => MenuButton::Back,
+        });
+    if let Some(button) = clicked.or(selected).or(back) {
+        let page = match button {
+            MenuButton::Resume => {
+                next.set(RunState::Running);
+                return;
+            }
+            // Starting over also closes the menu
+            MenuButton::Reset => {
+                resets.send(ResetRequested);
+                return;
+            }
+            MenuButton::Quit => {
+                exit.send(AppExit::Success);
+                return;
+            }
+            MenuButton::Settings => MenuPage::Settings,
+            MenuButton::Back => MenuPage::Main,
+            MenuButton::ToggleCrt => {
+                crt.enabled = !crt.enabled;
+                MenuPage::Settings
+            }
+            MenuButton::Volume => {
+                let next_step = VOLUME_STEPS
+                    .iter()
+                    .position(|&step| step > audio.volume)
+                    .unwrap_or(0);
+                audio.volume = VOLUME_STEPS[next_step];
+                MenuPage::Settings
+            }
+        };
+        if page != focus.page {
+            *focus = MenuFocus { page, slot: 0 };
+        }
+        // Rebuild the menu so the page and its labels reflect the change
+        for entity in &menu {
+            commands.entity(entity).despawn_recursive();
+        }
+        let font = asset_server.load("fonts/FiraMono-Regular.ttf");
+        spawn_menu(&mut commands, &font, &focus, &crt, &audio);
+    }
+}
+
+fn button_fill(focused: bool) -> Color {
+    if focused {
+        FOCUS_COLOR
+    } else {
+        Color::BLACK
+    }
+}
+
+fn spawn_menu(
+    commands: &mut Commands,
+    font: &Handle<Font>,
+    focus: &MenuFocus,
+    crt: &CrtSettings,
+    audio: &AudioSettings,
+) {
+    let page = focus.page;
+    let buttons = match page {
+        MenuPage::Main => vec![
+            (MenuButton::Resume, "Resume".to_string()),
+            (MenuButton::Settings, "Settings".to_string()),
+            (MenuButton::Reset, "Reset".to_string()),
+            (MenuButton::Quit, "Quit".to_string()),
+        ],
+        MenuPage::Settings => vec![
+            (
+                MenuButton::ToggleCrt,
+                format!("CRT: {}", if crt.enabled { "On" } else { "Off" }),
+            ),
+            (
+                MenuButton::Volume,
+                format!("Volume: {:.0}%", audio.volume * 100.0),
+            ),
+            (MenuButton::Back, "Back".to_string()),
+        ],
+    };
+    let style = TextStyle {
+        font: font.clone(),
+        font_size: FONT_SIZE,
+        color: DEFAULT_COLOR,
+    };
+    commands
+        .spawn((
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    align_items: AlignItems::Center,
+                    justify_content: JustifyContent::Center,
+                    row_gap: Val::Px(12.0),
+                    ..default()
+                },
+                background_color: Color::srgba(0.0, 0.0, 0.0, 0.8).into(),
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+            PauseMenu,
+        ))
+        .with_children(|menu| {
+            let title = if page == MenuPage::Main {
+                "PAUSED"
+            } else {
+                "SETTINGS"
+            };
+            menu.spawn(TextBundle::from_section(title, style.clone()));
+            for (slot, (button, label)) in buttons.into_iter().enumerate() {
+                menu.spawn((
+                    ButtonBundle {
+                        style: Style {
+                            width: Val::Px(220.0),
+                            padding: UiRect::all(Val::Px(8.0)),
+                            justify_content: JustifyContent::Center,
+                            border: UiRect::all(Val::Px(2.0)),
+                            ..default()
+                        },
+                        border_color: DEFAULT_COLOR.into(),
+                        background_color: button_fill(slot == focus.slot).into(),
+                        ..default()
+                    },
+                    button,
+                    MenuSlot(slot),
+                ))
+                .with_children(|parent| {
+                    parent.spawn(TextBundle::from_section(label, style.clone()));
+                });
+            }
+        });
+}
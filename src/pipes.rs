@@ -0,0 +1,163 @@
+use crate::clipboard;
+use crate::commands::{CommandRegistry, Effect};
+use crate::locale::{tr, Locale};
+use crate::markup;
+use crate::output::OutputLine;
+use crate::tokenize::{split_unquoted, tokenize};
+
+/// Lines `head` keeps when no count is given.
+const DEFAULT_HEAD_LINES: usize = 10;
+
+/// A command after a `|`, which reads the text before it instead of game state.
+enum Filter {
+    Grep(String),
+    Head(usize),
+    CountLines(usize),
+    /// Collects the text for the clipboard instead of showing it.
+    Clip(String),
+}
+
+impl Filter {
+    fn parse(stage: &str, locale: &Locale) -> Result<Self, String> {
+        let words = tokenize(stage).map_err(|err| err.message(locale))?;
+        let words: Vec<&str> = words.iter().map(String::as_str).collect();
+        let usage = |usage: &str| tr!(locale, "command.usage", usage = usage);
+        match words.as_slice() {
+            ["grep", pattern] => Ok(Filter::Grep(pattern.to_string())),
+            ["grep", ..] => Err(usage("grep <pattern>")),
+            ["head"] => Ok(Filter::Head(DEFAULT_HEAD_LINES)),
+            ["head", "-n", count] => count
+                .parse()
+                .map(Filter::Head)
+                .map_err(|_| tr!(locale, "head.invalid_count", count = count)),
+            ["head", ..] => Err(usage("head [-n count]")),
+            ["wc", "-l"] => Ok(Filter::CountLines(0)),
+            ["wc", ..] => Err(usage("wc -l")),
+            ["clip"] => Ok(Filter::Clip(String::new())),
+            ["clip", ..] => Err(usage("clip")),
+            [verb, ..] => Err(tr!(locale, "pipe.not_a_filter", verb = verb)),
+            [] => Err(locale.get("pipe.usage").to_string()),
+        }
+    }
+
+    /// Passes a line on, or holds it back. `grep` matches the text the line
+    /// is shown as.
+    fn feed(&mut self, line: OutputLine, locale: &Locale) -> Option<OutputLine> {
+        match self {
+            Filter::Grep(pattern) => markup::strip(&line.render(locale))
+                .contains(pattern.as_str())
+                .then_some(line),
+            Filter::Head(left) => {
+                let keep = *left > 0;
+                *left = left.saturating_sub(1);
+                keep.then_some(line)
+            }
+            Filter::CountLines(count) => {
+                *count += 1;
+                None
+            }
+            Filter::Clip(text) => {
+                if !text.is_empty() {
+                    text.push('\n');
+                }
+                text.push_str(&markup::strip(&line.render(locale)));
+                None
+            }
+        }
+    }
+
+    /// Output that can only be produced once the input has ended.
+    fn finish(&mut self, locale: &Locale) -> Option<OutputLine> {
+        match self {
+            Filter::CountLines(count) => Some(OutputLine::Info(count.to_string())),
+            Filter::Clip(text) => Some(clipboard::copy(text, locale)),
+            _ => None,
+        }
+    }
+}
+
+/// The filters after a command. Output goes through line by line, so text
+/// that arrives later, like `nmap`'s ports, is filtered the same way.
+pub struct Pipeline {
+    filters: Vec<Filter>,
+}
+
+impl Pipeline {
+    /// Splits `line` into the command that produces output and the
+    /// pipeline its output goes through, if it has one.
+    pub fn parse<'a>(
+        line: &'a str,
+        locale: &Locale,
+    ) -> Result<(&'a str, Option<Pipeline>), String> {
+        let mut stages = split_unquoted(line, '|').into_iter().map(str::trim);
+        let command = stages.next().unwrap_or_default();
+        let rest: Vec<&str> = stages.collect();
+        if rest.is_empty() {
+            return Ok((command, None));
+        }
+        if command.is_empty() || rest.iter().any(|stage| stage.is_empty()) {
+            return Err(locale.get("pipe.usage").to_string());
+        }
+        let filters = rest
+            .into_iter()
+            .map(|stage| Filter::parse(stage, locale))
+            .collect::<Result<_, _>>()?;
+        Ok((command, Some(Pipeline { filters })))
+    }
+
+    fn feed_from(&mut self, stage: usize, line: OutputLine, locale: &Locale) -> Option<OutputLine> {
+        self.filters[stage..]
+            .iter_mut()
+            .try_fold(line, |line, filter| filter.feed(line, locale))
+    }
+
+    /// The lines that make it through every filter.
+    pub fn feed(&mut self, lines: Vec<OutputLine>, locale: &Locale) -> Vec<OutputLine> {
+        lines
+            .into_iter()
+            .filter_map(|line| self.feed_from(0, line, locale))
+            .collect()
+    }
+
+    /// Ends the input, flushing what filters like `wc` were holding.
+    pub fn finish(&mut self, locale: &Locale) -> Vec<OutputLine> {
+        (0..self.filters.len())
+            .filter_map(|stage| {
+                let line = self.filters[stage].finish(locale)?;
+                self.feed_from(stage + 1, line, locale)
+            })
+            .collect()
+    }
+}
+
+/// Registers the filters as commands too, so `help` lists them and running
+/// one on its own explains how to pipe into it.
+pub fn register_filters(registry: &mut CommandRegistry) {
+    for (name, args, description) in [
+        ("grep", "<pattern>", "Keep piped lines containing a pattern"),
+        (
+            "head",
+            "[-n] [count]",
+            "Keep the first lines of piped output",
+        ),
+        ("wc", "[-l]", "Count the lines of piped output"),
+    ] {
+        registry.register(name, args, description, move |_, game| {
+            vec![OutputLine::Error(tr!(
+                game.locale,
+                "pipe.nothing_to_read",
+                name = name
+            ))]
+        });
+    }
+    // With nothing piped in, `clip` copies everything on screen and above
+    registry.register(
+        "clip",
+        "",
+        "Copy piped output, or the whole scrollback, to the clipboard",
+        |_, game| {
+            game.effects.push(Effect::CopyScrollback);
+            Vec::new()
+        },
+    );
+}
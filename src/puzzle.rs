@@ -0,0 +1,63 @@
+use bevy::prelude::*;
+
+use crate::difficulty::DifficultySettings;
+use crate::rng::GameRng;
+
+/// The firewall challenge `exploit` puts up: a run of hex bytes that must
+/// be typed back in reverse order before the deadline. While one is open,
+/// whatever is entered is taken as the answer rather than as a command.
+pub struct ExploitPuzzle {
+    pub target: String,
+    pub sequence: Vec<u8>,
+    pub deadline: Timer,
+}
+
+impl ExploitPuzzle {
+    /// A challenge against `target`, as long and as quick as `difficulty`
+    /// asks for.
+    pub fn new(target: &str, difficulty: &DifficultySettings, rng: &mut GameRng) -> Self {
+        ExploitPuzzle {
+            target: target.to_string(),
+            sequence: (0..difficulty.puzzle_bytes).map(|_| rng.byte()).collect(),
+            deadline: Timer::from_seconds(difficulty.puzzle_seconds, TimerMode::Once),
+        }
+    }
+
+    /// The bytes as they are shown, e.g. `3F A9 1C`.
+    pub fn challenge(&self) -> String {
+        hex(self.sequence.iter())
+    }
+
+    /// Whether `input` is the sequence reversed. Case and spacing don't
+    /// matter, so `1ca93f` answers `3F A9 1C`.
+    pub fn accepts(&self, input: &str) -> bool {
+        let typed: String = input.split_whitespace().collect();
+        typed.eq_ignore_ascii_case(&hex(self.sequence.iter().rev()).replace(' ', ""))
+    }
+}
+
+fn hex<'a>(bytes: impl Iterator<Item = &'a u8>) -> String {
+    bytes
+        .map(|byte| format!("{:02X}", byte))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn answer_is_the_sequence_reversed() {
+        let puzzle = ExploitPuzzle {
+            target: "neotechlabs.com".to_string(),
+            sequence: vec![0x3F, 0xA9, 0x1C],
+            deadline: Timer::from_seconds(10.0, TimerMode::Once),
+        };
+        assert_eq!(puzzle.challenge(), "3F A9 1C");
+        assert!(puzzle.accepts("1C A9 3F"));
+        assert!(puzzle.accepts("1ca93f"));
+        assert!(!puzzle.accepts("3F A9 1C"));
+        assert!(!puzzle.accepts(""));
+    }
+}
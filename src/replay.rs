@@ -0,0 +1,237 @@
+use bevy::prelude::*;
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use crate::commands::GameLogicState;
+use crate::events::{InterruptRequested, ReplayRequested};
+use crate::layout::TerminalLayout;
+use crate::locale::tr;
+use crate::settings::OutputSpeed;
+use crate::terminal::{
+    PendingOutput, ResponseDelay, ScrollbackState, TerminalConfig, TerminalState,
+};
+use crate::transcript::{TranscriptEntry, TranscriptLogger};
+
+/// Longest a replay waits between two commands, however long the runner
+/// sat thinking, so a clip never stalls.
+const MAX_PAUSE_SECS: f32 = 3.0;
+/// How long each character of a replayed command takes to type, at 1x.
+const TYPE_SECS: f32 = 0.06;
+/// Slowest and fastest `replay` plays back. Far enough out either way and
+/// its timers would overflow or never tick.
+pub const MIN_SPEED: f32 = 0.1;
+pub const MAX_SPEED: f32 = 100.0;
+
+/// Where a replay is in playing back the next command.
+enum Step {
+    /// Waiting out the time the runner took before typing it.
+    Pause(Timer),
+    /// Typing it into the input line, `typed` characters so far.
+    Typing { typed: usize, timer: Timer },
+}
+
+/// The session being played back by `replay`, if one is.
+#[derive(Resource, Default)]
+pub struct Replay {
+    entries: VecDeque<TranscriptEntry>,
+    speed: f32,
+    /// When the last command played back was first submitted.
+    last_at: Duration,
+    /// Whether commands appear whole rather than being typed, as with
+    /// instant output.
+    instant: bool,
+    step: Option<Step>,
+}
+
+impl Replay {
+    /// A replay of `entries` at `speed` times their pace, with commands
+    /// typed at `output_speed`.
+    fn new(entries: Vec<TranscriptEntry>, speed: f32, output_speed: OutputSpeed) -> Self {
+        let mut replay = Replay {
+            last_at: entries.first().map_or(Duration::ZERO, |entry| entry.at),
+            entries: entries.into(),
+            speed,
+            instant: output_speed == OutputSpeed::Instant,
+            step: None,
+        };
+        replay.pause();
+        replay
+    }
+
+    /// Starts waiting for the next command, as long as the runner did
+    /// after the last one, sped up.
+    fn pause(&mut self) {
+        let Some(next) = self.entries.front() else {
+            self.step = None;
+            return;
+        };
+        let gap = next.at.saturating_sub(self.last_at).as_secs_f32() / self.speed;
+        self.step = Some(Step::Pause(Timer::from_seconds(
+            gap.min(MAX_PAUSE_SECS),
+            TimerMode::Once,
+        )));
+    }
+
+    /// Moves the replay on by `delta`, typing into `state`. Returns the
+    /// next entry once its command has been typed out in full.
+    fn advance(&mut self, delta: Duration, state: &mut TerminalState) -> Option<TranscriptEntry> {
+        match self.step.as_mut()? {
+            Step::Pause(timer) => {
+                if timer.tick(delta).finished() {
+                    let timer = Timer::from_seconds(TYPE_SECS / self.speed, TimerMode::Repeating);
+                    self.step = Some(Step::Typing { typed: 0, timer });
+                }
+                None
+            }
+            Step::Typing { typed, timer } => {
+                *typed += timer.tick(delta).times_finished_this_tick() as usize;
+                let command = &self.entries.front()?.command;
+                if self.instant {
+                    *typed = command.chars().count();
+                }
+                state.set_input(command.chars().take(*typed).collect());
+                if *typed < command.chars().count() {
+                    return None;
+                }
+                let entry = self.entries.pop_front()?;
+                self.last_at = entry.at;
+                self.pause();
+                Some(entry)
+            }
+        }
+    }
+}
+
+/// Plays the session back once `replay` asks: each command is typed into
+/// the input line and its response typed out after it, with the runner's
+/// own pauses in between. Nothing can be typed meanwhile, and Escape or
+/// an interrupt stops it and gives the prompt back.
+pub fn play_replay(
+    time: Res<Time>,
+    mut keys: ResMut<ButtonInput<KeyCode>>,
+    layout: Res<TerminalLayout>,
+    config: Res<TerminalConfig>,
+    game: Res<GameLogicState>,
+    transcript: Res<TranscriptLogger>,
+    delay: Res<ResponseDelay>,
+    mut requests: EventReader<ReplayRequested>,
+    mut interrupts: EventReader<InterruptRequested>,
+    mut replay: ResMut<Replay>,
+    mut state: ResMut<TerminalState>,
+    mut pending: ResMut<PendingOutput>,
+    mut scrollback: ResMut<ScrollbackState>,
+) {
+    // Read every frame, so an old interrupt can't stop the next replay
+    let interrupted = interrupts.read().count() > 0;
+    if let Some(request) = requests.read().last() {
+        let mut entries = transcript.entries();
+        // The last entry is the `replay` that asked for this one
+        entries.pop();
+        if entries.is_empty() {
+            pending.push(game.locale.get("replay.empty"), &layout);
+        } else if state.start_replay() {
+            pending.push(
+                &tr!(
+                    game.locale,
+                    "replay.started",
+                    count = entries.len(),
+                    speed = request.speed
+                ),
+                &layout,
+            );
+            *replay = Replay::new(entries, request.speed, config.output_speed);
+        }
+    }
+    if !state.is_replaying() {
+        return;
+    }
+    if interrupted {
+        // The interrupt prints its own `^C`
+        *replay = Replay::default();
+        state.stop_replay();
+        return;
+    }
+    if keys.just_pressed(KeyCode::Escape) {
+        *replay = Replay::default();
+        state.stop_replay();
+        pending.flush(&mut scrollback);
+        scrollback.push(game.locale.get("replay.aborted"), &layout);
+        // Stopping the replay shouldn't also open the pause menu
+        keys.clear_just_pressed(KeyCode::Escape);
+        return;
+    }
+    // Each command waits for the last one's response to finish
+    if delay.is_waiting() || pending.is_streaming() {
+        return;
+    }
+    if replay.step.is_none() {
+        state.stop_replay();
+        pending.push(game.locale.get("replay.done"), &layout);
+        return;
+    }
+    if let Some(entry) = replay.advance(time.delta(), &mut state) {
+        state.set_input(String::new());
+        let response: Vec<String> = entry
+            .response
+            .lines()
+            .map(|line| format!("[{}] {}", entry.stamp, line))
+            .collect();
+        pending.push(&response.join("\n"), &layout);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(secs: u64, command: &str) -> TranscriptEntry {
+        TranscriptEntry {
+            at: Duration::from_secs(secs),
+            stamp: "23:47".to_string(),
+            command: command.to_string(),
+            response: String::new(),
+        }
+    }
+
+    #[test]
+    fn commands_are_typed_after_the_runners_pause_sped_up() {
+        let mut state = TerminalState::default();
+        let typewriter = OutputSpeed::default();
+        let mut replay = Replay::new(vec![entry(10, "ls"), entry(12, "pwd")], 2.0, typewriter);
+        let frame = Duration::from_secs_f32(TYPE_SECS / 2.0);
+        // The first command is typed straight away, a character a frame
+        assert!(replay.advance(frame, &mut state).is_none());
+        assert!(replay.advance(frame, &mut state).is_none());
+        assert_eq!(state.input(), "l");
+        let typed = replay.advance(frame, &mut state).unwrap();
+        assert_eq!(typed.command, "ls");
+        // Two seconds between them, at double speed
+        assert!(replay
+            .advance(Duration::from_millis(900), &mut state)
+            .is_none());
+        assert!(matches!(replay.step, Some(Step::Pause(_))));
+        assert!(replay
+            .advance(Duration::from_millis(100), &mut state)
+            .is_none());
+        assert!(matches!(replay.step, Some(Step::Typing { .. })));
+        assert_eq!(
+            replay
+                .advance(Duration::from_secs(1), &mut state)
+                .unwrap()
+                .command,
+            "pwd"
+        );
+        assert!(replay.step.is_none());
+        // A long think is cut short
+        let mut replay = Replay::new(vec![entry(0, "ls"), entry(600, "pwd")], 1.0, typewriter);
+        replay.advance(Duration::from_secs(1), &mut state);
+        replay.advance(Duration::from_secs(1), &mut state);
+        replay.advance(Duration::from_secs_f32(MAX_PAUSE_SECS), &mut state);
+        assert!(matches!(replay.step, Some(Step::Typing { .. })));
+        // Instant output shows each command whole
+        let mut replay = Replay::new(vec![entry(0, "nmap")], 1.0, OutputSpeed::Instant);
+        replay.advance(Duration::ZERO, &mut state);
+        let typed = replay.advance(Duration::ZERO, &mut state);
+        assert_eq!(typed.map(|entry| entry.command).as_deref(), Some("nmap"));
+    }
+}
@@ -0,0 +1,50 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+/// The one source of randomness for game logic. Runs started from the same
+/// seed play out the same way.
+pub struct GameRng {
+    seed: u64,
+    rng: StdRng,
+}
+
+impl Default for GameRng {
+    fn default() -> Self {
+        GameRng::new(rand::random())
+    }
+}
+
+impl GameRng {
+    pub fn new(seed: u64) -> Self {
+        GameRng {
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// Seeded by `seed` if the config sets one, or randomly otherwise.
+    /// The seed is printed either way so a run can be replayed.
+    pub fn from_seed(seed: Option<u64>) -> Self {
+        let rng = seed.map_or_else(GameRng::default, GameRng::new);
+        println!("Seed: {}", rng.seed);
+        rng
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// `true` with probability `p`.
+    pub fn chance(&mut self, p: f32) -> bool {
+        self.rng.gen::<f32>() < p
+    }
+
+    /// A whole number from 0 to `max`, inclusive.
+    pub fn up_to(&mut self, max: u32) -> u32 {
+        self.rng.gen_range(0..=max)
+    }
+
+    pub fn byte(&mut self) -> u8 {
+        self.rng.gen()
+    }
+}
@@ -0,0 +1,66 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::inventory::LootItem;
+use crate::network::HostStatus;
+
+/// Everything that survives a restart.
+#[derive(Serialize, Deserialize, Default)]
+pub struct SessionData {
+    #[serde(default)]
+    pub history: Vec<String>,
+    #[serde(default)]
+    pub output: Vec<String>,
+    #[serde(default)]
+    pub aliases: HashMap<String, String>,
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    #[serde(default)]
+    pub inventory: Vec<LootItem>,
+    #[serde(default)]
+    pub handle: Option<String>,
+    /// Every host found so far, in the order `hosts` numbers them.
+    #[serde(default)]
+    pub discovered: Vec<(String, HostStatus)>,
+}
+
+/// Where sessions are kept, in the platform config dir.
+pub fn session_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("neon-city").join("session.json"))
+}
+
+/// Writes the session to `path` and returns where it went. There being no
+/// path, as without a config dir, is an error.
+pub fn save_session(path: Option<&Path>, data: &SessionData) -> io::Result<PathBuf> {
+    let path =
+        path.ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no config directory"))?;
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, serde_json::to_string_pretty(data)?)?;
+    Ok(path.to_path_buf())
+}
+
+/// Loads the previous session, or `None` to start fresh. A missing file is
+/// expected on first run; an unreadable or corrupt one is logged.
+pub fn load_session(path: &Path) -> Option<SessionData> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return None,
+        Err(err) => {
+            warn!("Could not read {}: {}", path.display(), err);
+            return None;
+        }
+    };
+    match serde_json::from_str(&contents) {
+        Ok(data) => Some(data),
+        Err(err) => {
+            warn!("Ignoring corrupt session file {}: {}", path.display(), err);
+            None
+        }
+    }
+}
@@ -0,0 +1,168 @@
+use bevy::prelude::*;
+use bevy::render::camera::{RenderTarget, ScalingMode};
+use bevy::render::render_resource::{
+    Extent3d, TextureDescriptor, TextureDimension, TextureFormat, TextureUsages,
+};
+use bevy::render::view::RenderLayers;
+use bevy::window::PrimaryWindow;
+
+use crate::layout::{TerminalLayout, TERMINAL_CENTER};
+use crate::visuals::VisualSettings;
+
+/// The render layer only the screen camera sees.
+const SCREEN_LAYER: usize = 1;
+/// How rough the screen's glass is, low enough to catch the neon around it.
+const SCREEN_ROUGHNESS: f32 = 0.25;
+
+/// The texture the terminal and side log text is drawn into, which the
+/// background quad shows as a lit screen in the scene. Missing when
+/// `flat_text` is on.
+#[derive(Resource)]
+pub struct TerminalScreen {
+    image: Handle<Image>,
+}
+
+impl TerminalScreen {
+    /// Makes `material` a screen showing the text, glowing through the
+    /// background and lit by the scene like anything else in it.
+    pub fn show_on(&self, material: &mut StandardMaterial, visuals: &VisualSettings) {
+        // The texture can't hold colours past white, so the glow is here
+        let glow = if visuals.bloom {
+            visuals.text_glow
+        } else {
+            1.0
+        };
+        material.emissive = LinearRgba::WHITE * glow;
+        material.emissive_texture = Some(self.image.clone());
+        material.perceptual_roughness = SCREEN_ROUGHNESS;
+        material.unlit = false;
+    }
+}
+
+/// The layers text on the terminal goes on: the screen's, when there is
+/// one, or else the scene's own.
+pub fn text_layers(screen: Option<&TerminalScreen>) -> RenderLayers {
+    match screen {
+        Some(_) => RenderLayers::layer(SCREEN_LAYER),
+        None => RenderLayers::default(),
+    }
+}
+
+/// Marks the camera that draws the text into the screen's texture.
+#[derive(Component)]
+pub struct ScreenCamera;
+
+fn screen_extent(size: UVec2) -> Extent3d {
+    Extent3d {
+        width: size.x,
+        height: size.y,
+        depth_or_array_layers: 1,
+    }
+}
+
+/// A view of exactly the background quad, so the texture lines up with it.
+fn screen_scaling(layout: &TerminalLayout) -> ScalingMode {
+    ScalingMode::Fixed {
+        width: layout.size.x,
+        height: layout.size.y,
+    }
+}
+
+/// Creates the screen's texture and the camera that draws the text into
+/// it, unless `flat_text` is on.
+pub fn setup_screen(
+    mut commands: Commands,
+    visuals: Res<VisualSettings>,
+    layout: Res<TerminalLayout>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    if visuals.flat_text {
+        return;
+    }
+    let height = windows.get_single().map_or(0.0, |window| window.height());
+    let size = screen_extent(layout.screen_resolution(height));
+    let mut image = Image {
+        texture_descriptor: TextureDescriptor {
+            label: Some("terminal_screen"),
+            size,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Bgra8UnormSrgb,
+            mip_level_count: 1,
+            sample_count: 1,
+            usage: TextureUsages::TEXTURE_BINDING
+                | TextureUsages::COPY_DST
+                | TextureUsages::RENDER_ATTACHMENT,
+            view_formats: &[],
+        },
+        ..default()
+    };
+    image.resize(size);
+    let image = images.add(image);
+
+    let mut camera = Camera2dBundle {
+        camera: Camera {
+            target: RenderTarget::Image(image.clone()),
+            // Drawn before the scene that shows it
+            order: -1,
+            // Only the text glows; the background shows through the rest
+            clear_color: ClearColorConfig::Custom(Color::NONE),
+            ..default()
+        },
+        ..default()
+    };
+    camera.projection.scaling_mode = screen_scaling(&layout);
+    camera.transform.translation.x = TERMINAL_CENTER.x;
+    camera.transform.translation.y = TERMINAL_CENTER.y;
+    commands.spawn((camera, RenderLayers::layer(SCREEN_LAYER), ScreenCamera));
+    commands.insert_resource(TerminalScreen { image });
+}
+
+/// Keeps the screen's texture and view the size of the background quad
+/// as the window changes.
+pub fn resize_screen(
+    layout: Res<TerminalLayout>,
+    screen: Option<Res<TerminalScreen>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    mut images: ResMut<Assets<Image>>,
+    mut cameras: Query<&mut OrthographicProjection, With<ScreenCamera>>,
+) {
+    let Some(screen) = screen else {
+        return;
+    };
+    if !layout.is_changed() {
+        return;
+    }
+    let Ok(window) = windows.get_single() else {
+        return;
+    };
+    let size = screen_extent(layout.screen_resolution(window.height()));
+    if let Some(image) = images.get_mut(&screen.image) {
+        if image.texture_descriptor.size != size {
+            image.resize(size);
+        }
+    }
+    for mut projection in &mut cameras {
+        projection.scaling_mode = screen_scaling(&layout);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::DEFAULT_SPLIT;
+
+    #[test]
+    fn the_screen_camera_frames_the_quad_at_the_texture_aspect() {
+        // 16:9 and 21:9
+        for window in [Vec2::new(1920.0, 1080.0), Vec2::new(2560.0, 1080.0)] {
+            let layout = TerminalLayout::new(window, 1.0, DEFAULT_SPLIT);
+            let ScalingMode::Fixed { width, height } = screen_scaling(&layout) else {
+                panic!("the screen camera should have a fixed view");
+            };
+            assert_eq!(Vec2::new(width, height), layout.size);
+            let texture = layout.screen_resolution(window.y).as_vec2();
+            assert!((texture.x / texture.y - width / height).abs() < 0.01);
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use bevy::prelude::*;
+use std::collections::VecDeque;
+use std::fs;
+
+use crate::assets::asset_path;
+
+/// How many scripts may be running inside one another, so a script that
+/// runs itself stops instead of queueing forever.
+pub const MAX_SCRIPT_DEPTH: usize = 4;
+
+/// One command of a script, with its line number in the file.
+#[derive(Clone, PartialEq, Debug)]
+pub struct ScriptLine {
+    pub number: usize,
+    pub command: String,
+}
+
+/// A script file from `assets/scripts`, parsed into its commands.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Script {
+    pub name: String,
+    pub lines: Vec<ScriptLine>,
+}
+
+impl Script {
+    /// Every line that isn't blank or a `#` comment.
+    pub fn parse(name: &str, contents: &str) -> Self {
+        let lines = contents
+            .lines()
+            .enumerate()
+            .map(|(i, line)| (i + 1, line.trim()))
+            .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+            .map(|(number, command)| ScriptLine {
+                number,
+                command: command.to_string(),
+            })
+            .collect();
+        Script {
+            name: name.to_string(),
+            lines,
+        }
+    }
+
+    /// Loads `assets/scripts/<name>`, or `None` if there is no such script.
+    pub fn load(name: &str) -> Option<Self> {
+        // Keep the name from reaching outside the scripts directory
+        if !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.')
+            || name.starts_with('.')
+        {
+            return None;
+        }
+        let contents = fs::read_to_string(asset_path(&format!("scripts/{}", name))).ok()?;
+        Some(Script::parse(name, &contents))
+    }
+}
+
+/// A script command waiting to run.
+#[derive(Clone, PartialEq, Debug)]
+pub struct QueuedLine {
+    pub script: String,
+    pub line: ScriptLine,
+    /// How many scripts it is running inside, counting its own.
+    pub depth: usize,
+}
+
+/// Commands from running scripts, run one at a time whenever the terminal
+/// is free, in the order a shell would run them.
+#[derive(Resource, Default)]
+pub struct ScriptQueue(pub VecDeque<QueuedLine>);
+
+impl ScriptQueue {
+    /// Queues `script` to run next, ahead of what is left of the script
+    /// that ran it, if any. `parent` is the depth of that script, or 0 when
+    /// it was typed. Returns false if that would nest too deep.
+    pub fn start(&mut self, script: Script, parent: usize) -> bool {
+        let depth = parent + 1;
+        if depth > MAX_SCRIPT_DEPTH {
+            return false;
+        }
+        for line in script.lines.into_iter().rev() {
+            self.0.push_front(QueuedLine {
+                script: script.name.clone(),
+                line,
+                depth,
+            });
+        }
+        true
+    }
+
+    pub fn next(&mut self) -> Option<QueuedLine> {
+        self.0.pop_front()
+    }
+
+    pub fn is_running(&self) -> bool {
+        !self.0.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scripts_skip_comments_and_nest_in_order() {
+        let outer = Script::parse("outer.sh", "# recon\n\nnmap x\n  run inner.sh\npwd\n");
+        assert_eq!(
+            outer.lines,
+            vec![
+                ScriptLine {
+                    number: 3,
+                    command: "nmap x".to_string()
+                },
+                ScriptLine {
+                    number: 4,
+                    command: "run inner.sh".to_string()
+                },
+                ScriptLine {
+                    number: 5,
+                    command: "pwd".to_string()
+                },
+            ]
+        );
+        let mut queue = ScriptQueue::default();
+        assert!(queue.start(outer, 0));
+        queue.next();
+        let run = queue.next().unwrap();
+        assert!(queue.start(Script::parse("inner.sh", "ls"), run.depth));
+        let commands: Vec<(String, usize)> = queue
+            .0
+            .iter()
+            .map(|queued| (queued.line.command.clone(), queued.depth))
+            .collect();
+        assert_eq!(commands, [("ls".to_string(), 2), ("pwd".to_string(), 1)]);
+        assert!(!queue.start(Script::parse("deep.sh", "ls"), MAX_SCRIPT_DEPTH));
+    }
+}
@@ -0,0 +1,150 @@
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::prelude::*;
+
+use crate::markup;
+use crate::terminal::{ScrollbackState, TerminalState};
+
+/// Searching back through the output, entered with Ctrl+F.
+#[derive(Resource, Default)]
+pub struct SearchState {
+    pub query: String,
+    /// Scrollback lines containing the query, oldest first.
+    pub matches: Vec<usize>,
+    /// Which of `matches` the view is on.
+    pub current: usize,
+}
+
+impl SearchState {
+    /// Finds the query in every line of `scrollback`, ignoring case and
+    /// colours. An empty query matches nothing.
+    pub fn find(&mut self, scrollback: &ScrollbackState) {
+        let query = self.query.to_lowercase();
+        self.matches = if query.is_empty() {
+            Vec::new()
+        } else {
+            scrollback
+                .iter()
+                .enumerate()
+                .filter(|(_, line)| markup::strip(line).to_lowercase().contains(&query))
+                .map(|(index, _)| index)
+                .collect()
+        };
+        self.current = self.current.min(self.matches.len().saturating_sub(1));
+    }
+
+    /// Moves to the next match towards older output, or newer with
+    /// `newer`, wrapping around at either end.
+    pub fn step(&mut self, newer: bool) {
+        let len = self.matches.len();
+        if len == 0 {
+            return;
+        }
+        self.current = if newer {
+            (self.current + 1) % len
+        } else {
+            (self.current + len - 1) % len
+        };
+    }
+
+    /// The scrollback line of the current match, if there is one.
+    pub fn current_line(&self) -> Option<usize> {
+        self.matches.get(self.current).copied()
+    }
+}
+
+/// Ctrl+F starts a search. While searching, typing edits the query,
+/// Enter and Shift+Enter step to older and newer matches, and Escape goes
+/// back to the prompt.
+pub fn handle_search(
+    mut keys: ResMut<ButtonInput<KeyCode>>,
+    mut key_evr: EventReader<KeyboardInput>,
+    mut search: ResMut<SearchState>,
+    mut state: ResMut<TerminalState>,
+    mut scrollback: ResMut<ScrollbackState>,
+) {
+    let ctrl = keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    if !state.is_searching() {
+        key_evr.clear();
+        if ctrl && keys.just_pressed(KeyCode::KeyF) && state.start_search() {
+            *search = SearchState::default();
+        }
+        return;
+    }
+    let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    let mut edited = false;
+    let mut stepped = false;
+    for ev in key_evr.read().filter(|ev| ev.state.is_pressed()) {
+        match &ev.logical_key {
+            Key::Escape => {
+                state.stop_search();
+                scrollback.scroll_to_bottom();
+                // Leaving the search shouldn't also open the pause menu
+                keys.clear_just_pressed(KeyCode::Escape);
+                return;
+            }
+            Key::Enter => {
+                search.step(shift);
+                stepped = true;
+            }
+            Key::Backspace => edited |= search.query.pop().is_some(),
+            Key::Space => {
+                search.query.push(' ');
+                edited = true;
+            }
+            Key::Character(text) if !ctrl => {
+                search.query.push_str(text);
+                edited = true;
+            }
+            _ => {}
+        }
+    }
+    if edited {
+        search.find(&scrollback);
+        // A new query starts from the newest output
+        search.current = search.matches.len().saturating_sub(1);
+    } else if scrollback.is_changed() {
+        search.find(&scrollback);
+    }
+    if edited || stepped {
+        if let Some(line) = search.current_line() {
+            scrollback.show_line(line);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout::TerminalLayout;
+
+    #[test]
+    fn finds_lines_ignoring_case_and_steps_between_them() {
+        let layout = TerminalLayout::default();
+        let mut scrollback = ScrollbackState::default();
+        for i in 0..100 {
+            let line = match i {
+                10 => "> {red}Password rejected{/}".to_string(),
+                60 => "> password accepted".to_string(),
+                _ => format!("> line {}", i),
+            };
+            scrollback.push(&line, &layout);
+        }
+        let mut search = SearchState {
+            query: "PASSWORD".to_string(),
+            ..default()
+        };
+        search.find(&scrollback);
+        assert_eq!(search.matches, [10, 60]);
+        search.current = 1;
+        search.step(false);
+        assert_eq!(search.current_line(), Some(10));
+        search.step(false);
+        assert_eq!(search.current_line(), Some(60));
+
+        scrollback.show_line(10);
+        assert!(scrollback.visible_range().contains(&10));
+        search.query.clear();
+        search.find(&scrollback);
+        assert_eq!(search.current_line(), None);
+    }
+}
@@ -0,0 +1,180 @@
+use bevy::prelude::*;
+use bevy::sprite::Anchor;
+use bevy::text::TextLayoutInfo;
+use bevy::window::PrimaryWindow;
+
+use crate::clipboard;
+use crate::terminal::{PromptSections, TerminalText};
+
+/// How long a copied word stays highlighted.
+const FLASH_SECONDS: f32 = 0.25;
+
+/// A word of output and the area it covers, in the terminal text's local
+/// coordinates.
+pub struct WordHitbox {
+    pub rect: Rect,
+    pub word: String,
+}
+
+/// Where each word of output was laid out, rebuilt whenever the text is.
+#[derive(Resource, Default)]
+pub struct WordHitboxes(pub Vec<WordHitbox>);
+
+/// Highlight drawn over a word that was just copied.
+#[derive(Component)]
+pub struct WordFlash(Timer);
+
+/// Punctuation that sticks to words in output but isn't part of the name
+/// someone clicking would want, e.g. the dots in `Scanning host...`.
+fn trim_word(word: &str) -> &str {
+    word.trim_matches(|c: char| {
+        matches!(
+            c,
+            '.' | ',' | ';' | ':' | '\'' | '"' | '(' | ')' | '?' | '!'
+        )
+    })
+}
+
+pub fn update_word_hitboxes(
+    windows: Query<&Window, With<PrimaryWindow>>,
+    text_query: Query<
+        (&Text, &TextLayoutInfo, &Anchor, &PromptSections),
+        (With<TerminalText>, Changed<TextLayoutInfo>),
+    >,
+    mut hitboxes: ResMut<WordHitboxes>,
+) {
+    let Ok((text, layout, anchor, prompt)) = text_query.get_single() else {
+        return;
+    };
+    let scale = windows
+        .get_single()
+        .map_or(1.0, |window| window.scale_factor())
+        .recip();
+    // Same offset `Text2d` applies to place glyphs around its anchor
+    let origin = layout.logical_size * -(anchor.as_vec() + 0.5);
+    let output_sections = prompt.start(text);
+
+    hitboxes.0.clear();
+    let mut current: Option<(WordHitbox, usize, usize)> = None;
+    for glyph in &layout.glyphs {
+        if glyph.section_index >= output_sections {
+            continue;
+        }
+        let value = &text.sections[glyph.section_index].value;
+        let Some(c) = value[glyph.byte_index..].chars().next() else {
+            continue;
+        };
+        let center = origin + glyph.position * scale;
+        let rect = Rect::from_center_size(center, glyph.size * scale);
+        // A glyph continues the word if it is the very next char of the same section
+        let continues = current.as_ref().is_some_and(|(_, section, next_byte)| {
+            *section == glyph.section_index && *next_byte == glyph.byte_index
+        });
+        if !continues {
+            if let Some((word, _, _)) = current.take() {
+                hitboxes.0.push(word);
+            }
+        }
+        if c.is_whitespace() {
+            continue;
+        }
+        let next_byte = glyph.byte_index + c.len_utf8();
+        current = Some(match current.take() {
+            Some((mut word, section, _)) => {
+                word.rect = word.rect.union(rect);
+                word.word.push(c);
+                (word, section, next_byte)
+            }
+            None => (
+                WordHitbox {
+                    rect,
+                    word: c.to_string(),
+                },
+                glyph.section_index,
+                next_byte,
+            ),
+        });
+    }
+    if let Some((word, _, _)) = current {
+        hitboxes.0.push(word);
+    }
+}
+
+/// Copies the word under a left click and flashes it. Clicks between words
+/// or off the text do nothing.
+pub fn copy_clicked_word(
+    mut commands: Commands,
+    mouse: Res<ButtonInput<MouseButton>>,
+    windows: Query<&Window, With<PrimaryWindow>>,
+    // The scene's camera, not the one drawing the text onto the screen
+    camera_query: Query<(&Camera, &GlobalTransform), With<Camera3d>>,
+    text_query: Query<(Entity, &GlobalTransform), With<TerminalText>>,
+    hitboxes: Res<WordHitboxes>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    if !mouse.just_pressed(MouseButton::Left) {
+        return;
+    }
+    let Some(cursor) = windows.get_single().ok().and_then(Window::cursor_position) else {
+        return;
+    };
+    let Ok((camera, camera_transform)) = camera_query.get_single() else {
+        return;
+    };
+    let Ok((text_entity, text_transform)) = text_query.get_single() else {
+        return;
+    };
+    let Some(ray) = camera.viewport_to_world(camera_transform, cursor) else {
+        return;
+    };
+    let plane = InfinitePlane3d::new(text_transform.back());
+    let Some(distance) = ray.intersect_plane(text_transform.translation(), plane) else {
+        return;
+    };
+    let local = text_transform
+        .affine()
+        .inverse()
+        .transform_point3(ray.get_point(distance))
+        .truncate();
+    let Some(hit) = hitboxes.0.iter().find(|hitbox| hitbox.rect.contains(local)) else {
+        return;
+    };
+    let word = trim_word(&hit.word);
+    if word.is_empty() {
+        return;
+    }
+    if let Err(err) = clipboard::write_text(word) {
+        warn!("Could not copy to clipboard: {}", err);
+        return;
+    }
+    let flash = commands
+        .spawn((
+            PbrBundle {
+                mesh: meshes.add(Rectangle::from_size(hit.rect.size())),
+                material: materials.add(StandardMaterial {
+                    base_color: Color::srgba(0.0, 1.0, 0.0, 0.35),
+                    alpha_mode: AlphaMode::Blend,
+                    unlit: true,
+                    ..default()
+                }),
+                transform: Transform::from_translation(hit.rect.center().extend(0.01)),
+                ..default()
+            },
+            WordFlash(Timer::from_seconds(FLASH_SECONDS, TimerMode::Once)),
+        ))
+        .id();
+    commands.entity(text_entity).add_child(flash);
+}
+
+pub fn fade_word_flash(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut flashes: Query<(Entity, &mut WordFlash)>,
+) {
+    for (entity, mut flash) in &mut flashes {
+        if flash.0.tick(time.delta()).finished() {
+            commands.entity(entity).despawn_recursive();
+        }
+    }
+}
@@ -0,0 +1,229 @@
+use bevy::prelude::*;
+
+use crate::commands::GameLogicState;
+use crate::layout::TerminalLayout;
+use crate::locale::tr;
+use crate::network::HOME_HOST;
+use crate::scripts::ScriptQueue;
+use crate::terminal::{
+    PendingOutput, ResponseDelay, ScrollbackState, TerminalConfig, TerminalState,
+};
+
+/// Ctrl+1 to Ctrl+9 pick a session, so there can be no more than nine.
+pub const MAX_SESSIONS: usize = 9;
+
+const SESSION_KEYS: [KeyCode; MAX_SESSIONS] = [
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+];
+
+/// One terminal: its input line and history, its output, and where it is
+/// connected. The world, the trace and jobs are shared by all of them.
+#[derive(Default)]
+pub struct Session {
+    pub terminal: TerminalState,
+    pub scrollback: ScrollbackState,
+    pub host: String,
+    pub cwd: String,
+    pub hops: Vec<String>,
+}
+
+/// Every open terminal session. The active one lives in the usual
+/// resources, `TerminalState`, `ScrollbackState` and the host and working
+/// directory in `GameLogicState`, so the systems that read them only ever
+/// see it; its entry in `list` is a stand-in until it is switched away from.
+#[derive(Resource)]
+pub struct Sessions {
+    pub active: usize,
+    pub list: Vec<Session>,
+}
+
+impl Default for Sessions {
+    fn default() -> Self {
+        Sessions {
+            active: 0,
+            list: vec![Session::default()],
+        }
+    }
+}
+
+impl Sessions {
+    /// `[2] ` before the prompt of session 2, or nothing while there is
+    /// only one session.
+    pub fn tag(&self) -> String {
+        if self.list.len() < 2 {
+            return String::new();
+        }
+        format!("[{}] ", self.active + 1)
+    }
+
+    /// Makes session `index` the active one, opening `fresh` if `index` is
+    /// one past the last session. Returns false if there is no such session.
+    pub fn switch_to(
+        &mut self,
+        index: usize,
+        terminal: &mut TerminalState,
+        scrollback: &mut ScrollbackState,
+        game: &mut GameLogicState,
+        fresh: impl FnOnce() -> Session,
+    ) -> bool {
+        if index == self.active || index > self.list.len() || index >= MAX_SESSIONS {
+            return false;
+        }
+        if index == self.list.len() {
+            self.list.push(fresh());
+        }
+        // Put the active session away, then bring the chosen one out in
+        // its place, leaving the stand-in behind in its slot
+        for slot in [self.active, index] {
+            let session = &mut self.list[slot];
+            std::mem::swap(&mut session.terminal, terminal);
+            std::mem::swap(&mut session.scrollback, scrollback);
+            std::mem::swap(&mut session.host, &mut game.current_host);
+            std::mem::swap(&mut session.cwd, &mut game.cwd);
+            std::mem::swap(&mut session.hops, &mut game.hops);
+        }
+        self.active = index;
+        true
+    }
+
+    /// Drops every connection in the background sessions, as when a trace
+    /// completes.
+    pub fn send_home(&mut self) {
+        for (i, session) in self.list.iter_mut().enumerate() {
+            if i != self.active {
+                session.host = HOME_HOST.to_string();
+                session.cwd = "/".to_string();
+                session.hops.clear();
+            }
+        }
+    }
+}
+
+/// Ctrl+1 to Ctrl+9 switch to that session, opening the next one when it
+/// doesn't exist yet, and Ctrl+Tab cycles through them. Switching waits
+/// until the active session has finished running its command.
+pub fn switch_session(
+    keys: Res<ButtonInput<KeyCode>>,
+    config: Res<TerminalConfig>,
+    layout: Res<TerminalLayout>,
+    delay: Res<ResponseDelay>,
+    scripts: Res<ScriptQueue>,
+    mut sessions: ResMut<Sessions>,
+    mut state: ResMut<TerminalState>,
+    mut scrollback: ResMut<ScrollbackState>,
+    mut game: ResMut<GameLogicState>,
+    mut pending: ResMut<PendingOutput>,
+) {
+    if !keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+        return;
+    }
+    let index = if keys.just_pressed(KeyCode::Tab) {
+        (sessions.active + 1) % sessions.list.len()
+    } else {
+        match SESSION_KEYS.iter().position(|key| keys.just_pressed(*key)) {
+            Some(index) => index,
+            None => return,
+        }
+    };
+    if state.is_confirming()
+        || state.is_searching()
+        || state.is_replaying()
+        || delay.is_waiting()
+        || !delay.queued.is_empty()
+        || scripts.is_running()
+        // Scans, challenges and downloads stay on the shared game state,
+        // so they'd carry on in whichever session came next
+        || game.scan.is_some()
+        || game.puzzle.is_some()
+        || game.download.is_some()
+    {
+        return;
+    }
+    // Output still being typed out belongs to the session it came from
+    pending.flush(&mut scrollback);
+    let rows = scrollback.rows();
+    let opened = tr!(game.locale, "session.opened", number = index + 1);
+    sessions.switch_to(index, &mut state, &mut scrollback, &mut game, || {
+        let mut scrollback = ScrollbackState::new(&config);
+        scrollback.set_rows(rows);
+        scrollback.push(&opened, &layout);
+        Session {
+            terminal: TerminalState::default(),
+            scrollback,
+            host: HOME_HOST.to_string(),
+            cwd: "/".to_string(),
+            hops: Vec::new(),
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::commands::CommandRegistry;
+    use bevy::ecs::system::RunSystemOnce;
+
+    #[test]
+    fn switching_swaps_output_and_connection() {
+        let layout = TerminalLayout::default();
+        let mut sessions = Sessions::default();
+        let mut terminal = TerminalState::default();
+        let mut scrollback = ScrollbackState::default();
+        let mut game = GameLogicState::default();
+        scrollback.push("> first", &layout);
+        game.current_host = "neotechlabs.com".to_string();
+        let fresh = || Session {
+            host: HOME_HOST.to_string(),
+            cwd: "/".to_string(),
+            ..default()
+        };
+
+        assert!(!sessions.switch_to(2, &mut terminal, &mut scrollback, &mut game, fresh));
+        assert!(sessions.switch_to(1, &mut terminal, &mut scrollback, &mut game, fresh));
+        assert_eq!(sessions.tag(), "[2] ");
+        assert_eq!(scrollback.pushed(), 0);
+        assert_eq!(game.current_host, HOME_HOST);
+
+        sessions.send_home();
+        assert!(sessions.switch_to(0, &mut terminal, &mut scrollback, &mut game, fresh));
+        assert_eq!(scrollback.lines_since(0).collect::<Vec<_>>(), ["> first"]);
+        assert_eq!(game.current_host, HOME_HOST);
+        assert_eq!(sessions.list.len(), 2);
+    }
+
+    #[test]
+    fn no_switching_while_a_scan_runs() {
+        let mut world = World::new();
+        let mut game = GameLogicState::default();
+        CommandRegistry::default().evaluate_command("nmap neotechlabs.com", &mut game);
+        assert!(game.scan.is_some());
+        world.insert_resource(game);
+        let mut keys = ButtonInput::<KeyCode>::default();
+        keys.press(KeyCode::ControlLeft);
+        keys.press(KeyCode::Digit2);
+        world.insert_resource(keys);
+        world.init_resource::<TerminalConfig>();
+        world.init_resource::<TerminalLayout>();
+        world.init_resource::<ResponseDelay>();
+        world.init_resource::<ScriptQueue>();
+        world.init_resource::<Sessions>();
+        world.init_resource::<TerminalState>();
+        world.init_resource::<ScrollbackState>();
+        world.init_resource::<PendingOutput>();
+        world.run_system_once(switch_session);
+        let sessions = world.resource::<Sessions>();
+        assert_eq!((sessions.active, sessions.list.len()), (0, 1));
+        // Once it's done, Ctrl+2 opens the second session as usual
+        world.resource_mut::<GameLogicState>().scan = None;
+        world.run_system_once(switch_session);
+        assert_eq!(world.resource::<Sessions>().active, 1);
+    }
+}
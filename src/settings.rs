@@ -0,0 +1,239 @@
+use bevy::prelude::*;
+use bevy::window::WindowMode;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::locale::{tr, Locale};
+
+/// Slowest and fastest `settings speed` allows, in characters per second.
+const MIN_CHARS_PER_SECOND: u32 = 10;
+const MAX_CHARS_PER_SECOND: u32 = 5000;
+
+/// How fast responses are typed out into the terminal.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum OutputSpeed {
+    CharsPerSecond(u32),
+    /// No typewriter at all: responses show the moment they're ready.
+    Instant,
+}
+
+impl Default for OutputSpeed {
+    fn default() -> Self {
+        OutputSpeed::CharsPerSecond(100)
+    }
+}
+
+impl OutputSpeed {
+    /// `instant`, or a number of characters per second, kept in range.
+    pub fn parse(arg: &str) -> Option<Self> {
+        if arg.eq_ignore_ascii_case("instant") {
+            return Some(OutputSpeed::Instant);
+        }
+        let chars = arg.parse::<u32>().ok()?;
+        Some(OutputSpeed::CharsPerSecond(
+            chars.clamp(MIN_CHARS_PER_SECOND, MAX_CHARS_PER_SECOND),
+        ))
+    }
+
+    pub fn describe(self, locale: &Locale) -> String {
+        match self {
+            OutputSpeed::CharsPerSecond(chars) => {
+                tr!(locale, "settings.speed_chars", chars = chars)
+            }
+            OutputSpeed::Instant => locale.get("settings.speed_instant").to_string(),
+        }
+    }
+}
+
+/// How the window fills the screen. F11 steps through them in order.
+#[derive(Serialize, Deserialize, Default, Clone, Copy, PartialEq, Eq, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum DisplayMode {
+    #[default]
+    Windowed,
+    /// A window without decorations covering the whole monitor.
+    Borderless,
+    /// Exclusive fullscreen, at the monitor's own video mode.
+    Fullscreen,
+}
+
+impl DisplayMode {
+    pub fn parse(arg: &str) -> Option<Self> {
+        match arg.to_ascii_lowercase().as_str() {
+            "windowed" => Some(DisplayMode::Windowed),
+            "borderless" => Some(DisplayMode::Borderless),
+            "fullscreen" => Some(DisplayMode::Fullscreen),
+            _ => None,
+        }
+    }
+
+    /// The mode F11 switches to from this one.
+    pub fn next(self) -> Self {
+        match self {
+            DisplayMode::Windowed => DisplayMode::Borderless,
+            DisplayMode::Borderless => DisplayMode::Fullscreen,
+            DisplayMode::Fullscreen => DisplayMode::Windowed,
+        }
+    }
+
+    pub fn window_mode(self) -> WindowMode {
+        match self {
+            DisplayMode::Windowed => WindowMode::Windowed,
+            DisplayMode::Borderless => WindowMode::BorderlessFullscreen,
+            DisplayMode::Fullscreen => WindowMode::Fullscreen,
+        }
+    }
+
+    pub fn describe(self, locale: &Locale) -> &str {
+        locale.get(match self {
+            DisplayMode::Windowed => "settings.window_windowed",
+            DisplayMode::Borderless => "settings.window_borderless",
+            DisplayMode::Fullscreen => "settings.window_fullscreen",
+        })
+    }
+}
+
+/// What `settings` and `opacity` change, kept between runs.
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(default)]
+pub struct Settings {
+    pub output_speed: OutputSpeed,
+    /// The window mode last used, with F11 or `settings window`.
+    pub display_mode: DisplayMode,
+    /// Whether a unique prefix runs a command, as `nm` does `nmap`. Off
+    /// unless asked for, so a new runner's typos aren't run as commands.
+    pub prefix_matching: bool,
+    /// How opaque the terminal background is, in percent.
+    pub opacity: u8,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            output_speed: OutputSpeed::default(),
+            display_mode: DisplayMode::default(),
+            prefix_matching: false,
+            opacity: 100,
+        }
+    }
+}
+
+/// Where settings are kept, in the platform config dir.
+pub fn settings_path() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join("neon-city").join("settings.json"))
+}
+
+impl Settings {
+    /// The settings saved last time at `path`, or the defaults if there
+    /// are none.
+    pub fn load(path: Option<&Path>) -> Self {
+        let Some(path) = path else {
+            return Settings::default();
+        };
+        let contents = match fs::read_to_string(path) {
+            Ok(contents) => contents,
+            Err(err) => {
+                if err.kind() != io::ErrorKind::NotFound {
+                    warn!("Could not read {}: {}", path.display(), err);
+                }
+                return Settings::default();
+            }
+        };
+        serde_json::from_str(&contents).unwrap_or_else(|err| {
+            warn!("Ignoring invalid {}: {}", path.display(), err);
+            Settings::default()
+        })
+    }
+
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)
+    }
+
+    /// Saves the settings at `path` with `change` made to them, leaving the
+    /// rest as they were. Without a path, as in a headless run, the change
+    /// only lasts the run and nothing is written.
+    pub fn update(path: Option<&Path>, change: impl FnOnce(&mut Settings)) -> io::Result<()> {
+        let Some(path) = path else {
+            return Ok(());
+        };
+        let mut settings = Settings::load(Some(path));
+        change(&mut settings);
+        settings.save(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn speeds_parse_into_range_and_round_trip() {
+        assert_eq!(OutputSpeed::parse("Instant"), Some(OutputSpeed::Instant));
+        assert_eq!(
+            OutputSpeed::parse("250"),
+            Some(OutputSpeed::CharsPerSecond(250))
+        );
+        assert_eq!(
+            OutputSpeed::parse("1"),
+            Some(OutputSpeed::CharsPerSecond(MIN_CHARS_PER_SECOND))
+        );
+        assert_eq!(OutputSpeed::parse("fast"), None);
+        let settings = Settings {
+            output_speed: OutputSpeed::Instant,
+            display_mode: DisplayMode::Borderless,
+            prefix_matching: true,
+            opacity: 45,
+        };
+        let saved = serde_json::to_string(&settings).unwrap();
+        assert_eq!(serde_json::from_str::<Settings>(&saved).unwrap(), settings);
+        assert_eq!(
+            serde_json::from_str::<Settings>("{}").unwrap(),
+            Settings::default()
+        );
+        assert_eq!(Settings::default().opacity, 100);
+    }
+
+    #[test]
+    fn updates_keep_the_other_settings_and_need_a_path() {
+        let path =
+            std::env::temp_dir().join(format!("neon-city-settings-{}.json", std::process::id()));
+        Settings::update(Some(&path), |settings| settings.opacity = 60).unwrap();
+        Settings::update(Some(&path), |settings| settings.prefix_matching = true).unwrap();
+        let saved = Settings::load(Some(&path));
+        assert_eq!((saved.opacity, saved.prefix_matching), (60, true));
+        fs::remove_file(&path).unwrap();
+        // Without a path there's nowhere to write, and nothing to read back
+        Settings::update(None, |settings| settings.opacity = 45).unwrap();
+        assert_eq!(Settings::load(None), Settings::default());
+    }
+
+    #[test]
+    fn display_modes_cycle_back_to_windowed() {
+        let mut mode = DisplayMode::default();
+        let mut seen = Vec::new();
+        for _ in 0..3 {
+            seen.push(mode.window_mode());
+            mode = mode.next();
+        }
+        assert_eq!(mode, DisplayMode::Windowed);
+        assert_eq!(
+            seen,
+            [
+                WindowMode::Windowed,
+                WindowMode::BorderlessFullscreen,
+                WindowMode::Fullscreen
+            ]
+        );
+        assert_eq!(
+            DisplayMode::parse("Borderless"),
+            Some(DisplayMode::Borderless)
+        );
+        assert_eq!(DisplayMode::parse("maximised"), None);
+    }
+}
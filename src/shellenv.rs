@@ -0,0 +1,138 @@
+use std::collections::HashMap;
+
+use crate::locale::{tr, Locale};
+use crate::output::OutputLine;
+
+/// Variables every runner starts with.
+const DEFAULT_VARS: &[(&str, &str)] = &[
+    ("USER", "runner"),
+    ("HOME", "/"),
+    ("SHELL", "/bin/nsh"),
+    ("TERM", "neon-256color"),
+];
+
+/// Shell variables, set with `export` and read back as `$NAME`. `$HOST` is
+/// the host the runner is on unless it has been exported.
+pub struct ShellEnv(pub HashMap<String, String>);
+
+impl Default for ShellEnv {
+    fn default() -> Self {
+        ShellEnv(
+            DEFAULT_VARS
+                .iter()
+                .map(|(name, value)| (name.to_string(), value.to_string()))
+                .collect(),
+        )
+    }
+}
+
+/// How many variables deep a value may refer to others before the rest is
+/// left as it is, so `export A=$A` can't hang the terminal.
+const MAX_VAR_DEPTH: usize = 8;
+
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+impl ShellEnv {
+    /// A variable's value, with the variables it refers to expanded in
+    /// turn, since `export` keeps them as they were typed.
+    fn get(&self, name: &str, host: &str, depth: usize) -> String {
+        match self.0.get(name) {
+            Some(value) if depth < MAX_VAR_DEPTH => self.expand_at(value, host, depth + 1),
+            Some(value) => value.clone(),
+            None if name == "HOST" => host.to_string(),
+            None => String::new(),
+        }
+    }
+
+    /// Replaces every `$NAME` in `line` with its value, or with nothing if
+    /// it isn't set. A `$` not followed by a name is kept as it is, and so
+    /// is one escaped or inside single quotes, as a shell would.
+    pub fn expand(&self, line: &str, host: &str) -> String {
+        self.expand_at(line, host, 0)
+    }
+
+    fn expand_at(&self, line: &str, host: &str, depth: usize) -> String {
+        let mut out = String::with_capacity(line.len());
+        let mut quote = None;
+        let mut chars = line.char_indices();
+        while let Some((index, c)) = chars.next() {
+            match (quote, c) {
+                (Some(open), c) if c == open => quote = None,
+                (Some('\''), _) => {}
+                (None, '"' | '\'') => quote = Some(c),
+                (_, '\\') => {
+                    // The escaped character is `tokenize`'s to keep
+                    out.push(c);
+                    if let Some((_, escaped)) = chars.next() {
+                        out.push(escaped);
+                    }
+                    continue;
+                }
+                (_, '$') => {
+                    let after = &line[index + 1..];
+                    let len = after.find(|c| !is_name_char(c)).unwrap_or(after.len());
+                    if len > 0 {
+                        out += &self.get(&after[..len], host, depth);
+                        // Names are ASCII, so one char per byte
+                        chars.nth(len - 1);
+                        continue;
+                    }
+                }
+                _ => {}
+            }
+            out.push(c);
+        }
+        out
+    }
+
+    /// Handles `export`, listing every variable, and `export NAME=value`.
+    pub fn export(&mut self, definition: &str, host: &str, locale: &Locale) -> Vec<OutputLine> {
+        if definition.is_empty() {
+            let mut vars: Vec<(&str, String)> = self
+                .0
+                .iter()
+                .map(|(name, value)| (name.as_str(), value.clone()))
+                .collect();
+            if !self.0.contains_key("HOST") {
+                vars.push(("HOST", host.to_string()));
+            }
+            vars.sort_unstable();
+            return vars
+                .into_iter()
+                .map(|(name, value)| OutputLine::Info(format!("export {}='{}'", name, value)))
+                .collect();
+        }
+        let Some((name, value)) = definition.split_once('=') else {
+            return vec![OutputLine::error(locale.get("export.usage"))];
+        };
+        if name.is_empty()
+            || name.starts_with(|c: char| c.is_ascii_digit())
+            || !name.chars().all(is_name_char)
+        {
+            return vec![OutputLine::Error(tr!(
+                locale,
+                "export.invalid_name",
+                name = name
+            ))];
+        }
+        let value = value.trim_matches(|c| c == '"' || c == '\'');
+        self.0.insert(name.to_string(), value.to_string());
+        Vec::new()
+    }
+}
+
+/// Handles `echo`, which prints its arguments with their quotes removed.
+/// With none it prints an empty line.
+pub fn echo(args: &[&str]) -> OutputLine {
+    let words: Vec<&str> = args
+        .iter()
+        .map(|arg| arg.trim_matches(|c| c == '"' || c == '\''))
+        .collect();
+    let text = words.join(" ");
+    if text.trim().is_empty() {
+        return OutputLine::Prompt;
+    }
+    OutputLine::Info(text)
+}
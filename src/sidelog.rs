@@ -0,0 +1,248 @@
+use bevy::prelude::*;
+use bevy::sprite::Anchor;
+use std::collections::VecDeque;
+
+use crate::a11y::A11ySettings;
+use crate::commands::GameLogicState;
+use crate::layout::TerminalLayout;
+use crate::locale::tr;
+use crate::markup::append_colored;
+use crate::network::HOME_HOST;
+use crate::screen::{text_layers, TerminalScreen};
+use crate::terminal::TerminalUi;
+use crate::theme::Theme;
+use crate::visuals::VisualSettings;
+
+/// Wrapped lines the side log shows, which fill the panel at its font size.
+const LOG_LINES: usize = 28;
+/// Seconds between ambient events.
+const AMBIENT_SECONDS: f32 = 4.0;
+/// A running trace is reported whenever its seconds left reach a multiple
+/// of this.
+const TRACE_REPORT_STEP: u32 = 5;
+/// Locale keys of the ambient events, shown in turn.
+const AMBIENT_EVENTS: &[&str] = &[
+    "sidelog.heartbeat",
+    "sidelog.sweep",
+    "sidelog.heartbeat",
+    "sidelog.packet_loss",
+    "sidelog.heartbeat",
+    "sidelog.cert_rotation",
+];
+
+/// Marks the intrusion detection log to the right of the terminal.
+#[derive(Component)]
+pub struct SideLogPanel;
+
+/// Events shown in the side log, newest last.
+#[derive(Resource)]
+pub struct SideLog {
+    /// Kept unwrapped so a resize can wrap them again to the new width.
+    entries: VecDeque<String>,
+    ambient: Timer,
+    next_ambient: usize,
+    /// Host the runner was on when last checked, to notice hops.
+    host: String,
+    /// Whole seconds left on the trace when last checked, if one is running.
+    trace_left: Option<u32>,
+}
+
+impl Default for SideLog {
+    fn default() -> Self {
+        SideLog {
+            entries: VecDeque::new(),
+            ambient: Timer::from_seconds(AMBIENT_SECONDS, TimerMode::Repeating),
+            next_ambient: 0,
+            host: HOME_HOST.to_string(),
+            trace_left: None,
+        }
+    }
+}
+
+impl SideLog {
+    /// Adds an event stamped with the in-game time. Each event wraps to at
+    /// least one line, so more than `LOG_LINES` of them are never shown.
+    fn push(&mut self, game: &GameLogicState, event: &str) {
+        if self.entries.len() >= LOG_LINES {
+            self.entries.pop_front();
+        }
+        let time = game.clock.time_of_day();
+        self.entries
+            .push_back(format!("{{gray}}{}{{/}} {}", &time[..5], event));
+    }
+
+    /// The newest `LOG_LINES` lines, wrapped to the panel.
+    fn visible(&self, layout: &TerminalLayout) -> String {
+        let lines: Vec<String> = self
+            .entries
+            .iter()
+            .flat_map(|entry| layout.wrap_log(entry))
+            .collect();
+        let start = lines.len().saturating_sub(LOG_LINES);
+        lines[start..]
+            .iter()
+            .map(|line| format!("{}\n", line))
+            .collect()
+    }
+}
+
+pub fn setup_side_log(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    layout: Res<TerminalLayout>,
+    screen: Option<Res<TerminalScreen>>,
+) {
+    commands.spawn((
+        Text2dBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font: asset_server.load("fonts/FiraMono-Regular.ttf"),
+                    font_size: layout.log_font_size(),
+                    ..default()
+                },
+            ),
+            text_anchor: Anchor::BottomLeft,
+            transform: Transform::from_translation(layout.log_origin()),
+            ..default()
+        },
+        text_layers(screen.as_deref()),
+        SideLogPanel,
+        TerminalUi,
+    ));
+}
+
+/// Logs ambient chatter, hops between hosts and the progress of a trace.
+pub fn update_side_log(time: Res<Time>, game: Res<GameLogicState>, mut log: ResMut<SideLog>) {
+    // The timer running isn't a change the panel needs to redraw for
+    if log
+        .bypass_change_detection()
+        .ambient
+        .tick(time.delta())
+        .just_finished()
+    {
+        let key = AMBIENT_EVENTS[log.next_ambient];
+        log.next_ambient = (log.next_ambient + 1) % AMBIENT_EVENTS.len();
+        log.push(&game, game.locale.get(key));
+    }
+
+    if log.host != game.current_host {
+        let event = if game.current_host == HOME_HOST {
+            tr!(game.locale, "sidelog.disconnected", host = log.host)
+        } else {
+            tr!(game.locale, "sidelog.connected", host = game.current_host)
+        };
+        log.push(&game, &event);
+        log.host = game.current_host.clone();
+    }
+
+    let trace_left = game
+        .trace
+        .active
+        .then(|| game.trace.timer.remaining_secs().ceil() as u32);
+    if trace_left == log.trace_left {
+        return;
+    }
+    match (log.trace_left, trace_left) {
+        (None, Some(left)) => {
+            log.push(
+                &game,
+                &tr!(game.locale, "sidelog.trace_started", seconds = left),
+            );
+        }
+        (Some(_), Some(left)) if left % TRACE_REPORT_STEP == 0 => {
+            log.push(
+                &game,
+                &tr!(game.locale, "sidelog.trace_progress", seconds = left),
+            );
+        }
+        (Some(_), None) => log.push(&game, game.locale.get("sidelog.trace_ended")),
+        _ => {}
+    }
+    log.bypass_change_detection().trace_left = trace_left;
+}
+
+pub fn render_side_log(
+    asset_server: Res<AssetServer>,
+    log: Res<SideLog>,
+    layout: Res<TerminalLayout>,
+    theme: Res<Theme>,
+    visuals: Res<VisualSettings>,
+    a11y: Res<A11ySettings>,
+    mut query: Query<&mut Text, With<SideLogPanel>>,
+) {
+    if !(log.is_changed()
+        || layout.is_changed()
+        || theme.is_changed()
+        || visuals.is_changed()
+        || a11y.is_changed())
+    {
+        return;
+    }
+    let Ok(mut text) = query.get_single_mut() else {
+        return;
+    };
+    text.sections.clear();
+    append_colored(
+        &mut text,
+        &log.visible(&layout),
+        &asset_server.load("fonts/FiraMono-Regular.ttf"),
+        layout.log_font_size(),
+        &a11y.theme(&theme, &visuals),
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::markup;
+    use bevy::ecs::system::RunSystemOnce;
+    use std::time::Duration;
+
+    /// The newest entry after running `update_side_log` `seconds` on.
+    fn logged_after(world: &mut World, seconds: f32) -> Option<String> {
+        let before = world.resource::<SideLog>().entries.len();
+        world
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_secs_f32(seconds));
+        world.run_system_once(update_side_log);
+        let log = world.resource::<SideLog>();
+        (log.entries.len() > before).then(|| markup::strip(log.entries.back().unwrap()))
+    }
+
+    #[test]
+    fn hops_and_a_trace_are_logged_between_the_chatter() {
+        let mut world = World::new();
+        world.init_resource::<Time>();
+        world.init_resource::<GameLogicState>();
+        world.init_resource::<SideLog>();
+        assert_eq!(logged_after(&mut world, 0.0), None);
+        world.resource_mut::<GameLogicState>().current_host = "neotechlabs.com".to_string();
+        let entry = logged_after(&mut world, 0.0).unwrap();
+        assert!(
+            entry.ends_with("session open: neotechlabs.com"),
+            "{}",
+            entry
+        );
+
+        world.resource_mut::<GameLogicState>().start_trace();
+        let entry = logged_after(&mut world, 0.0).unwrap();
+        assert!(entry.ends_with("TRACE started, 30s"), "{}", entry);
+        let tick_trace = |world: &mut World, seconds: f32| {
+            let mut game = world.resource_mut::<GameLogicState>();
+            game.trace.timer.tick(Duration::from_secs_f32(seconds));
+            logged_after(world, 0.0)
+        };
+        // Only every fifth second is worth a line
+        assert_eq!(tick_trace(&mut world, 3.0), None);
+        let entry = tick_trace(&mut world, 2.0).unwrap();
+        assert!(entry.ends_with("trace: 25s left"), "{}", entry);
+        world.resource_mut::<GameLogicState>().trace.active = false;
+        let entry = logged_after(&mut world, 0.0).unwrap();
+        assert!(entry.ends_with("trace ended"), "{}", entry);
+
+        assert_eq!(logged_after(&mut world, AMBIENT_SECONDS - 0.5), None);
+        let entry = logged_after(&mut world, 0.5).unwrap();
+        assert!(entry.ends_with("heartbeat ok"), "{}", entry);
+    }
+}
@@ -0,0 +1,72 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fs;
+
+use crate::assets::asset_path;
+
+pub const MAX_INTEGRITY: u8 = 100;
+
+/// The runner's health. At zero they flatline and the run is over.
+#[derive(Resource)]
+pub struct RunnerStatus {
+    pub integrity: u8,
+}
+
+impl Default for RunnerStatus {
+    fn default() -> Self {
+        RunnerStatus {
+            integrity: MAX_INTEGRITY,
+        }
+    }
+}
+
+/// Mistakes that cost the runner integrity.
+#[derive(Clone, Copy, PartialEq, Eq, Hash, Debug, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Failure {
+    /// `exploit` against a host with nothing vulnerable to hit.
+    ExploitFailed,
+    /// `ssh` with the wrong password.
+    AuthFailed,
+    /// A trace that ran out before being cloaked.
+    TraceCompleted,
+}
+
+/// Integrity lost to each kind of failure.
+#[derive(Resource)]
+pub struct DamageTable {
+    damage: HashMap<Failure, u8>,
+}
+
+impl Default for DamageTable {
+    fn default() -> Self {
+        DamageTable {
+            damage: HashMap::from([
+                (Failure::ExploitFailed, 20),
+                (Failure::AuthFailed, 10),
+                (Failure::TraceCompleted, 60),
+            ]),
+        }
+    }
+}
+
+impl DamageTable {
+    pub fn damage(&self, failure: Failure) -> u8 {
+        self.damage[&failure]
+    }
+
+    /// Defaults overridden by whatever `assets/damage.ron` specifies, in
+    /// the same partial-map form as the keybindings file.
+    pub fn load() -> Self {
+        let mut table = DamageTable::default();
+        let path = asset_path("damage.ron");
+        if let Ok(contents) = fs::read_to_string(&path) {
+            match ron::from_str::<HashMap<Failure, u8>>(&contents) {
+                Ok(overrides) => table.damage.extend(overrides),
+                Err(err) => warn!("Ignoring invalid {}: {}", path.display(), err),
+            }
+        }
+        table
+    }
+}
@@ -1,157 +1,2888 @@
+use bevy::input::keyboard::{Key, KeyboardInput}; // Explicit import
 use bevy::prelude::*;
-use bevy::input::keyboard::KeyboardInput; // Explicit import
+use bevy::sprite::Anchor;
+use bevy::window::WindowResized;
+use std::collections::VecDeque;
+use std::ops::Range;
+use std::path::PathBuf;
+use std::time::Duration;
 
-#[derive(Resource, Default)]
+use crate::a11y::A11ySettings;
+use crate::audio::{Sfx, Sound};
+use crate::banner;
+use crate::boot::{self, BootSequence, BootState};
+use crate::camera::CameraIntro;
+use crate::clipboard;
+use crate::commands::{self, CommandRegistry, Effect, GameLogicState};
+use crate::events::{
+    self, HostScanned, InterruptRequested, ResetRequested, TerminalEvents, TraceEvaded,
+    TraceStarted,
+};
+use crate::gamepad::{self, PadAction, PadBindings};
+use crate::glitch;
+use crate::heat::{self, Heat, HeatSettings};
+use crate::highlight;
+use crate::keybindings::{Action, Controls, KeyBindings};
+use crate::layout::TerminalLayout;
+use crate::locale::tr;
+use crate::markup::{self, append_colored, append_colored_from};
+use crate::missions;
+use crate::network::HOME_HOST;
+use crate::output::OutputLine;
+use crate::pause::RunState;
+use crate::replay::{self, Replay};
+use crate::rng::GameRng;
+use crate::save::{self, SessionData};
+use crate::screen::{self, text_layers, TerminalScreen};
+use crate::scripts::{ScriptQueue, MAX_SCRIPT_DEPTH};
+use crate::search::{self, SearchState};
+use crate::selection::{self, WordHitboxes};
+use crate::sessions::{self, Sessions};
+use crate::settings::{self, DisplayMode, OutputSpeed, Settings};
+use crate::sidelog::{self, SideLog, SideLogPanel};
+use crate::status::{DamageTable, Failure, RunnerStatus};
+use crate::theme::{self, Theme};
+use crate::transcript::{self, TranscriptLogger};
+use crate::transition::{self, ConnectTransition, TransitionSettings};
+use crate::visuals::VisualSettings;
+
+/// Something held back until the player confirms it.
+#[derive(Clone, PartialEq, Debug)]
+pub enum PendingAction {
+    /// A command line that runs a destructive command.
+    Run(String),
+}
+
+/// Whether keys edit the input line or answer a confirmation.
+#[derive(Default, Clone, PartialEq, Debug)]
+pub enum InputMode {
+    #[default]
+    Normal,
+    /// `Are you sure? (y/n)` is showing; the next key decides.
+    ConfirmPending { action: PendingAction },
+    /// Keys edit the query of a search through the output.
+    Search,
+    /// The session is being played back, and only Escape does anything.
+    Replay,
+}
+
+/// Longest input line, in chars, unless `TerminalState` says otherwise.
+const DEFAULT_MAX_INPUT_LEN: usize = 256;
+
+#[derive(Resource)]
 pub struct TerminalState {
     input: String,
+    history: Vec<String>,
+    history_index: Option<usize>,
+    /// Cursor position within `input`, counted in chars rather than bytes.
+    cursor_pos: usize,
+    locked: bool,
+    mode: InputMode,
+    /// Chars the input line can hold; typing and pasting stop there.
+    max_input_len: usize,
+}
+
+impl Default for TerminalState {
+    fn default() -> Self {
+        TerminalState {
+            input: String::new(),
+            history: Vec::new(),
+            history_index: None,
+            cursor_pos: 0,
+            locked: false,
+            mode: InputMode::default(),
+            max_input_len: DEFAULT_MAX_INPUT_LEN,
+        }
+    }
+}
+
+impl TerminalState {
+    /// The line as typed so far.
+    #[cfg(test)]
+    pub fn input(&self) -> &str {
+        &self.input
+    }
+
+    /// Commands entered so far, oldest first.
+    #[cfg(test)]
+    pub fn history(&self) -> &[String] {
+        &self.history
+    }
+
+    pub fn is_confirming(&self) -> bool {
+        matches!(self.mode, InputMode::ConfirmPending { .. })
+    }
+
+    pub fn is_searching(&self) -> bool {
+        self.mode == InputMode::Search
+    }
+
+    /// Switches keys over to searching, unless they are answering a
+    /// confirmation or the terminal is locked. Returns whether it did.
+    pub fn start_search(&mut self) -> bool {
+        if self.mode != InputMode::Normal || self.locked {
+            return false;
+        }
+        self.mode = InputMode::Search;
+        true
+    }
+
+    pub fn stop_search(&mut self) {
+        if self.is_searching() {
+            self.mode = InputMode::Normal;
+        }
+    }
+
+    pub fn is_replaying(&self) -> bool {
+        self.mode == InputMode::Replay
+    }
+
+    /// Hands the input line over to a replay, like `start_search`.
+    pub fn start_replay(&mut self) -> bool {
+        if self.mode != InputMode::Normal || self.locked {
+            return false;
+        }
+        self.mode = InputMode::Replay;
+        true
+    }
+
+    /// Gives the input line back, empty, once a replay ends.
+    pub fn stop_replay(&mut self) {
+        if self.is_replaying() {
+            self.mode = InputMode::Normal;
+            self.set_input(String::new());
+        }
+    }
+
+    /// Stops taking input until the run is restarted.
+    pub fn lock(&mut self) {
+        self.locked = true;
+    }
+
+    /// Replaces the input line, cut to `max_input_len`, and puts the
+    /// cursor at its end.
+    pub fn set_input(&mut self, input: String) {
+        self.input = input.chars().take(self.max_input_len).collect();
+        self.cursor_pos = self.input.chars().count();
+    }
+
+    /// Byte offset of the char at `char_pos`, or the end of the input.
+    fn byte_index(&self, char_pos: usize) -> usize {
+        self.input
+            .char_indices()
+            .nth(char_pos)
+            .map_or(self.input.len(), |(i, _)| i)
+    }
+
+    /// Inserts `c` at the cursor, or returns false if the line is full.
+    fn insert_char(&mut self, c: char) -> bool {
+        if self.input.chars().count() >= self.max_input_len {
+            return false;
+        }
+        let at = self.byte_index(self.cursor_pos);
+        self.input.insert(at, c);
+        self.cursor_pos += 1;
+        true
+    }
+
+    fn remove_char(&mut self, char_pos: usize) {
+        if char_pos < self.input.chars().count() {
+            let at = self.byte_index(char_pos);
+            self.input.remove(at);
+        }
+    }
+}
+
+/// Marks the main terminal text entity: output sections, then the prompt up
+/// to the cursor, the cursor itself, and the rest of the input line.
+#[derive(Component)]
+pub struct TerminalText;
+
+/// The sections that end the terminal text and make up the prompt, as
+/// `render_prompt` last split it by colour.
+#[derive(Component)]
+pub struct PromptSections {
+    /// Colour of each prompt section, as highlighting left it.
+    pub colors: Vec<Color>,
+    /// Which of them is the char under the cursor.
+    pub cursor: usize,
+}
+
+impl PromptSections {
+    fn new(color: Color) -> Self {
+        PromptSections {
+            colors: vec![color; 3],
+            cursor: 1,
+        }
+    }
+
+    /// Index of the first prompt section in `text`.
+    pub fn start(&self, text: &Text) -> usize {
+        text.sections.len() - self.colors.len()
+    }
+}
+
+/// Marks the quad drawn behind the terminal text.
+#[derive(Component)]
+pub struct TerminalBackground;
+
+/// Marks the HUD line that shows the trace countdown.
+#[derive(Component)]
+pub struct TraceHud;
+
+/// Marks every entity the terminal and its HUD are drawn with, so a reset
+/// can despawn them all and spawn them afresh.
+#[derive(Component)]
+pub struct TerminalUi;
+
+/// Present once `setup_terminal` has spawned the terminal, so running it
+/// again, as a hot reload or a second setup can, doesn't spawn a second
+/// one for `get_single` to choke on. A reset removes it along with the
+/// entities it stands for.
+#[derive(Resource)]
+pub struct TerminalInitialized;
+
+const RESIZE_DEBOUNCE_SECS: f32 = 0.2;
+
+/// Number of output lines shown at once at the normal font size;
+/// PageUp/PageDown move by a screenful.
+pub const VISIBLE_LINES: usize = 20;
+
+/// Tunables for the terminal's output buffer and input.
+#[derive(Resource)]
+pub struct TerminalConfig {
+    /// Lines of output kept for scrolling back; older lines are dropped.
+    pub max_scrollback_lines: usize,
+    /// Seconds a key has to be held before it starts repeating.
+    pub key_repeat_delay: f32,
+    /// Repeats per second once a held key is repeating.
+    pub key_repeat_rate: f32,
+    /// Whether destructive commands ask `Are you sure?` before running.
+    pub confirm_destructive: bool,
+    /// Whether startup picks up the last saved session.
+    pub restore_session: bool,
+    /// How opaque the terminal background is, in percent. Set with `opacity`.
+    pub opacity: u8,
+    /// How fast responses, the boot sequence and replays are typed out.
+    /// Set with `settings speed`, which saves it.
+    pub output_speed: OutputSpeed,
+    /// How the window fills the screen. Set with F11 or `settings window`,
+    /// which save it.
+    pub display_mode: DisplayMode,
+    /// Where `save` and exiting save the session, and startup restores it
+    /// from. `None` when there is no config dir to keep it in.
+    pub session_path: Option<PathBuf>,
+    /// Where changing a setting saves it, and startup loads it from. `None`
+    /// keeps changes to the run, as when there's no config dir.
+    pub settings_path: Option<PathBuf>,
+    /// The transition `ssh` plays logging in, from `display` in the config.
+    pub connect_transition: TransitionSettings,
+}
+
+impl Default for TerminalConfig {
+    fn default() -> Self {
+        TerminalConfig {
+            max_scrollback_lines: 1000,
+            key_repeat_delay: 0.5,
+            key_repeat_rate: 30.0,
+            confirm_destructive: true,
+            restore_session: true,
+            opacity: 100,
+            output_speed: OutputSpeed::default(),
+            display_mode: DisplayMode::default(),
+            session_path: save::session_path(),
+            settings_path: settings::settings_path(),
+            connect_transition: TransitionSettings::default(),
+        }
+    }
+}
+
+impl TerminalConfig {
+    /// Alpha of the terminal background.
+    pub fn background_alpha(&self) -> f32 {
+        f32::from(self.opacity) / 100.0
+    }
+}
+
+/// The editing key being held down, repeating like an OS keyboard would.
+#[derive(Resource)]
+pub struct KeyRepeat {
+    pub key: Option<KeyCode>,
+    /// What the held key typed, as the keyboard layout had it.
+    pub text: Option<String>,
+    pub delay: Timer,
+    pub rate: Timer,
+}
+
+impl KeyRepeat {
+    pub fn new(config: &TerminalConfig) -> Self {
+        KeyRepeat {
+            key: None,
+            text: None,
+            delay: Timer::from_seconds(config.key_repeat_delay, TimerMode::Once),
+            rate: Timer::from_seconds(config.key_repeat_rate.recip(), TimerMode::Repeating),
+        }
+    }
+
+    /// Starts timing `key`, which typed `text`, replacing any key that was
+    /// repeating before.
+    fn start(&mut self, key: KeyCode, text: Option<&str>) {
+        self.key = Some(key);
+        self.text = text.map(str::to_string);
+        self.delay.reset();
+        self.rate.reset();
+    }
+
+    /// The held key and how many times it repeats over `delta`. Releasing
+    /// it stops the repeat.
+    fn tick(&mut self, keys: &ButtonInput<KeyCode>, delta: Duration) -> Option<(KeyCode, u32)> {
+        let key = self.key?;
+        if !keys.pressed(key) {
+            self.key = None;
+            return None;
+        }
+        if self.delay.finished() {
+            return Some((key, self.rate.tick(delta).times_finished_this_tick()));
+        }
+        // However long a frame is, the repeat starts on time
+        let past_delay = delta.saturating_sub(self.delay.remaining());
+        if !self.delay.tick(delta).finished() {
+            return None;
+        }
+        let times = self.rate.tick(past_delay).times_finished_this_tick();
+        Some((key, 1 + times))
+    }
+}
+
+/// Output lines kept for scrolling back, of which only a window is rendered.
+#[derive(Resource)]
+pub struct ScrollbackState {
+    lines: VecDeque<String>,
+    /// How many lines the view is scrolled up from the bottom.
+    view_offset: usize,
+    max_lines: usize,
+    /// Lines ever added, counting ones since dropped, so readers outside
+    /// the terminal can tell which lines are new.
+    pushed: usize,
+    /// Lines shown at once.
+    rows: usize,
+    /// A line still changing, like a download's progress bar, shown below
+    /// the rest and replaced in place. It only becomes output once pushed.
+    live: Option<String>,
+}
+
+impl Default for ScrollbackState {
+    fn default() -> Self {
+        ScrollbackState::new(&TerminalConfig::default())
+    }
+}
+
+impl ScrollbackState {
+    pub fn new(config: &TerminalConfig) -> Self {
+        // Always room for a full screen, so the view is never short
+        let max_lines = config.max_scrollback_lines.max(VISIBLE_LINES);
+        ScrollbackState {
+            lines: VecDeque::with_capacity(max_lines),
+            view_offset: 0,
+            max_lines,
+            pushed: 0,
+            rows: VISIBLE_LINES,
+            live: None,
+        }
+    }
+
+    /// Shows `rows` lines at a time, e.g. fewer once the font is larger.
+    pub fn set_rows(&mut self, rows: usize) {
+        self.rows = rows.clamp(1, self.max_lines);
+        self.view_offset = self.view_offset.min(self.max_offset());
+    }
+
+    pub fn pushed(&self) -> usize {
+        self.pushed
+    }
+
+    pub fn rows(&self) -> usize {
+        self.rows
+    }
+
+    /// Lines added since `pushed()` returned `since`, as far as they are
+    /// still kept.
+    pub fn lines_since(&self, since: usize) -> impl Iterator<Item = &str> {
+        let new = self.pushed.saturating_sub(since).min(self.lines.len());
+        self.lines
+            .range(self.lines.len() - new..)
+            .map(String::as_str)
+    }
+
+    /// Drops the oldest lines beyond the cap, e.g. after restoring a
+    /// session saved with a larger one.
+    fn trim(&mut self) {
+        let excess = self.lines.len().saturating_sub(self.max_lines);
+        self.lines.drain(..excess);
+        self.view_offset = self.view_offset.min(self.max_offset());
+    }
+
+    /// Adds one line, dropping the oldest first once the buffer is full so
+    /// its storage is reused rather than grown. A view at the bottom follows
+    /// new output; a view the user scrolled up stays on the lines they were
+    /// reading.
+    fn push_line(&mut self, line: String) {
+        if self.lines.len() >= self.max_lines {
+            self.lines.pop_front();
+        }
+        self.lines.push_back(line);
+        self.pushed += 1;
+        if self.view_offset > 0 {
+            self.view_offset = (self.view_offset + 1).min(self.max_offset());
+        }
+    }
+
+    /// Appends `text` line by line, wrapped to the layout.
+    pub fn push(&mut self, text: &str, layout: &TerminalLayout) {
+        for line in text.lines().flat_map(|line| layout.wrap(line)) {
+            self.push_line(line);
+        }
+    }
+
+    pub fn live(&self) -> Option<&str> {
+        self.live.as_deref()
+    }
+
+    /// Shows `line` in place of the live line, or takes it away.
+    pub fn set_live(&mut self, line: Option<String>) {
+        self.live = line;
+    }
+
+    /// Drops every line, leaving an empty screen. `pushed()` keeps counting
+    /// from where it was, so readers of new lines aren't thrown.
+    pub fn clear(&mut self) {
+        self.lines.clear();
+        self.view_offset = 0;
+    }
+
+    /// Appends a single streamed character; `'\n'` starts a new line.
+    fn push_char(&mut self, c: char) {
+        if c == '\n' || self.lines.is_empty() {
+            self.push_line(String::new());
+        }
+        if c != '\n' {
+            if let Some(line) = self.lines.back_mut() {
+                line.push(c);
+            }
+        }
+    }
+
+    fn scroll(&mut self, up: bool) {
+        self.scroll_lines(up, self.rows);
+    }
+
+    pub fn scroll_lines(&mut self, up: bool, lines: usize) {
+        self.view_offset = if up {
+            (self.view_offset + lines).min(self.max_offset())
+        } else {
+            self.view_offset.saturating_sub(lines)
+        };
+    }
+
+    fn max_offset(&self) -> usize {
+        self.lines.len().saturating_sub(self.rows)
+    }
+
+    /// Every line kept, oldest first.
+    pub fn iter(&self) -> impl Iterator<Item = &str> {
+        self.lines.iter().map(String::as_str)
+    }
+
+    /// Scrolls so line `index` is in the middle of the view, or as near as
+    /// the ends of the buffer allow.
+    pub fn show_line(&mut self, index: usize) {
+        let below = self.lines.len().saturating_sub(index + 1);
+        self.view_offset = below.saturating_sub(self.rows / 2).min(self.max_offset());
+    }
+
+    pub fn scroll_to_bottom(&mut self) {
+        self.view_offset = 0;
+    }
+
+    /// Indices of the lines currently in view.
+    pub fn visible_range(&self) -> Range<usize> {
+        let end = self.lines.len() - self.view_offset;
+        end.saturating_sub(self.rows)..end
+    }
+
+    fn visible(&self) -> String {
+        self.lines
+            .range(self.visible_range())
+            .map(|line| format!("{}\n", line))
+            .collect()
+    }
+
+    /// Line `index` as shown, picked out if `search` matched it and
+    /// brightest if it is the current match.
+    fn shown_line(&self, index: usize, search: Option<&SearchState>) -> String {
+        let line = &self.lines[index];
+        match search {
+            Some(search) if Some(index) == search.current_line() => {
+                format!("{{cyan}}{}{{/}}\n", markup::strip(line))
+            }
+            Some(search) if search.matches.binary_search(&index).is_ok() => {
+                format!("{{yellow}}{}{{/}}\n", markup::strip(line))
+            }
+            _ => format!("{}\n", line),
+        }
+    }
+
+    /// How many lines were ever added up to and including line `index`.
+    fn pushed_at(&self, index: usize) -> usize {
+        (self.pushed + index).saturating_sub(self.lines.len())
+    }
+}
+
+/// Response text waiting to be typed out into the scrollback.
+#[derive(Resource, Default)]
+pub struct PendingOutput {
+    buffer: VecDeque<char>,
+    /// The part of a character due to be revealed that didn't make it
+    /// into the last frame.
+    carried: f32,
+}
+
+impl PendingOutput {
+    /// Queues `text` to be typed out, each of its lines wrapped to the
+    /// layout and started on a fresh output line.
+    pub fn push(&mut self, text: &str, layout: &TerminalLayout) {
+        for line in text.lines().flat_map(|line| layout.wrap(line)) {
+            self.buffer.push_back('\n');
+            self.buffer.extend(line.chars());
+        }
+    }
+
+    pub fn is_streaming(&self) -> bool {
+        !self.buffer.is_empty()
+    }
+
+    pub fn flush(&mut self, scrollback: &mut ScrollbackState) {
+        for c in self.buffer.drain(..) {
+            scrollback.push_char(c);
+        }
+    }
+}
+
+/// When each recently added output line appeared, so it can fade in.
+#[derive(Resource, Default)]
+pub struct LineFades {
+    /// `ScrollbackState::pushed()` when last looked at.
+    seen: usize,
+    /// Lines still fading in, by their `pushed()` count, with when they
+    /// appeared, oldest first.
+    fading: VecDeque<(usize, Duration)>,
+}
+
+impl LineFades {
+    /// Starts fading in every line added since the last call as of `now`,
+    /// and forgets lines done fading after `duration`.
+    fn update(&mut self, pushed: usize, now: Duration, duration: Duration) {
+        if pushed < self.seen {
+            // The scrollback was replaced by a fresh one
+            self.seen = 0;
+            self.fading.clear();
+        }
+        if !duration.is_zero() {
+            self.fading
+                .extend((self.seen..pushed).map(|line| (line, now)));
+        }
+        self.seen = pushed;
+        while self
+            .fading
+            .front()
+            .is_some_and(|(_, born)| now.saturating_sub(*born) >= duration)
+        {
+            self.fading.pop_front();
+        }
+    }
+
+    fn is_fading(&self) -> bool {
+        !self.fading.is_empty()
+    }
+
+    /// Opacity of the `line`th line ever added, from 0 as it appears to 1
+    /// once it has been showing for `duration`.
+    fn alpha(&self, line: usize, now: Duration, duration: Duration) -> f32 {
+        match self.fading.iter().find(|(fading, _)| *fading == line) {
+            Some((_, born)) => now.saturating_sub(*born).as_secs_f32() / duration.as_secs_f32(),
+            None => 1.0,
+        }
+    }
+}
+
+/// How long each step of the waiting ellipsis shows.
+const ELLIPSIS_STEP_SECS: f32 = 0.3;
+
+/// A response held back while a remote host "thinks", and the commands
+/// submitted meanwhile.
+#[derive(Resource, Default)]
+pub struct ResponseDelay {
+    pub timer: Timer,
+    pub pending: Option<String>,
+    pub queued: VecDeque<String>,
+    /// Whether Enter was just pressed on an empty line, asking to skip a
+    /// login's transition. Left for `play_connect_transition` to take.
+    pub skip_requested: bool,
+}
+
+impl ResponseDelay {
+    pub fn is_waiting(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    fn start(&mut self, seconds: f32, response: String) {
+        self.timer = Timer::from_seconds(seconds, TimerMode::Once);
+        self.pending = Some(response);
+    }
+
+    /// `.`, `..` or `...`, cycling while the response is held back.
+    fn ellipsis(&self) -> &'static str {
+        let step = (self.timer.elapsed_secs() / ELLIPSIS_STEP_SECS) as usize % 3;
+        [".", "..", "..."][step]
+    }
+}
+
+/// Blink state of the cursor drawn between the prompt sections.
+#[derive(Component)]
+pub struct CursorBlink {
+    visible: bool,
+}
+
+#[derive(Resource)]
+pub struct CursorTimer(Timer);
+
+impl Default for CursorTimer {
+    fn default() -> Self {
+        CursorTimer(Timer::from_seconds(0.5, TimerMode::Repeating))
+    }
+}
+
+/// The terminal: its state, events and commands, the clocks that drive
+/// them, and unless `headless`, the text it's drawn with and the input it
+/// reads. Insert a `CommandRegistry`, `GameLogicState`, `TranscriptLogger`,
+/// `TerminalConfig` or `TerminalLayout` before adding it to start from
+/// something other than the defaults. It spawns no camera and doesn't
+/// look for a scene, so it can sit in front of anything.
+#[derive(Default)]
+pub struct TerminalPlugin {
+    /// Leaves out everything that needs a window or loaded assets.
+    pub headless: bool,
+}
+
+impl Plugin for TerminalPlugin {
+    fn build(&self, app: &mut App) {
+        events::add_events(app);
+        app.init_state::<BootState>()
+            .init_state::<RunState>()
+            .init_resource::<CommandRegistry>()
+            .init_resource::<TranscriptLogger>()
+            .init_resource::<GameLogicState>();
+        let config = app
+            .world()
+            .resource::<GameLogicState>()
+            .config
+            .config
+            .clone();
+        app.insert_resource(config.audio())
+            .insert_resource(config.theme())
+            .init_resource::<TerminalConfig>()
+            .init_resource::<TerminalLayout>()
+            .init_resource::<RunnerStatus>()
+            .init_resource::<SideLog>()
+            .init_resource::<Sessions>()
+            .init_resource::<Replay>()
+            .init_resource::<Heat>()
+            .init_resource::<HeatSettings>()
+            .init_resource::<ConnectTransition>()
+            .insert_resource(KeyBindings::load())
+            .insert_resource(PadBindings::load())
+            .insert_resource(DamageTable::load())
+            .add_systems(Startup, init_terminal)
+            .add_systems(
+                Update,
+                (
+                    // Input and the clocks, including the trace, stop while paused
+                    (
+                        update_terminal.run_if(in_state(BootState::Ready)),
+                        // The boot sequence has nothing to interrupt
+                        interrupt_terminal.run_if(in_state(BootState::Ready)),
+                        tick_response_delay,
+                        tick_clock,
+                        tick_trace,
+                        heat::update_heat.after(update_terminal),
+                        tick_lockouts,
+                        tick_cooldowns,
+                        tick_scan,
+                        // A trace set off by a finished download counts from the next frame
+                        tick_download.after(tick_trace),
+                        tick_jobs,
+                        tick_puzzle,
+                        apply_damage,
+                        missions::track_objectives,
+                        stream_output,
+                        sidelog::update_side_log,
+                        replay::play_replay.after(update_terminal),
+                        transition::play_connect_transition
+                            .after(update_terminal)
+                            .before(tick_response_delay),
+                    )
+                        .run_if(in_state(RunState::Running)),
+                    restart_run
+                        .run_if(in_state(RunState::GameOver).or_else(in_state(RunState::Victory))),
+                    reset_run.after(update_terminal),
+                    gamepad::announce_gamepads,
+                    transcript::flush_transcript,
+                ),
+            )
+            .add_systems(Last, save_on_exit);
+        if self.headless {
+            return;
+        }
+        app.init_resource::<WordHitboxes>()
+            .init_resource::<SearchState>()
+            .init_resource::<LineFades>()
+            .insert_resource(VisualSettings::load())
+            .insert_resource(A11ySettings::load(config.a11y()))
+            .add_systems(
+                Startup,
+                (
+                    screen::setup_screen,
+                    (setup_terminal, sidelog::setup_side_log)
+                        .after(init_terminal)
+                        .after(screen::setup_screen),
+                ),
+            )
+            .add_systems(
+                Update,
+                (
+                    // Keyboard and mouse input stop while paused as well
+                    (
+                        handle_input,
+                        request_interrupt,
+                        clear_on_ctrl_l,
+                        boot::run_boot.run_if(in_state(BootState::Booting)),
+                        handle_scroll,
+                        sessions::switch_session,
+                        search::handle_search,
+                        selection::copy_clicked_word,
+                    )
+                        .run_if(in_state(RunState::Running)),
+                    (
+                        render_prompt,
+                        blink_cursor,
+                        track_line_fades,
+                        render_scrollback,
+                    )
+                        .chain(),
+                    render_trace_hud,
+                    sidelog::render_side_log,
+                    (
+                        theme::apply_theme,
+                        glitch::glitch_effect
+                            .after(theme::apply_theme)
+                            .after(render_prompt),
+                    ),
+                    (on_resize, screen::resize_screen.after(on_resize)),
+                    selection::update_word_hitboxes,
+                    selection::fade_word_flash,
+                ),
+            );
+    }
+}
+
+/// Sets up the terminal's state, restoring the last session if there is
+/// one. Nothing here needs a window, so headless runs use it too.
+pub fn init_terminal(
+    mut commands: Commands,
+    mut game: ResMut<GameLogicState>,
+    config: Res<TerminalConfig>,
+) {
+    insert_idle_terminal(&mut commands, &config);
+    let mut scrollback = ScrollbackState::new(&config);
+    let session = config
+        .restore_session
+        .then(|| config.session_path.as_deref().and_then(save::load_session))
+        .flatten();
+    match session {
+        Some(session) => {
+            scrollback.lines = session.output.into();
+            scrollback.trim();
+            game.aliases.0 = session.aliases;
+            game.env.0.extend(session.env);
+            game.inventory.items = session.inventory;
+            for (host, status) in session.discovered {
+                game.network.discover(&host, status);
+            }
+            // A hand-edited save could hold anything
+            if let Some(handle) = session.handle.filter(|h| commands::is_valid_handle(h)) {
+                game.set_handle(&handle);
+            }
+            commands.insert_resource(BootSequence::load(&game.locale, "greeting.restored"));
+            commands.insert_resource(TerminalState {
+                history: session.history,
+                ..default()
+            });
+        }
+        None => {
+            commands.insert_resource(BootSequence::load(&game.locale, "greeting.welcome"));
+            commands.insert_resource(TerminalState::default());
+        }
+    }
+    commands.insert_resource(scrollback);
+}
+
+/// The terminal's queues and timers, with nothing in them.
+fn insert_idle_terminal(commands: &mut Commands, config: &TerminalConfig) {
+    commands.insert_resource(PendingOutput::default());
+    commands.insert_resource(ResponseDelay::default());
+    commands.insert_resource(ScriptQueue::default());
+    commands.insert_resource(KeyRepeat::new(config));
 }
 
+/// Spawns the terminal quad, its text and the trace countdown, unless they
+/// are already there.
 pub fn setup_terminal(
+    initialized: Option<Res<TerminalInitialized>>,
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
+    layout: Res<TerminalLayout>,
+    scrollback: Res<ScrollbackState>,
+    theme: Res<Theme>,
+    visuals: Res<VisualSettings>,
+    a11y: Res<A11ySettings>,
+    config: Res<TerminalConfig>,
+    screen: Option<Res<TerminalScreen>>,
 ) {
-    commands.insert_resource(TerminalState::default());
-    
-    // Terminal background sprite
-    let bg_material = materials.add(StandardMaterial {
-        base_color: Color::srgb(0.0, 0.0, 0.0),
+    if initialized.is_some() {
+        warn!("The terminal is already set up; not spawning it again");
+        return;
+    }
+    commands.insert_resource(TerminalInitialized);
+    commands.insert_resource(CursorTimer::default());
+    let colors = a11y.theme(&theme, &visuals);
+
+    // Terminal background sprite, which shows the text as well unless it
+    // is drawn flat in front
+    let mut bg_material = StandardMaterial {
+        base_color: colors.background.with_alpha(config.background_alpha()),
         base_color_texture: Some(asset_server.load("sprites/terminal_bg.png")),
         alpha_mode: AlphaMode::Blend,
         unlit: true,
         ..default()
-    });
-    commands.spawn(PbrBundle {
-        mesh: meshes.add(Rectangle::new(4.0, 3.0)),
-        material: bg_material,
-        transform: Transform::from_xyz(0.0, 1.5, 0.0),
-        ..default()
-    });
+    };
+    if let Some(screen) = &screen {
+        screen.show_on(&mut bg_material, &visuals);
+    }
+    // The mesh is a unit-height quad scaled to the layout, so resizing
+    // never has to rebuild it
+    commands.spawn((
+        PbrBundle {
+            mesh: meshes.add(Rectangle::new(1.0, 1.0)),
+            material: materials.add(bg_material),
+            transform: layout.background_transform(),
+            ..default()
+        },
+        TerminalBackground,
+        TerminalUi,
+    ));
 
-    // Terminal text
+    // Terminal text: colour-tagged output followed by the prompt sections
     let font_handle = asset_server.load("fonts/FiraMono-Regular.ttf");
-    commands.spawn(Text2dBundle {
-        text: Text {
-            sections: vec![
-                TextSection {
-                    value: "Initializing...\n> Welcome to the dark pool, runner.\n".to_string(),
-                    style: TextStyle {
-                        font: font_handle.clone(),
-                        font_size: 24.0,
-                        color: Color::srgb(0.0, 1.0, 0.0),
-                    },
-                },
-                TextSection {
-                    value: "> ".to_string(),
-                    style: TextStyle {
-                        font: font_handle,
-                        font_size: 24.0,
-                        color: Color::srgb(0.0, 1.0, 0.0),
-                    },
-                },
-            ],
+    let style = TextStyle {
+        font: font_handle.clone(),
+        font_size: layout.font_size(),
+        color: colors.foreground,
+    };
+    let mut text = Text::default();
+    append_colored(
+        &mut text,
+        &scrollback.visible(),
+        &font_handle,
+        layout.font_size(),
+        &colors,
+    );
+    text.sections.extend([
+        TextSection::new(String::new(), style.clone()),
+        TextSection::new("_", style.clone()),
+        TextSection::new(String::new(), style),
+    ]);
+    commands.spawn((
+        Text2dBundle {
+            text,
+            text_anchor: Anchor::BottomLeft,
+            transform: Transform::from_translation(layout.text_origin()),
             ..default()
         },
-        transform: Transform::from_xyz(-1.8, 1.2, 0.1), // Adjusted for 3D
-        ..default()
-    });
+        CursorBlink { visible: true },
+        PromptSections::new(colors.foreground),
+        text_layers(screen.as_deref()),
+        TerminalText,
+        TerminalUi,
+    ));
+
+    // Trace countdown, empty until a trace starts
+    commands.spawn((
+        TextBundle::from_section(
+            "",
+            TextStyle {
+                font: font_handle,
+                font_size: 24.0,
+                color: Color::srgb(1.0, 0.0, 0.0),
+            },
+        )
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(10.0),
+            right: Val::Px(10.0),
+            ..default()
+        }),
+        TraceHud,
+        TerminalUi,
+    ));
 }
 
 pub fn handle_input(
+    time: Res<Time>,
     mut key_evr: EventReader<KeyboardInput>,
-    keys: Res<ButtonInput<KeyCode>>,
+    controls: Controls,
+    registry: Res<CommandRegistry>,
+    layout: Res<TerminalLayout>,
+    mut sfx: Sfx,
     mut state: ResMut<TerminalState>,
-    mut text_query: Query<&mut Text>,
+    mut scrollback: ResMut<ScrollbackState>,
+    mut repeat: ResMut<KeyRepeat>,
+    pending: Res<PendingOutput>,
+    boot: Res<State<BootState>>,
 ) {
-    let mut text = text_query.single_mut();
+    // Keys pressed during boot are drained here rather than left queued,
+    // or the one that skipped the boot would be typed once it ends
+    if state.locked
+        || state.mode != InputMode::Normal
+        || pending.is_streaming()
+        || *boot.get() == BootState::Booting
+    {
+        key_evr.clear();
+        repeat.key = None;
+        return;
+    }
+    let (keys, bindings) = (&controls.keys, &controls.bindings);
+    let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    let ctrl = keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
     for ev in key_evr.read() {
-        if ev.state.is_pressed() {
-            // Changed this line - ev.key_code is already a KeyCode, not an Option<KeyCode>
-            let key_code = ev.key_code;
-            if let Some(c) = keycode_to_char(key_code) {
-                if c.is_alphanumeric() || c.is_whitespace() || c == '.' {
-                    state.input.push(c);
-                    text.sections[1].value.push(c);
+        // Ctrl chords are shortcuts, not typing
+        if !ev.state.is_pressed() || ctrl {
+            continue;
+        }
+        let text = typed_text(&ev.logical_key);
+        match edit_key(ev.key_code, text, shift, bindings, &mut state) {
+            Edit::Done => {
+                repeat.start(ev.key_code, text);
+                if !bindings.is_bound(ev.key_code) {
+                    sfx.play(Sound::Keypress);
                 }
             }
+            // The line is full; say so rather than silently dropping keys
+            Edit::Refused => sfx.play(Sound::Error),
+            Edit::Ignored => {}
+        }
+    }
+    if let Some((key, times)) = repeat.tick(keys, time.delta()) {
+        let text = repeat.text.clone();
+        for _ in 0..times {
+            edit_key(key, text.as_deref(), shift, bindings, &mut state);
+        }
+    }
+    if ctrl && keys.just_pressed(KeyCode::KeyV) {
+        if let Some(text) = clipboard::read_text() {
+            if !paste(&text, &mut state) {
+                sfx.play(Sound::Error);
+            }
+        }
+    }
+    // Plain Ctrl+C interrupts, as in any terminal
+    if ctrl && shift && keys.just_pressed(KeyCode::KeyC) && !state.input.is_empty() {
+        if let Err(err) = clipboard::write_text(&state.input) {
+            warn!("Could not copy to clipboard: {}", err);
+        }
+    }
+    let len = state.input.chars().count();
+    if controls.just_pressed(Action::LineStart) {
+        state.cursor_pos = 0;
+    }
+    if controls.just_pressed(Action::LineEnd) {
+        state.cursor_pos = len;
+    }
+    // Ctrl+Tab switches sessions instead
+    if !ctrl && controls.just_pressed(Action::Complete) {
+        // Only the verb is completed; any arguments after it are kept as-is
+        let (prefix, rest) = match state.input.split_once(' ') {
+            Some((verb, args)) => (verb.to_string(), format!(" {}", args)),
+            None => (state.input.clone(), String::new()),
+        };
+        let candidates = complete_command(&registry, &prefix);
+        if let [name] = candidates.as_slice() {
+            let rest = if rest.is_empty() {
+                " ".to_string()
+            } else {
+                rest
+            };
+            state.set_input(format!("{}{}", name, rest));
+        } else if candidates.len() > 1 {
+            scrollback.push(&format!("> {}", candidates.join("  ")), &layout);
         }
     }
-    if keys.just_pressed(KeyCode::Backspace) && !state.input.is_empty() {
-        state.input.pop();
-        text.sections[1].value.pop();
+    if controls.just_pressed(Action::HistoryPrev) && !state.history.is_empty() {
+        let index = match state.history_index {
+            None => state.history.len() - 1,
+            Some(i) => i.saturating_sub(1),
+        };
+        state.history_index = Some(index);
+        let recalled = state.history[index].clone();
+        state.set_input(recalled);
+    }
+    if controls.just_pressed(Action::HistoryNext) {
+        if let Some(i) = state.history_index {
+            if i + 1 < state.history.len() {
+                state.history_index = Some(i + 1);
+                let recalled = state.history[i + 1].clone();
+                state.set_input(recalled);
+            } else {
+                // Walked past the newest entry, back to an empty prompt
+                state.history_index = None;
+                state.set_input(String::new());
+            }
+        }
     }
 }
 
 pub fn update_terminal(
-    keys: Res<ButtonInput<KeyCode>>,
+    controls: Controls,
+    registry: Res<CommandRegistry>,
+    layout: Res<TerminalLayout>,
+    mut config: ResMut<TerminalConfig>,
+    transcript: Res<TranscriptLogger>,
+    mut events: TerminalEvents,
     mut state: ResMut<TerminalState>,
-    mut query: Query<&mut Text>,
-) {
-    if keys.just_pressed(KeyCode::Enter) && !state.input.is_empty() {
-        let cmd = state.input.trim().to_string();
-        let mut text = query.single_mut();
-        let response = if cmd == "nmap neotechlabs.com" {
-            "> Scanning NeoTech Labs...\n> Port 80: HTTP (vulnerable)"
-        } else if cmd == "ssh neotechlabs.com" {
-            "> Connected—auth required"
-        } else if cmd == "exploit" {
-            "> Firewall breached"
-        } else if cmd == "wget data" {
-            "> 500MB downloaded—trace active!"
-        } else if cmd == "cloak" {
-            "> Trace evaded"
-        } else if cmd == "exit" {
-            std::process::exit(0);
-        } else {
-            &format!("> Unknown command: {}. Type 'help' for options.", cmd)
+    mut game: ResMut<GameLogicState>,
+    mut scrollback: ResMut<ScrollbackState>,
+    mut pending: ResMut<PendingOutput>,
+    mut theme: ResMut<Theme>,
+    mut delay: ResMut<ResponseDelay>,
+    mut scripts: ResMut<ScriptQueue>,
+    mut key_evr: EventReader<KeyboardInput>,
+    mut exit: EventWriter<AppExit>,
+) {
+    // Read every frame, so the Enter that submitted a destructive command
+    // isn't taken as the answer to its confirmation
+    let answer = key_evr
+        .read()
+        .filter(|ev| ev.state.is_pressed())
+        .filter_map(|ev| confirm_answer(&ev.logical_key, game.locale.get("confirm.yes")))
+        .fold(None, |first, answer| first.or(Some(answer)));
+    // Nothing new runs while searching, so the matches stay put, or while
+    // replaying, which types into the input line itself
+    if state.is_searching() || state.is_replaying() {
+        return;
+    }
+    let mut confirmed = None;
+    if state.is_confirming() {
+        let Some(yes) = answer else {
+            return;
         };
-        text.sections[0].value += &format!("{}\n", response);
-        text.sections[1].value = "> ".to_string();
-        state.input.clear();
-    }
-}
-
-fn keycode_to_char(key_code: KeyCode) -> Option<char> {
-    match key_code {
-        KeyCode::KeyA => Some('a'),
-        KeyCode::KeyB => Some('b'),
-        KeyCode::KeyC => Some('c'),
-        KeyCode::KeyD => Some('d'),
-        KeyCode::KeyE => Some('e'),
-        KeyCode::KeyF => Some('f'),
-        KeyCode::KeyG => Some('g'),
-        KeyCode::KeyH => Some('h'),
-        KeyCode::KeyI => Some('i'),
-        KeyCode::KeyJ => Some('j'),
-        KeyCode::KeyK => Some('k'),
-        KeyCode::KeyL => Some('l'),
-        KeyCode::KeyM => Some('m'),
-        KeyCode::KeyN => Some('n'),
-        KeyCode::KeyO => Some('o'),
-        KeyCode::KeyP => Some('p'),
-        KeyCode::KeyQ => Some('q'),
-        KeyCode::KeyR => Some('r'),
-        KeyCode::KeyS => Some('s'),
-        KeyCode::KeyT => Some('t'),
-        KeyCode::KeyU => Some('u'),
-        KeyCode::KeyV => Some('v'),
-        KeyCode::KeyW => Some('w'),
-        KeyCode::KeyX => Some('x'),
-        KeyCode::KeyY => Some('y'),
-        KeyCode::KeyZ => Some('z'),
-        KeyCode::Digit0 => Some('0'),
-        KeyCode::Digit1 => Some('1'),
-        KeyCode::Digit2 => Some('2'),
-        KeyCode::Digit3 => Some('3'),
-        KeyCode::Digit4 => Some('4'),
-        KeyCode::Digit5 => Some('5'),
-        KeyCode::Digit6 => Some('6'),
-        KeyCode::Digit7 => Some('7'),
-        KeyCode::Digit8 => Some('8'),
-        KeyCode::Digit9 => Some('9'),
-        KeyCode::Space => Some(' '),
-        KeyCode::Period => Some('.'),
-        _ => None,
+        let InputMode::ConfirmPending { action } = std::mem::take(&mut state.mode) else {
+            unreachable!();
+        };
+        pending.flush(&mut scrollback);
+        if !yes {
+            pending.push(game.locale.get("confirm.cancelled"), &layout);
+            return;
+        }
+        confirmed = Some(action);
+    }
+    if controls.just_pressed(Action::Submit) && pending.is_streaming() {
+        // Enter skips the rest of the animation
+        pending.flush(&mut scrollback);
+        return;
     }
-}
\ No newline at end of file
+    // A pad can't type, so on an empty line it runs the last command again
+    if controls.pad_just_pressed(PadAction::Submit) && state.input.is_empty() {
+        if let Some(last) = state.history.last().cloned() {
+            state.set_input(last);
+        }
+    }
+    // Decided here, before submitting takes the line away
+    if controls.just_pressed(Action::Submit) && state.input.is_empty() {
+        delay.skip_requested = true;
+    }
+    if controls.just_pressed(Action::Submit) && !state.input.is_empty() && !state.locked {
+        let cmd = std::mem::take(&mut state.input);
+        submit(&cmd, &mut state, &mut delay);
+    }
+    if delay.is_waiting() || pending.is_streaming() {
+        return;
+    }
+    // The script line being run, if the command comes from one
+    let mut script_line = None;
+    // The background job that has just finished, if the command is one
+    let mut job = None;
+    let cmd = match confirmed {
+        Some(PendingAction::Run(cmd)) => cmd,
+        None => {
+            // Finished jobs and scripts wait for anything typed to run first
+            let cmd = match delay.queued.pop_front() {
+                Some(cmd) => cmd,
+                // Only the runner can answer an `exploit` challenge
+                None if game.puzzle.is_some() => return,
+                None => match game.jobs.take_finished() {
+                    Some(finished) => {
+                        let cmd = finished.command.clone();
+                        job = Some(finished);
+                        cmd
+                    }
+                    None => {
+                        let Some(queued) = scripts.next() else {
+                            return;
+                        };
+                        let cmd = queued.line.command.clone();
+                        script_line = Some(queued);
+                        cmd
+                    }
+                },
+            };
+            if config.confirm_destructive && registry.is_destructive(&cmd, &game) {
+                pending.push(game.locale.get("confirm.prompt"), &layout);
+                state.mode = InputMode::ConfirmPending {
+                    action: PendingAction::Run(cmd),
+                };
+                return;
+            }
+            cmd
+        }
+    };
+    // A job runs where it was started, not wherever the runner is now
+    let runner_at = job.as_ref().map(|job| {
+        (
+            std::mem::replace(&mut game.current_host, job.host.clone()),
+            std::mem::replace(&mut game.cwd, job.cwd.clone()),
+        )
+    });
+    let prompt = game.prompt();
+    let mut outcome = registry.evaluate_command(&cmd, &mut game);
+    // Then back to where the runner is, unless the job moved them itself
+    if let (Some(job), Some((host, cwd))) = (&job, runner_at) {
+        if game.current_host == job.host && game.cwd == job.cwd {
+            game.current_host = host;
+            game.cwd = cwd;
+        }
+    }
+    let from_job = job.is_some();
+    let mut latency = None;
+    let mut connected = false;
+    for effect in &outcome.effects {
+        match effect {
+            Effect::SetTheme(new_theme) => *theme = new_theme.clone(),
+            // A job has already taken its time in the background
+            Effect::Latency(seconds) if !from_job => latency = Some(*seconds),
+            Effect::Latency(_) => {}
+            Effect::Connected(_) if !from_job => connected = true,
+            Effect::Opacity(percent) => {
+                if let Some(percent) = percent {
+                    config.opacity = *percent;
+                    if let Err(err) =
+                        Settings::update(config.settings_path.as_deref(), |settings| {
+                            settings.opacity = *percent
+                        })
+                    {
+                        warn!("Failed to save settings: {}", err);
+                        outcome.lines.push(OutputLine::Error(tr!(
+                            game.locale,
+                            "settings.save_failed",
+                            error = err
+                        )));
+                    }
+                }
+                outcome.lines.push(OutputLine::Info(tr!(
+                    game.locale,
+                    "opacity.show",
+                    percent = config.opacity
+                )));
+            }
+            Effect::OutputSpeed(speed) => {
+                if let Some(speed) = speed {
+                    config.output_speed = *speed;
+                    if let Err(err) =
+                        Settings::update(config.settings_path.as_deref(), |settings| {
+                            settings.output_speed = *speed
+                        })
+                    {
+                        warn!("Failed to save settings: {}", err);
+                        outcome.lines.push(OutputLine::Error(tr!(
+                            game.locale,
+                            "settings.save_failed",
+                            error = err
+                        )));
+                    }
+                }
+                outcome
+                    .lines
+                    .push(OutputLine::Info(config.output_speed.describe(&game.locale)));
+            }
+            Effect::DisplayMode(mode) => {
+                if let Some(mode) = mode {
+                    // `apply_display_mode` hands it on to the window
+                    config.display_mode = *mode;
+                    if let Err(err) =
+                        Settings::update(config.settings_path.as_deref(), |settings| {
+                            settings.display_mode = *mode
+                        })
+                    {
+                        warn!("Failed to save settings: {}", err);
+                        outcome.lines.push(OutputLine::Error(tr!(
+                            game.locale,
+                            "settings.save_failed",
+                            error = err
+                        )));
+                    }
+                }
+                outcome
+                    .lines
+                    .push(OutputLine::info(config.display_mode.describe(&game.locale)));
+            }
+            Effect::PrefixMatching(on) => {
+                if let Some(on) = on {
+                    game.prefix_matching = *on;
+                    if let Err(err) =
+                        Settings::update(config.settings_path.as_deref(), |settings| {
+                            settings.prefix_matching = *on
+                        })
+                    {
+                        warn!("Failed to save settings: {}", err);
+                        outcome.lines.push(OutputLine::Error(tr!(
+                            game.locale,
+                            "settings.save_failed",
+                            error = err
+                        )));
+                    }
+                }
+                let key = if game.prefix_matching {
+                    "settings.prefix_on"
+                } else {
+                    "settings.prefix_off"
+                };
+                outcome.lines.push(OutputLine::info(game.locale.get(key)));
+            }
+            Effect::Clear => scrollback.clear(),
+            Effect::Banner => {
+                // Room for the time stamp and marker each response line gets
+                let margin = format!("[{}] > ", game.clock.time_of_day()).chars().count();
+                let columns = layout.columns.saturating_sub(margin).max(1);
+                outcome.lines.extend(
+                    banner::fit(&banner::load(&game.locale), columns)
+                        .into_iter()
+                        .map(OutputLine::Info),
+                );
+            }
+            Effect::CopyScrollback => {
+                let text: Vec<String> = scrollback.iter().map(markup::strip).collect();
+                outcome
+                    .lines
+                    .push(clipboard::copy(&text.join("\n"), &game.locale));
+            }
+            Effect::RunScript(script) => {
+                let depth = script_line.as_ref().map_or(0, |queued| queued.depth);
+                if !scripts.start(script.clone(), depth) {
+                    scripts.0.clear();
+                    outcome.lines = vec![OutputLine::Error(tr!(
+                        game.locale,
+                        "script.too_deep",
+                        name = script.name,
+                        max = MAX_SCRIPT_DEPTH
+                    ))];
+                }
+            }
+            // Announcements go out as events, and saving happens last
+            Effect::Breached(_)
+            | Effect::Connected(_)
+            | Effect::TraceStarted(_)
+            | Effect::TraceEvaded(_)
+            | Effect::Looted(_)
+            | Effect::Replay(_)
+            | Effect::Reset
+            | Effect::SaveSession
+            | Effect::Exit => {}
+        }
+    }
+    if let Some(queued) = &script_line {
+        // Stop the script at the first line that fails, however it fails
+        if outcome.failed {
+            scripts.0.clear();
+            outcome.lines.push(OutputLine::Error(tr!(
+                game.locale,
+                "script.failed",
+                name = queued.script,
+                line = queued.line.number
+            )));
+        }
+        outcome.lines.insert(
+            0,
+            OutputLine::Info(tr!(game.locale, "script.line", command = cmd)),
+        );
+    }
+    if from_job {
+        outcome.lines.insert(
+            0,
+            OutputLine::Info(tr!(game.locale, "jobs.done", command = cmd)),
+        );
+    }
+    events.submitted(&cmd, outcome.failed);
+    for effect in &outcome.effects {
+        events.send(effect);
+    }
+    let stamp = game.clock.time_of_day();
+    let rendered = outcome.render(&game.locale);
+    transcript.log(game.clock.elapsed, &stamp, &prompt, &cmd, &rendered);
+    let response: Vec<String> = rendered
+        .lines()
+        .map(|line| format!("[{}] {}", stamp, line))
+        .collect();
+    // The greeting waits out the transition to the new host as well
+    let transition = config.connect_transition.seconds;
+    if connected && transition > 0.0 {
+        latency = Some(latency.unwrap_or(0.0) + transition);
+    }
+    match latency {
+        Some(seconds) => delay.start(seconds, response.join("\n")),
+        None => pending.push(&response.join("\n"), &layout),
+    }
+
+    // Saving happens last so the file includes the command that asked for it
+    if outcome.effects.contains(&Effect::SaveSession) {
+        pending.flush(&mut scrollback);
+        let session = session_data(&state, &scrollback, &game);
+        match save::save_session(config.session_path.as_deref(), &session) {
+            Ok(path) => scrollback.push(
+                &tr!(game.locale, "save.saved", path = path.display()),
+                &layout,
+            ),
+            Err(err) => {
+                warn!("Failed to save session: {}", err);
+                scrollback.push(&tr!(game.locale, "save.failed", error = err), &layout);
+            }
+        }
+    }
+    // `save_on_exit` saves the session on the way out
+    if outcome.effects.contains(&Effect::Exit) {
+        exit.send(AppExit::Success);
+    }
+}
+
+/// What of the terminal and the run is saved right now.
+fn session_data(
+    state: &TerminalState,
+    scrollback: &ScrollbackState,
+    game: &GameLogicState,
+) -> SessionData {
+    SessionData {
+        history: state.history.clone(),
+        output: scrollback.lines.iter().cloned().collect(),
+        aliases: game.aliases.0.clone(),
+        env: game.env.0.clone(),
+        inventory: game.inventory.items.clone(),
+        handle: Some(game.handle.clone()),
+        discovered: game
+            .network
+            .numbered()
+            .map(|(_, host, status)| (host.to_string(), status))
+            .collect(),
+    }
+}
+/// Saves the session, flushes the transcript and silences any sound once
+/// the app is exiting, however it came to: `exit`, the pause menu, the
+/// window being closed or stdin running dry. Runs in `Last`, after
+/// anything in the frame could have asked to exit, and the app only stops
+/// once the frame is over.
+pub fn save_on_exit(
+    mut exits: EventReader<AppExit>,
+    config: Res<TerminalConfig>,
+    transcript: Res<TranscriptLogger>,
+    state: Res<TerminalState>,
+    game: Res<GameLogicState>,
+    sinks: Query<&AudioSink>,
+    mut pending: ResMut<PendingOutput>,
+    mut scrollback: ResMut<ScrollbackState>,
+) {
+    if exits.read().count() == 0 {
+        return;
+    }
+    // Output still being typed out belongs in the save too
+    pending.flush(&mut scrollback);
+    let session = session_data(&state, &scrollback, &game);
+    match save::save_session(config.session_path.as_deref(), &session) {
+        Ok(path) => info!("Saved session to {}", path.display()),
+        Err(err) => warn!("Failed to save session: {}", err),
+    }
+    transcript.flush();
+    for sink in &sinks {
+        sink.stop();
+    }
+}
+
+/// Enters `cmd` as if it had been typed: it goes into the history, the
+/// input line is cleared, and the command waits its turn to run.
+pub fn submit(cmd: &str, state: &mut TerminalState, delay: &mut ResponseDelay) {
+    let cmd = cmd.trim().to_string();
+    if !cmd.is_empty() && state.history.last() != Some(&cmd) {
+        state.history.push(cmd.clone());
+    }
+    state.history_index = None;
+    state.set_input(String::new());
+    // Queued rather than run, so commands typed while a response is
+    // held back still run after it, in the order they were entered
+    delay.queued.push_back(cmd);
+}
+
+/// `Some(true)` for the `yes` key, e.g. `y`, `Some(false)` for any other
+/// key, and `None` for modifiers, which are only ever half of a keypress.
+fn confirm_answer(key: &Key, yes: &str) -> Option<bool> {
+    match key {
+        Key::Character(c) => Some(c.eq_ignore_ascii_case(yes)),
+        Key::Shift | Key::Control | Key::Alt | Key::Super | Key::CapsLock => None,
+        _ => Some(false),
+    }
+}
+
+/// Releases a held-back response once its host's latency has passed.
+pub fn tick_response_delay(
+    time: Res<Time>,
+    layout: Res<TerminalLayout>,
+    mut delay: ResMut<ResponseDelay>,
+    mut pending: ResMut<PendingOutput>,
+) {
+    if !delay.is_waiting() || !delay.timer.tick(time.delta()).finished() {
+        return;
+    }
+    if let Some(response) = delay.pending.take() {
+        pending.push(&response, &layout);
+    }
+}
+
+/// What the terminal shows when Ctrl+C interrupts it.
+const INTERRUPT_ECHO: &str = "^C";
+
+/// Ctrl+C asks everything running to stop. Ctrl+Shift+C copies instead.
+pub fn request_interrupt(
+    keys: Res<ButtonInput<KeyCode>>,
+    mut interrupts: EventWriter<InterruptRequested>,
+) {
+    let ctrl = keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    let shift = keys.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    if ctrl && !shift && keys.just_pressed(KeyCode::KeyC) {
+        interrupts.send(InterruptRequested);
+    }
+}
+
+/// Clears the screen on Ctrl+L, as `clear` does. The input line and the
+/// history stay as they are.
+pub fn clear_on_ctrl_l(keys: Res<ButtonInput<KeyCode>>, mut scrollback: ResMut<ScrollbackState>) {
+    let ctrl = keys.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]);
+    if ctrl && keys.just_pressed(KeyCode::KeyL) {
+        scrollback.clear();
+    }
+}
+
+/// Stops what the terminal itself has running on an interrupt: output
+/// still being typed out is shown at once, a response held back is
+/// dropped along with the commands queued behind it, and a script stops.
+/// Whatever was being answered, searched or typed goes too, leaving `^C`
+/// and a fresh, empty prompt.
+pub fn interrupt_terminal(
+    layout: Res<TerminalLayout>,
+    mut interrupts: EventReader<InterruptRequested>,
+    mut state: ResMut<TerminalState>,
+    mut scrollback: ResMut<ScrollbackState>,
+    mut pending: ResMut<PendingOutput>,
+    mut delay: ResMut<ResponseDelay>,
+    mut scripts: ResMut<ScriptQueue>,
+) {
+    if interrupts.read().count() == 0 {
+        return;
+    }
+    pending.flush(&mut scrollback);
+    delay.pending = None;
+    delay.queued.clear();
+    scripts.0.clear();
+    if matches!(
+        state.mode,
+        InputMode::ConfirmPending { .. } | InputMode::Search
+    ) {
+        state.mode = InputMode::Normal;
+    }
+    state.history_index = None;
+    state.set_input(String::new());
+    scrollback.push(INTERRUPT_ECHO, &layout);
+    scrollback.scroll_to_bottom();
+}
+
+pub fn tick_clock(time: Res<Time>, mut game: ResMut<GameLogicState>) {
+    // Ticking every frame shouldn't count as a change for prompt re-rendering
+    game.bypass_change_detection().clock.elapsed += time.delta();
+}
+
+pub fn tick_scan(
+    time: Res<Time>,
+    layout: Res<TerminalLayout>,
+    mut interrupts: EventReader<InterruptRequested>,
+    mut game: ResMut<GameLogicState>,
+    mut pending: ResMut<PendingOutput>,
+    mut scanned: EventWriter<HostScanned>,
+) {
+    // An interrupted scan stops where it is, never finishing
+    if interrupts.read().count() > 0 && game.scan.is_some() {
+        game.scan = None;
+        return;
+    }
+    // A scan ticking along isn't a change the prompt or HUD need to see
+    let game = game.bypass_change_detection();
+    let stamp = game.clock.time_of_day();
+    let Some(job) = game.scan.as_mut() else {
+        return;
+    };
+    let (lines, done) = job.advance(time.delta(), &game.locale);
+    for line in lines {
+        pending.push(
+            &format!("[{}] {}", stamp, line.render(&game.locale)),
+            &layout,
+        );
+    }
+    if done {
+        scanned.send(HostScanned {
+            host: job.host.clone(),
+        });
+        game.scan = None;
+    }
+}
+
+/// Fills the progress bar of a running `wget data` in place, once the host
+/// has answered, and finishes the download when the bar is full.
+pub fn tick_download(
+    time: Res<Time>,
+    layout: Res<TerminalLayout>,
+    delay: Res<ResponseDelay>,
+    mut interrupts: EventReader<InterruptRequested>,
+    mut game: ResMut<GameLogicState>,
+    mut pending: ResMut<PendingOutput>,
+    mut scrollback: ResMut<ScrollbackState>,
+    mut events: TerminalEvents,
+    mut showing_bar: Local<bool>,
+) {
+    // An interrupted download is abandoned, bringing nothing down
+    if interrupts.read().count() > 0 && game.download.is_some() {
+        game.download = None;
+    }
+    if game.download.is_none() {
+        // A reset or restart can drop the download, leaving the bar behind
+        if *showing_bar {
+            scrollback.set_live(None);
+            *showing_bar = false;
+        }
+        return;
+    }
+    // The bar goes below the line saying the download started
+    if delay.is_waiting() || pending.is_streaming() {
+        return;
+    }
+    // Only finishing is a change; the bar filling up isn't
+    let stamp = game.clock.time_of_day();
+    let ticking = game.bypass_change_detection();
+    let Some(job) = ticking.download.as_mut() else {
+        return;
+    };
+    let done = job.advance(time.delta());
+    let bar = format!(
+        "[{}] {}",
+        stamp,
+        OutputLine::Info(job.progress_bar()).render(&ticking.locale)
+    );
+    if !done {
+        if scrollback.live() != Some(bar.as_str()) {
+            scrollback.set_live(Some(bar));
+            *showing_bar = true;
+        }
+        return;
+    }
+    scrollback.set_live(None);
+    *showing_bar = false;
+    scrollback.push(&bar, &layout);
+    for line in commands::finish_download(&mut game) {
+        pending.push(
+            &format!("[{}] {}", stamp, line.render(&game.locale)),
+            &layout,
+        );
+    }
+    // No command is being evaluated to hand the trace and loot on
+    events.announce(&mut game.effects);
+}
+
+/// Fails the open `exploit` challenge once its time is up. The clock only
+/// starts once the host has answered and the challenge is on screen.
+pub fn tick_puzzle(
+    time: Res<Time>,
+    layout: Res<TerminalLayout>,
+    delay: Res<ResponseDelay>,
+    mut interrupts: EventReader<InterruptRequested>,
+    mut game: ResMut<GameLogicState>,
+    mut pending: ResMut<PendingOutput>,
+    mut events: TerminalEvents,
+) {
+    // Walking away from a challenge leaves the firewall standing, but
+    // isn't held against the runner like running out of time
+    if interrupts.read().count() > 0 && game.puzzle.is_some() {
+        game.puzzle = None;
+        return;
+    }
+    if delay.is_waiting() {
+        return;
+    }
+    // The countdown isn't a change; the challenge closing is
+    let Some(puzzle) = game.bypass_change_detection().puzzle.as_mut() else {
+        return;
+    };
+    if !puzzle.deadline.tick(time.delta()).finished() {
+        return;
+    }
+    let Some(puzzle) = game.puzzle.take() else {
+        return;
+    };
+    let stamp = game.clock.time_of_day();
+    for line in commands::fail_puzzle(&puzzle, "exploit.timed_out", &mut game) {
+        pending.push(
+            &format!("[{}] {}", stamp, line.render(&game.locale)),
+            &layout,
+        );
+    }
+    // No command is being evaluated to hand the trace starting on
+    events.announce(&mut game.effects);
+}
+
+pub fn tick_jobs(
+    time: Res<Time>,
+    mut interrupts: EventReader<InterruptRequested>,
+    mut game: ResMut<GameLogicState>,
+) {
+    // Background downloads and the like are cancelled along with the rest
+    if interrupts.read().count() > 0 && !game.jobs.is_empty() {
+        game.jobs.0.clear();
+        return;
+    }
+    if !game.jobs.is_empty() {
+        // Progress only matters when `jobs` asks; finishing is picked up
+        // by `update_terminal`
+        game.bypass_change_detection().jobs.tick(time.delta());
+    }
+}
+
+pub fn tick_lockouts(time: Res<Time>, mut game: ResMut<GameLogicState>) {
+    if game.lockouts.any_locked() {
+        // Cooldowns only matter when `ssh` asks about them
+        game.bypass_change_detection().lockouts.tick(time.delta());
+    }
+}
+
+pub fn tick_cooldowns(time: Res<Time>, mut game: ResMut<GameLogicState>) {
+    if game.cooldowns.any_recharging() {
+        // Like lockouts, cooldowns only matter when a command asks
+        game.bypass_change_detection().cooldowns.tick(time.delta());
+    }
+}
+
+/// Runs a trace down. One that completes terminates the connection: the
+/// runner is sent home, charged integrity for it and locked out, and the
+/// run ends once the damage has been reported.
+pub fn tick_trace(
+    time: Res<Time>,
+    layout: Res<TerminalLayout>,
+    mut game: ResMut<GameLogicState>,
+    mut state: ResMut<TerminalState>,
+    mut pending: ResMut<PendingOutput>,
+    mut sessions: ResMut<Sessions>,
+) {
+    if !game.trace.active {
+        return;
+    }
+    // Only the trace ending is a change; the countdown itself isn't
+    let trace = &mut game.bypass_change_detection().trace;
+    if trace.timer.tick(time.delta()).just_finished() {
+        trace.active = false;
+        // The runner is kicked back home and pays for it in integrity
+        game.current_host = HOME_HOST.to_string();
+        game.cwd = "/".to_string();
+        game.hops.clear();
+        sessions.send_home();
+        game.failures.push(Failure::TraceCompleted);
+        state.lock();
+        pending.push(game.locale.get("trace.complete"), &layout);
+    }
+}
+
+/// Shows the seconds left on a trace from its `TraceStarted` until its
+/// `TraceEvaded`, and nothing otherwise.
+pub fn render_trace_hud(
+    mut started: EventReader<TraceStarted>,
+    mut evaded: EventReader<TraceEvaded>,
+    game: Res<GameLogicState>,
+    mut tracing: Local<bool>,
+    mut hud_query: Query<&mut Text, With<TraceHud>>,
+) {
+    if started.read().count() > 0 {
+        *tracing = true;
+    }
+    if evaded.read().count() > 0 {
+        *tracing = false;
+    }
+    // A trace that completes, or a reset, ends it without an event
+    if !game.trace.active {
+        *tracing = false;
+    }
+    let Ok(mut hud) = hud_query.get_single_mut() else {
+        return;
+    };
+    let countdown = if *tracing {
+        format!("TRACE: {:.0}s", game.trace.timer.remaining_secs().ceil())
+    } else {
+        String::new()
+    };
+    // The countdown skips change detection, so compare rather than rebuild
+    if hud.sections[0].value != countdown {
+        hud.sections[0].value = countdown;
+    }
+}
+
+/// Charges integrity for the failures commands and the trace have recorded,
+/// and ends the run when it reaches zero.
+pub fn apply_damage(
+    layout: Res<TerminalLayout>,
+    damage: Res<DamageTable>,
+    mut game: ResMut<GameLogicState>,
+    mut runner: ResMut<RunnerStatus>,
+    mut state: ResMut<TerminalState>,
+    mut scrollback: ResMut<ScrollbackState>,
+    mut pending: ResMut<PendingOutput>,
+    delay: Res<ResponseDelay>,
+    mut next: ResMut<NextState<RunState>>,
+) {
+    // Charge once the response explaining the failure has been shown
+    if game.failures.is_empty() || delay.is_waiting() {
+        return;
+    }
+    let total: u8 = game
+        .failures
+        .drain(..)
+        .map(|failure| damage.damage(failure))
+        .fold(0, u8::saturating_add);
+    runner.integrity = runner.integrity.saturating_sub(total);
+    if runner.integrity > 0 {
+        pending.push(
+            &tr!(
+                game.locale,
+                "damage.integrity",
+                damage = total,
+                left = runner.integrity
+            ),
+            &layout,
+        );
+        // A completed trace has locked the terminal, which ends the run
+        // however much integrity is left
+        if !state.locked {
+            return;
+        }
+    }
+    // Output stops streaming outside `Running`, so show everything now
+    pending.flush(&mut scrollback);
+    let over = if runner.integrity > 0 {
+        "damage.traced"
+    } else {
+        "damage.flatlined"
+    };
+    scrollback.push(game.locale.get(over), &layout);
+    state.locked = true;
+    next.set(RunState::GameOver);
+}
+
+/// A new run's game state. Only the difficulty, language, network
+/// conditions, mission and seed are taken from `game`.
+fn fresh_game(game: &mut GameLogicState) -> GameLogicState {
+    GameLogicState {
+        difficulty: std::mem::take(&mut game.difficulty),
+        locale: std::mem::take(&mut game.locale),
+        conditions: std::mem::take(&mut game.conditions),
+        mission: game.mission.restarted(),
+        rng: GameRng::new(game.rng.seed()),
+        ..default()
+    }
+}
+
+/// Starts everything over once a reset is asked for, as if the game had
+/// just been launched with no session to restore. Timers, jobs, scripts
+/// and held-back output are all dropped with the old run.
+pub fn reset_run(
+    mut resets: EventReader<ResetRequested>,
+    mut commands: Commands,
+    config: Res<TerminalConfig>,
+    mut game: ResMut<GameLogicState>,
+    mut next: ResMut<NextState<RunState>>,
+) {
+    if resets.read().count() == 0 {
+        return;
+    }
+    let fresh = fresh_game(&mut game);
+    *game = fresh;
+    insert_idle_terminal(&mut commands, &config);
+    commands.insert_resource(BootSequence::load(&game.locale, "greeting.welcome"));
+    commands.insert_resource(TerminalState::default());
+    commands.insert_resource(ScrollbackState::new(&config));
+    commands.insert_resource(RunnerStatus::default());
+    commands.insert_resource(Heat::default());
+    commands.insert_resource(SideLog::default());
+    commands.insert_resource(Sessions::default());
+    next.set(RunState::Running);
+}
+
+/// Starts a fresh run from the game-over or victory screen. Aliases, the
+/// handle, command history, difficulty and language belong to the player rather
+/// than the run, so they carry over. The seed does too, and restarts from
+/// the top so the new run replays the old one's luck.
+pub fn restart_run(
+    controls: Controls,
+    layout: Res<TerminalLayout>,
+    mut game: ResMut<GameLogicState>,
+    mut runner: ResMut<RunnerStatus>,
+    mut heat: ResMut<Heat>,
+    mut state: ResMut<TerminalState>,
+    mut scrollback: ResMut<ScrollbackState>,
+    mut delay: ResMut<ResponseDelay>,
+    mut scripts: ResMut<ScriptQueue>,
+    mut sessions: ResMut<Sessions>,
+    mut next: ResMut<NextState<RunState>>,
+) {
+    if !controls.just_pressed(Action::Submit) {
+        return;
+    }
+    *delay = ResponseDelay::default();
+    scripts.0.clear();
+    // Only the session the run ended in carries on
+    *sessions = Sessions::default();
+    let aliases = std::mem::take(&mut game.aliases);
+    let env = std::mem::take(&mut game.env);
+    let handle = std::mem::take(&mut game.handle);
+    let fresh = fresh_game(&mut game);
+    *game = GameLogicState {
+        aliases,
+        env,
+        handle,
+        ..fresh
+    };
+    *runner = RunnerStatus::default();
+    *heat = Heat::default();
+    state.locked = false;
+    state.mode = InputMode::Normal;
+    state.history_index = None;
+    state.set_input(String::new());
+    scrollback.push(game.locale.get("restart.reboot"), &layout);
+    next.set(RunState::Running);
+}
+
+/// Types pending output out at the configured speed, or all at once when
+/// it is `instant`.
+pub fn stream_output(
+    time: Res<Time>,
+    config: Res<TerminalConfig>,
+    mut pending: ResMut<PendingOutput>,
+    mut scrollback: ResMut<ScrollbackState>,
+) {
+    if !pending.is_streaming() {
+        pending.carried = 0.0;
+        return;
+    }
+    let OutputSpeed::CharsPerSecond(speed) = config.output_speed else {
+        pending.flush(&mut scrollback);
+        return;
+    };
+    pending.carried += speed as f32 * time.delta_seconds();
+    let chars = pending.carried.floor();
+    pending.carried -= chars;
+    for _ in 0..chars as usize {
+        match pending.buffer.pop_front() {
+            Some('{') => {
+                // Reveal colour tags in one go so they never show half-typed
+                scrollback.push_char('{');
+                while let Some(c) = pending.buffer.pop_front() {
+                    scrollback.push_char(c);
+                    if c == '}' || c == '\n' {
+                        break;
+                    }
+                }
+            }
+            Some(c) => scrollback.push_char(c),
+            None => break,
+        }
+    }
+}
+
+pub fn handle_scroll(controls: Controls, mut scrollback: ResMut<ScrollbackState>) {
+    if controls.just_pressed(Action::ScrollUp) {
+        scrollback.scroll(true);
+    }
+    if controls.just_pressed(Action::ScrollDown) {
+        scrollback.scroll(false);
+    }
+}
+
+/// Notes when new output lines appear, so `render_scrollback` can fade
+/// them in. Lines typed out character by character start fading as soon
+/// as their first character shows.
+pub fn track_line_fades(
+    time: Res<Time>,
+    scrollback: Res<ScrollbackState>,
+    visuals: Res<VisualSettings>,
+    mut fades: ResMut<LineFades>,
+) {
+    // Nothing new and nothing left to fade isn't worth a redraw
+    if fades.seen == scrollback.pushed() && !fades.is_fading() {
+        return;
+    }
+    fades.update(scrollback.pushed(), time.elapsed(), visuals.line_fade());
+}
+
+pub fn render_scrollback(
+    time: Res<Time>,
+    scrollback: Res<ScrollbackState>,
+    layout: Res<TerminalLayout>,
+    theme: Res<Theme>,
+    visuals: Res<VisualSettings>,
+    a11y: Res<A11ySettings>,
+    state: Res<TerminalState>,
+    search: Res<SearchState>,
+    fades: Res<LineFades>,
+    mut prompt_lines: Local<usize>,
+    mut query: Query<(&mut Text, &PromptSections), With<TerminalText>>,
+) {
+    let Ok((mut text, prompt_sections)) = query.get_single_mut() else {
+        warn_once!("No terminal text to show the scrollback in");
+        return;
+    };
+    // Each line a long prompt wraps onto pushes the oldest output line off
+    // the top, so the text never grows past the terminal
+    let split = prompt_sections.start(&text);
+    let extra = extra_prompt_lines(&text.sections[split..]);
+    if scrollback.is_changed()
+        || layout.is_changed()
+        || theme.is_changed()
+        || visuals.is_changed()
+        || a11y.is_changed()
+        || state.is_changed()
+        || search.is_changed()
+        || fades.is_changed()
+        || extra != *prompt_lines
+    {
+        *prompt_lines = extra;
+        let mut prompt = text.sections.split_off(split);
+        text.sections.clear();
+        let search = state.is_searching().then_some(&*search);
+        let colors = a11y.theme(&theme, &visuals);
+        // The live line takes the bottom row from the oldest line too
+        let live = scrollback.live().map(|line| format!("{}\n", line));
+        let skipped = extra + usize::from(live.is_some());
+        // A span that wraps keeps its colour on the lines it wraps onto
+        let mut color = colors.foreground;
+        for index in scrollback.visible_range().skip(skipped) {
+            let start = text.sections.len();
+            append_colored_from(
+                &mut text,
+                &scrollback.shown_line(index, search),
+                &prompt[0].style.font,
+                layout.font_size(),
+                &colors,
+                &mut color,
+            );
+            let alpha = fades.alpha(
+                scrollback.pushed_at(index),
+                time.elapsed(),
+                visuals.line_fade(),
+            );
+            if alpha < 1.0 {
+                for section in &mut text.sections[start..] {
+                    let color = section.style.color;
+                    section.style.color = color.with_alpha(color.alpha() * alpha);
+                }
+            }
+        }
+        if let Some(live) = &live {
+            append_colored(
+                &mut text,
+                live,
+                &prompt[0].style.font,
+                layout.font_size(),
+                &colors,
+            );
+        }
+        for section in &mut prompt {
+            section.style.font_size = layout.font_size();
+        }
+        text.sections.extend(prompt);
+    }
+}
+
+pub fn render_prompt(
+    state: Res<TerminalState>,
+    layout: Res<TerminalLayout>,
+    game: Res<GameLogicState>,
+    registry: Res<CommandRegistry>,
+    boot: Res<State<BootState>>,
+    delay: Res<ResponseDelay>,
+    sessions: Res<Sessions>,
+    search: Res<SearchState>,
+    theme: Res<Theme>,
+    visuals: Res<VisualSettings>,
+    a11y: Res<A11ySettings>,
+    mut query: Query<(&mut Text, &mut PromptSections), With<TerminalText>>,
+) {
+    if !state.is_changed()
+        && !layout.is_changed()
+        && !game.is_changed()
+        && !boot.is_changed()
+        && !delay.is_changed()
+        && !sessions.is_changed()
+        && !search.is_changed()
+        && !theme.is_changed()
+        && !visuals.is_changed()
+        && !a11y.is_changed()
+    {
+        return;
+    }
+    let Ok((mut text, mut prompt_sections)) = query.get_single_mut() else {
+        warn_once!("No terminal text to show the prompt in");
+        return;
+    };
+    let colors = a11y.theme(&theme, &visuals);
+    let (runs, cursor) = if state.locked || *boot.get() == BootState::Booting {
+        (Vec::new(), 0)
+    } else if state.is_searching() {
+        // The query stands in for the input line, with the cursor after it
+        let line = format!(
+            "(search {}/{}) {}",
+            search.matches.len().min(search.current + 1),
+            search.matches.len(),
+            search.query
+        );
+        let end = line.chars().count();
+        (vec![(line, colors.foreground)], end)
+    } else {
+        // While a host is responding, an ellipsis stands in for the prompt
+        let status = if delay.is_waiting() {
+            format!("{:<4}", delay.ellipsis())
+        } else {
+            game.prompt()
+        };
+        let prefix = format!("{}{}", sessions.tag(), status);
+        let cursor = prefix.chars().count() + state.cursor_pos;
+        let mut runs = vec![(prefix, colors.foreground)];
+        if game.puzzle.is_some() {
+            // A guess at the exploit isn't a command line
+            runs.push((state.input.clone(), colors.foreground));
+        } else {
+            let known = game
+                .expand_line(&state.input)
+                .is_ok_and(|line| registry.is_known(&line, &game));
+            runs.extend(
+                highlight::classify(&state.input, known)
+                    .into_iter()
+                    .map(|(run, part)| (run, part.color(&colors))),
+            );
+        }
+        (runs, cursor)
+    };
+    let (sections, cursor) = split_at_cursor(runs, cursor, colors.foreground);
+    let start = prompt_sections.start(&text);
+    let font = text.sections[start].style.font.clone();
+    text.sections.truncate(start);
+    text.sections.extend(sections.iter().map(|(value, color)| {
+        TextSection::new(
+            value.clone(),
+            TextStyle {
+                font: font.clone(),
+                font_size: layout.font_size(),
+                color: *color,
+            },
+        )
+    }));
+    wrap_prompt(&mut text.sections[start..], cursor, layout.columns);
+    *prompt_sections = PromptSections {
+        colors: sections.into_iter().map(|(_, color)| color).collect(),
+        cursor,
+    };
+}
+
+/// Splits coloured runs of prompt text into sections around the char at
+/// `cursor`, which gets one of its own in its run's colour for
+/// `blink_cursor` to redraw. Past the end the cursor is drawn in `color`.
+/// Returns the sections and which of them is the cursor.
+fn split_at_cursor(
+    runs: Vec<(String, Color)>,
+    cursor: usize,
+    color: Color,
+) -> (Vec<(String, Color)>, usize) {
+    let mut sections = Vec::new();
+    let mut cursor_section = None;
+    let mut at = 0;
+    for (run, run_color) in runs {
+        let len = run.chars().count();
+        if cursor_section.is_none() && cursor < at + len {
+            let (before, rest) = split_at_char(&run, cursor - at);
+            let (under, after) = split_at_char(rest, 1);
+            if !before.is_empty() {
+                sections.push((before.to_string(), run_color));
+            }
+            cursor_section = Some(sections.len());
+            sections.push((under.to_string(), run_color));
+            if !after.is_empty() {
+                sections.push((after.to_string(), run_color));
+            }
+        } else {
+            sections.push((run, run_color));
+        }
+        at += len;
+    }
+    let cursor = cursor_section.unwrap_or_else(|| {
+        sections.push((String::new(), color));
+        sections.len() - 1
+    });
+    (sections, cursor)
+}
+
+fn split_at_char(s: &str, chars: usize) -> (&str, &str) {
+    s.split_at(s.char_indices().nth(chars).map_or(s.len(), |(i, _)| i))
+}
+
+/// Breaks the prompt onto as many lines as it takes to fit in `columns`,
+/// so a long command grows upward from the bottom like the output does.
+/// The cursor section stays one char, for `blink_cursor` to redraw, so a
+/// break just after it starts the section after it instead.
+fn wrap_prompt(prompt: &mut [TextSection], cursor: usize, columns: usize) {
+    let mut at = 0usize;
+    for (index, section) in prompt.iter_mut().enumerate() {
+        if index == cursor {
+            at += 1;
+            continue;
+        }
+        let mut broken = String::new();
+        for c in section.value.chars() {
+            if index > cursor && at.is_multiple_of(columns) {
+                broken.push('\n');
+            }
+            broken.push(c);
+            at += 1;
+            // The cursor always follows, so a break here never ends the text
+            if index < cursor && at.is_multiple_of(columns) {
+                broken.push('\n');
+            }
+        }
+        section.value = broken;
+    }
+}
+
+/// Lines the prompt takes up beyond its first, as `wrap_prompt` broke it.
+fn extra_prompt_lines(prompt: &[TextSection]) -> usize {
+    prompt
+        .iter()
+        .map(|section| section.value.matches('\n').count())
+        .sum()
+}
+
+pub fn blink_cursor(
+    time: Res<Time>,
+    state: Res<TerminalState>,
+    mut timer: ResMut<CursorTimer>,
+    mut query: Query<(&mut Text, &mut CursorBlink, &PromptSections), With<TerminalText>>,
+) {
+    let Ok((mut text, mut cursor, prompt)) = query.get_single_mut() else {
+        warn_once!("No terminal text to show the cursor in");
+        return;
+    };
+    if state.is_changed() {
+        // Keep the cursor solid while typing or after a command resets the prompt
+        timer.0.reset();
+        cursor.visible = true;
+    } else if timer.0.tick(time.delta()).times_finished_this_tick() % 2 == 1 {
+        // A long frame can span several blinks, which mustn't put it out of step
+        cursor.visible = !cursor.visible;
+    }
+    let under = match state.is_searching() {
+        true => ' ',
+        false => state.input.chars().nth(state.cursor_pos).unwrap_or(' '),
+    };
+    let index = prompt.start(&text) + prompt.cursor;
+    text.sections[index].value = if cursor.visible { '_' } else { under }.to_string();
+}
+
+/// Types `text` at the cursor. Returns false if the line filled up before
+/// all of it fit.
+fn paste(text: &str, state: &mut TerminalState) -> bool {
+    for c in text.chars() {
+        // Pasted line breaks and tabs become plain spaces
+        let c = if c.is_whitespace() { ' ' } else { c };
+        if is_typeable(c) && !state.insert_char(c) {
+            return false;
+        }
+    }
+    true
+}
+
+/// What a key did to the input line.
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Edit {
+    Done,
+    /// A char that didn't fit on the full line.
+    Refused,
+    /// Not an editing key at all.
+    Ignored,
+}
+
+/// What a key press typed according to the keyboard layout, so AZERTY,
+/// QWERTZ and the rest type what their keycaps say. `Some("")` for a dead
+/// key, which types nothing until the key after it completes the
+/// character. `None` when the layout didn't say.
+fn typed_text(key: &Key) -> Option<&str> {
+    match key {
+        Key::Character(text) => Some(text.as_str()),
+        Key::Space => Some(" "),
+        Key::Dead(_) => Some(""),
+        _ => None,
+    }
+}
+
+/// Applies one press of a key that repeats while held: typing `text`, or
+/// the character `key` makes on a US layout when there is no `text`,
+/// deleting one, or moving the cursor. Any other key is `Edit::Ignored`.
+fn edit_key(
+    key: KeyCode,
+    text: Option<&str>,
+    shift: bool,
+    bindings: &KeyBindings,
+    state: &mut TerminalState,
+) -> Edit {
+    let len = state.input.chars().count();
+    if key == bindings.key(Action::Backspace) {
+        if state.cursor_pos > 0 {
+            state.cursor_pos -= 1;
+            let at = state.cursor_pos;
+            state.remove_char(at);
+        }
+    } else if key == bindings.key(Action::Delete) {
+        let at = state.cursor_pos;
+        state.remove_char(at);
+    } else if key == bindings.key(Action::CursorLeft) {
+        state.cursor_pos = state.cursor_pos.saturating_sub(1);
+    } else if key == bindings.key(Action::CursorRight) {
+        state.cursor_pos = (state.cursor_pos + 1).min(len);
+    } else if bindings.is_bound(key) {
+        return Edit::Ignored;
+    } else {
+        let typed: Vec<char> = match text {
+            Some(text) => text.chars().filter(|&c| is_typeable(c)).collect(),
+            None => keycode_to_char(key, shift)
+                .filter(|&c| is_typeable(c))
+                .into_iter()
+                .collect(),
+        };
+        if typed.is_empty() {
+            return Edit::Ignored;
+        }
+        for c in typed {
+            if !state.insert_char(c) {
+                return Edit::Refused;
+            }
+        }
+    }
+    Edit::Done
+}
+
+fn complete_command<'a>(registry: &'a CommandRegistry, prefix: &str) -> Vec<&'a str> {
+    let prefix = prefix.to_lowercase();
+    registry
+        .names()
+        .into_iter()
+        .filter(|name| name.starts_with(&prefix))
+        .collect()
+}
+
+/// Characters allowed into the input line: anything printable, with
+/// spaces the only whitespace.
+fn is_typeable(c: char) -> bool {
+    c == ' ' || !(c.is_control() || c.is_whitespace())
+}
+
+/// The character `key_code` types on a US QWERTY layout, for when the
+/// layout itself doesn't say.
+#[rustfmt::skip]
+fn keycode_to_char(key_code: KeyCode, shift: bool) -> Option<char> {
+    let c = match key_code {
+        KeyCode::KeyA => 'a',
+        KeyCode::KeyB => 'b',
+        KeyCode::KeyC => 'c',
+        KeyCode::KeyD => 'd',
+        KeyCode::KeyE => 'e',
+        KeyCode::KeyF => 'f',
+        KeyCode::KeyG => 'g',
+        KeyCode::KeyH => 'h',
+        KeyCode::KeyI => 'i',
+        KeyCode::KeyJ => 'j',
+        KeyCode::KeyK => 'k',
+        KeyCode::KeyL => 'l',
+        KeyCode::KeyM => 'm',
+        KeyCode::KeyN => 'n',
+        KeyCode::KeyO => 'o',
+        KeyCode::KeyP => 'p',
+        KeyCode::KeyQ => 'q',
+        KeyCode::KeyR => 'r',
+        KeyCode::KeyS => 's',
+        KeyCode::KeyT => 't',
+        KeyCode::KeyU => 'u',
+        KeyCode::KeyV => 'v',
+        KeyCode::KeyW => 'w',
+        KeyCode::KeyX => 'x',
+        KeyCode::KeyY => 'y',
+        KeyCode::KeyZ => 'z',
+        KeyCode::Digit0 => if shift { ')' } else { '0' },
+        KeyCode::Digit1 => if shift { '!' } else { '1' },
+        KeyCode::Digit2 => if shift { '@' } else { '2' },
+        KeyCode::Digit3 => if shift { '#' } else { '3' },
+        KeyCode::Digit4 => if shift { '$' } else { '4' },
+        KeyCode::Digit5 => if shift { '%' } else { '5' },
+        KeyCode::Digit6 => if shift { '^' } else { '6' },
+        KeyCode::Digit7 => if shift { '&' } else { '7' },
+        KeyCode::Digit8 => if shift { '*' } else { '8' },
+        KeyCode::Digit9 => if shift { '(' } else { '9' },
+        KeyCode::Space => ' ',
+        KeyCode::Period => if shift { '>' } else { '.' },
+        KeyCode::Minus => if shift { '_' } else { '-' },
+        KeyCode::Slash => if shift { '?' } else { '/' },
+        KeyCode::Backslash => if shift { '|' } else { '\\' },
+        KeyCode::Semicolon => if shift { ':' } else { ';' },
+        _ => return None,
+    };
+    if shift && c.is_ascii_lowercase() {
+        Some(c.to_ascii_uppercase())
+    } else {
+        Some(c)
+    }
+}
+
+/// Most recent window size, applied once resizing has settled.
+#[derive(Default)]
+pub struct PendingResize {
+    size: Option<Vec2>,
+    timer: Timer,
+}
+
+/// Rebuilds the layout after the window is resized. Dragging a window edge
+/// fires an event every frame, so the work waits until events stop for
+/// `RESIZE_DEBOUNCE_SECS`.
+pub fn on_resize(
+    time: Res<Time>,
+    mut resize_evr: EventReader<WindowResized>,
+    mut pending: Local<PendingResize>,
+    mut layout: ResMut<TerminalLayout>,
+    // Camera, background, text and side log transforms, which never overlap
+    mut transforms: ParamSet<(
+        Query<(&mut Transform, Option<&mut CameraIntro>), With<Camera3d>>,
+        Query<&mut Transform, With<TerminalBackground>>,
+        Query<&mut Transform, With<TerminalText>>,
+        Query<&mut Transform, With<SideLogPanel>>,
+    )>,
+) {
+    if let Some(ev) = resize_evr.read().last() {
+        pending.size = Some(Vec2::new(ev.width, ev.height));
+        pending.timer = Timer::from_seconds(RESIZE_DEBOUNCE_SECS, TimerMode::Once);
+    }
+    let Some(size) = pending.size else {
+        return;
+    };
+    if !pending.timer.tick(time.delta()).finished() {
+        return;
+    }
+    pending.size = None;
+    *layout = TerminalLayout::new(size, layout.font_scale, layout.split);
+    for (mut transform, intro) in &mut transforms.p0() {
+        // A camera still flying in lands on the new framing instead
+        match intro {
+            Some(mut intro) => intro.end = layout.camera_transform(),
+            None => *transform = layout.camera_transform(),
+        }
+    }
+    for mut transform in &mut transforms.p1() {
+        *transform = layout.background_transform();
+    }
+    for mut transform in &mut transforms.p2() {
+        transform.translation = layout.text_origin();
+    }
+    for mut transform in &mut transforms.p3() {
+        transform.translation = layout.log_origin();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::audio::AudioSettings;
+    use bevy::ecs::system::RunSystemOnce;
+    use bevy::input::ButtonState;
+    use bevy::input::InputPlugin;
+    use bevy::render::view::RenderLayers;
+    use bevy::state::app::StatesPlugin;
+
+    #[test]
+    fn the_headless_plugin_brings_everything_it_needs() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, StatesPlugin, InputPlugin))
+            .insert_resource(TerminalConfig {
+                restore_session: false,
+                session_path: None,
+                ..default()
+            })
+            .add_plugins(TerminalPlugin { headless: true });
+        app.update();
+        let world = app.world();
+        assert!(world.contains_resource::<TerminalState>());
+        assert!(world.contains_resource::<ScrollbackState>());
+        assert!(!world.contains_resource::<VisualSettings>());
+        assert_eq!(world.resource::<GameLogicState>().current_host, HOME_HOST);
+    }
+
+    #[test]
+    fn flat_text_draws_the_terminal_straight_into_the_scene() {
+        let mut app = App::new();
+        app.add_plugins((
+            MinimalPlugins,
+            StatesPlugin,
+            InputPlugin,
+            AssetPlugin::default(),
+        ))
+        .init_asset::<Mesh>()
+        .init_asset::<StandardMaterial>()
+        .init_asset::<Image>()
+        .init_asset::<Font>()
+        .add_event::<WindowResized>()
+        .insert_resource(TerminalConfig {
+            restore_session: false,
+            session_path: None,
+            settings_path: None,
+            ..default()
+        })
+        .add_plugins(TerminalPlugin::default())
+        .insert_resource(VisualSettings {
+            flat_text: true,
+            ..default()
+        });
+        app.update();
+        let world = app.world_mut();
+        assert!(!world.contains_resource::<TerminalScreen>());
+        let mut texts = world.query_filtered::<&RenderLayers, With<TerminalText>>();
+        assert_eq!(texts.single(world), &RenderLayers::default());
+    }
+
+    #[test]
+    fn the_trace_countdown_shows_from_its_start_to_its_evasion() {
+        let mut world = World::new();
+        world.init_resource::<Events<TraceStarted>>();
+        world.init_resource::<Events<TraceEvaded>>();
+        world.init_resource::<GameLogicState>();
+        world.spawn((Text::from_section("", default()), TraceHud));
+        let render = world.register_system(render_trace_hud);
+        let shown = |world: &mut World| {
+            world.run_system(render).unwrap();
+            world.query::<&Text>().single(world).sections[0]
+                .value
+                .clone()
+        };
+        world.resource_mut::<GameLogicState>().start_trace();
+        assert_eq!(shown(&mut world), "");
+        world.send_event(TraceStarted { seconds: 30.0 });
+        assert_eq!(shown(&mut world), "TRACE: 30s");
+        assert_eq!(shown(&mut world), "TRACE: 30s");
+        world.send_event(TraceEvaded {
+            remaining_secs: 30.0,
+        });
+        assert_eq!(shown(&mut world), "");
+    }
+
+    #[test]
+    fn setting_up_twice_spawns_one_terminal() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+            .init_asset::<Mesh>()
+            .init_asset::<StandardMaterial>()
+            .init_asset::<Image>()
+            .init_asset::<Font>()
+            .init_resource::<ScrollbackState>()
+            .init_resource::<TerminalLayout>()
+            .init_resource::<Theme>()
+            .init_resource::<VisualSettings>()
+            .init_resource::<A11ySettings>()
+            .init_resource::<TerminalConfig>();
+        let world = app.world_mut();
+        world.run_system_once(setup_terminal);
+        world.run_system_once(setup_terminal);
+        let mut texts = world.query_filtered::<(), With<TerminalText>>();
+        assert_eq!(texts.iter(world).count(), 1);
+    }
+
+    #[test]
+    fn rendering_without_terminal_text_does_not_panic() {
+        let mut world = World::new();
+        world.init_resource::<Time>();
+        world.init_resource::<ScrollbackState>();
+        world.init_resource::<TerminalLayout>();
+        world.init_resource::<Theme>();
+        world.init_resource::<VisualSettings>();
+        world.init_resource::<A11ySettings>();
+        world.init_resource::<TerminalState>();
+        world.init_resource::<GameLogicState>();
+        world.init_resource::<CommandRegistry>();
+        world.insert_resource(State::new(BootState::Ready));
+        world.init_resource::<ResponseDelay>();
+        world.init_resource::<Sessions>();
+        world.init_resource::<SearchState>();
+        world.init_resource::<CursorTimer>();
+        world.init_resource::<LineFades>();
+        world.run_system_once(render_scrollback);
+        world.run_system_once(render_prompt);
+        world.run_system_once(blink_cursor);
+    }
+
+    #[test]
+    fn a_coloured_span_stays_coloured_where_it_wraps() {
+        let mut world = World::new();
+        world.init_resource::<Time>();
+        world.init_resource::<TerminalLayout>();
+        world.init_resource::<Theme>();
+        world.init_resource::<VisualSettings>();
+        world.init_resource::<A11ySettings>();
+        world.init_resource::<TerminalState>();
+        world.init_resource::<SearchState>();
+        world.init_resource::<LineFades>();
+        let layout = TerminalLayout::default();
+        let mut scrollback = ScrollbackState::default();
+        let long = "x".repeat(layout.columns * 2);
+        scrollback.push(&format!("> {{red}}{}{{/}} done", long), &layout);
+        assert_eq!(scrollback.lines.len(), 3);
+        world.insert_resource(scrollback);
+        world.spawn((
+            Text::from_sections(vec![TextSection::default(); 3]),
+            PromptSections::new(Color::WHITE),
+            TerminalText,
+        ));
+        world.run_system_once(render_scrollback);
+        let colors = world.resource::<A11ySettings>().theme(
+            world.resource::<Theme>(),
+            world.resource::<VisualSettings>(),
+        );
+        let opaque = |color: Color| color.with_alpha(1.0);
+        let text = world.query::<&Text>().single(&world);
+        let output = &text.sections[..text.sections.len() - 3];
+        assert!(output.len() >= 5, "{:?}", output);
+        for section in output {
+            let expected = if section.value.contains('x') {
+                colors.error
+            } else {
+                colors.foreground
+            };
+            assert_eq!(
+                opaque(section.style.color),
+                opaque(expected),
+                "{:?}",
+                section.value
+            );
+        }
+    }
+
+    #[test]
+    fn ctrl_l_clears_the_output_but_not_the_input_or_history() {
+        let mut world = World::new();
+        let mut scrollback = ScrollbackState::default();
+        scrollback.push("> Connected to neotechlabs.com", &TerminalLayout::default());
+        world.insert_resource(scrollback);
+        let mut state = TerminalState::default();
+        state.history.push("ssh neotechlabs.com".to_string());
+        state.set_input("nmap".to_string());
+        world.insert_resource(state);
+        let mut keys = ButtonInput::<KeyCode>::default();
+        keys.press(KeyCode::ControlLeft);
+        keys.press(KeyCode::KeyL);
+        world.insert_resource(keys);
+        world.run_system_once(clear_on_ctrl_l);
+        let scrollback = world.resource::<ScrollbackState>();
+        assert!(scrollback.lines.is_empty());
+        assert_eq!(scrollback.pushed(), 1);
+        let state = world.resource::<TerminalState>();
+        assert_eq!(state.input, "nmap");
+        assert_eq!(state.history, ["ssh neotechlabs.com"]);
+    }
+
+    #[test]
+    fn new_lines_fade_in_then_stay_opaque() {
+        let fade = Duration::from_millis(300);
+        let at = Duration::from_millis;
+        let mut fades = LineFades::default();
+        fades.update(2, at(1000), fade);
+        fades.update(3, at(1150), fade);
+        assert_eq!(fades.alpha(0, at(1150), fade), 0.5);
+        assert_eq!(fades.alpha(2, at(1150), fade), 0.0);
+        fades.update(3, at(1300), fade);
+        assert_eq!(fades.alpha(1, at(1300), fade), 1.0);
+        assert_eq!(fades.alpha(2, at(1300), fade), 0.5);
+        fades.update(3, at(1450), fade);
+        assert!(!fades.is_fading());
+        // A fresh scrollback starts counting again
+        fades.update(1, at(2000), fade);
+        assert_eq!(fades.alpha(0, at(2000), fade), 0.0);
+        fades.update(2, at(2100), Duration::ZERO);
+        assert_eq!(fades.alpha(1, at(2100), Duration::ZERO), 1.0);
+    }
+
+    #[test]
+    fn input_never_grows_past_its_limit() {
+        let bindings = KeyBindings::default();
+        let mut state = TerminalState {
+            max_input_len: 16,
+            ..default()
+        };
+        for _ in 0..1000 {
+            edit_key(KeyCode::KeyA, None, false, &bindings, &mut state);
+        }
+        assert_eq!(state.input.chars().count(), 16);
+        assert_eq!(
+            edit_key(KeyCode::KeyA, None, false, &bindings, &mut state),
+            Edit::Refused
+        );
+        // Freeing room lets typing and pasting pick up again, up to the cap
+        edit_key(
+            bindings.key(Action::Backspace),
+            None,
+            false,
+            &bindings,
+            &mut state,
+        );
+        edit_key(
+            bindings.key(Action::Backspace),
+            None,
+            false,
+            &bindings,
+            &mut state,
+        );
+        assert!(!paste(&"x\ny".repeat(500), &mut state));
+        assert_eq!(state.input, "aaaaaaaaaaaaaax ");
+        state.set_input("z".repeat(100));
+        assert_eq!(state.cursor_pos, 16);
+    }
+
+    #[test]
+    fn the_cursor_takes_the_colour_of_the_char_under_it() {
+        let runs = vec![
+            ("$ ".to_string(), Color::WHITE),
+            ("ls".to_string(), Color::BLACK),
+        ];
+        assert_eq!(
+            split_at_cursor(runs.clone(), 2, Color::WHITE),
+            (
+                vec![
+                    ("$ ".to_string(), Color::WHITE),
+                    ("l".to_string(), Color::BLACK),
+                    ("s".to_string(), Color::BLACK),
+                ],
+                1
+            )
+        );
+        let (sections, cursor) = split_at_cursor(runs, 4, Color::WHITE);
+        assert_eq!((sections.len(), cursor), (3, 2));
+        assert_eq!(sections[cursor], (String::new(), Color::WHITE));
+    }
+
+    #[test]
+    fn long_prompts_wrap_around_the_cursor() {
+        let wrapped = |before: &str, after: &str| {
+            let mut prompt = [
+                TextSection::from(before),
+                TextSection::from("_"),
+                TextSection::from(after),
+            ];
+            wrap_prompt(&mut prompt, 1, 4);
+            let lines = extra_prompt_lines(&prompt);
+            (prompt.map(|section| section.value), lines)
+        };
+        assert_eq!(wrapped("ab", ""), (["ab".into(), "_".into(), "".into()], 0));
+        assert_eq!(
+            wrapped("abcdef", "g"),
+            (["abcd\nef".into(), "_".into(), "g".into()], 1)
+        );
+        // A break right after the cursor starts the text after it
+        assert_eq!(
+            wrapped("abc", "defghi"),
+            (["abc".into(), "_".into(), "\ndefg\nhi".into()], 2)
+        );
+    }
+
+    #[test]
+    fn typing_follows_the_keyboard_layout() {
+        let bindings = KeyBindings::default();
+        let mut state = TerminalState::default();
+        // AZERTY: the key in QWERTY's Q spot types an a, and 2 types an é
+        for (key, typed) in [(KeyCode::KeyQ, "a"), (KeyCode::Digit2, "é")] {
+            let typed = Key::Character(typed.into());
+            assert_eq!(
+                edit_key(key, typed_text(&typed), false, &bindings, &mut state),
+                Edit::Done
+            );
+        }
+        // A dead key waits for the next one instead of typing a US ^
+        let dead = typed_text(&Key::Dead(Some('^')));
+        assert_eq!(
+            edit_key(KeyCode::BracketLeft, dead, false, &bindings, &mut state),
+            Edit::Ignored
+        );
+        edit_key(KeyCode::KeyE, Some("ê"), false, &bindings, &mut state);
+        // Without a logical key, the US mapping stands in
+        edit_key(KeyCode::Digit2, None, true, &bindings, &mut state);
+        assert_eq!(state.input, "aéê@");
+    }
+
+    #[test]
+    fn a_held_key_repeats_after_the_delay_at_the_rate() {
+        let mut world = World::new();
+        world.insert_resource(KeyRepeat::new(&TerminalConfig {
+            key_repeat_delay: 0.5,
+            key_repeat_rate: 8.0,
+            ..default()
+        }));
+        world.init_resource::<Time>();
+        world.init_resource::<Events<KeyboardInput>>();
+        world.init_resource::<ButtonInput<KeyCode>>();
+        world.init_resource::<KeyBindings>();
+        world.init_resource::<ButtonInput<GamepadButton>>();
+        world.init_resource::<PadBindings>();
+        world.init_resource::<CommandRegistry>();
+        world.init_resource::<TerminalLayout>();
+        world.init_resource::<AudioSettings>();
+        world.init_resource::<TerminalState>();
+        world.init_resource::<ScrollbackState>();
+        world.init_resource::<PendingOutput>();
+        world.insert_resource(State::new(BootState::Ready));
+        let press = |world: &mut World, key_code: KeyCode, logical_key: Key| {
+            world.resource_mut::<ButtonInput<KeyCode>>().press(key_code);
+            world.send_event(KeyboardInput {
+                key_code,
+                logical_key,
+                state: ButtonState::Pressed,
+                window: Entity::PLACEHOLDER,
+            });
+        };
+        let after = |world: &mut World, millis: u64| {
+            world
+                .resource_mut::<Time>()
+                .advance_by(Duration::from_millis(millis));
+            world.run_system_once(handle_input);
+            // The press was a frame ago now
+            world.resource_mut::<Events<KeyboardInput>>().clear();
+            world.resource_mut::<ButtonInput<KeyCode>>().clear();
+            world.resource::<TerminalState>().input.clone()
+        };
+        press(&mut world, KeyCode::KeyX, Key::Character("x".into()));
+        assert_eq!(after(&mut world, 0), "x");
+        assert_eq!(after(&mut world, 400), "x");
+        // The delay is up, and then it's one more every eighth of a second
+        assert_eq!(after(&mut world, 100), "xx");
+        assert_eq!(after(&mut world, 100), "xx");
+        assert_eq!(after(&mut world, 25), "xxx");
+        assert_eq!(after(&mut world, 375), "xxxxxx");
+        world
+            .resource_mut::<ButtonInput<KeyCode>>()
+            .release(KeyCode::KeyX);
+        assert_eq!(after(&mut world, 500), "xxxxxx");
+        // Backspace repeats the same way
+        press(&mut world, KeyCode::Backspace, Key::Backspace);
+        assert_eq!(after(&mut world, 0), "xxxxx");
+        // All the same in one long frame
+        assert_eq!(after(&mut world, 625), "xxx");
+    }
+
+    #[test]
+    fn scrollback_keeps_only_the_newest_lines() {
+        let layout = TerminalLayout::default();
+        let mut scrollback = ScrollbackState::new(&TerminalConfig {
+            max_scrollback_lines: 200,
+            ..default()
+        });
+        for i in 0..5000 {
+            scrollback.push(&format!("> line {}", i), &layout);
+        }
+        assert_eq!(scrollback.lines.len(), 200);
+        assert_eq!(scrollback.lines.front().unwrap(), "> line 4800");
+        let visible = scrollback.visible();
+        assert_eq!(visible.lines().count(), VISIBLE_LINES);
+        assert!(visible.ends_with("> line 4999\n"));
+    }
+
+    #[test]
+    fn full_scrollback_does_not_reallocate() {
+        let layout = TerminalLayout::default();
+        let mut scrollback = ScrollbackState::new(&TerminalConfig {
+            max_scrollback_lines: 100,
+            ..default()
+        });
+        for _ in 0..100 {
+            scrollback.push("> filler", &layout);
+        }
+        let capacity = scrollback.lines.capacity();
+        for c in "\n> streamed".chars().cycle().take(20_000) {
+            scrollback.push_char(c);
+        }
+        assert_eq!(scrollback.lines.len(), 100);
+        assert_eq!(scrollback.lines.capacity(), capacity);
+    }
+
+    #[test]
+    fn only_yes_confirms_and_modifiers_wait() {
+        let key = |c: &str| Key::Character(c.into());
+        assert_eq!(confirm_answer(&key("y"), "y"), Some(true));
+        assert_eq!(confirm_answer(&key("Y"), "y"), Some(true));
+        assert_eq!(confirm_answer(&key("n"), "y"), Some(false));
+        assert_eq!(confirm_answer(&key("s"), "s"), Some(true));
+        assert_eq!(confirm_answer(&Key::Enter, "y"), Some(false));
+        assert_eq!(confirm_answer(&Key::Shift, "y"), None);
+    }
+
+    #[test]
+    fn scrolled_view_stays_within_the_cap() {
+        let layout = TerminalLayout::default();
+        let mut scrollback = ScrollbackState::new(&TerminalConfig {
+            max_scrollback_lines: 50,
+            ..default()
+        });
+        for i in 0..50 {
+            scrollback.push(&format!("> line {}", i), &layout);
+        }
+        scrollback.scroll(true);
+        scrollback.scroll(true);
+        for i in 50..1000 {
+            scrollback.push(&format!("> line {}", i), &layout);
+        }
+        assert!(scrollback.view_offset <= scrollback.max_offset());
+        assert_eq!(scrollback.visible().lines().count(), VISIBLE_LINES);
+    }
+}
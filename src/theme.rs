@@ -0,0 +1,140 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+use std::fs;
+
+use crate::a11y::A11ySettings;
+use crate::assets::asset_path;
+use crate::terminal::{TerminalBackground, TerminalConfig};
+use crate::visuals::VisualSettings;
+
+/// Built-in themes, selectable with `theme <name>`.
+pub const PRESETS: [&str; 3] = ["classic", "amber", "ice"];
+
+/// Terminal colours. Untagged output and the prompt use `foreground`;
+/// `{red}` output uses `error` and `{cyan}` uses `highlight`.
+#[derive(Resource, Clone, PartialEq, Debug)]
+pub struct Theme {
+    pub name: String,
+    pub foreground: Color,
+    pub background: Color,
+    pub error: Color,
+    pub highlight: Color,
+    /// Whether the other colour tags, like `{yellow}`, take the colours
+    /// colour-blind players can tell apart.
+    pub colorblind: bool,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme::preset("classic").unwrap()
+    }
+}
+
+/// A custom theme in `assets/themes/<name>.ron`, as sRGB triples.
+#[derive(Deserialize)]
+struct ThemeFile {
+    foreground: (f32, f32, f32),
+    background: (f32, f32, f32),
+    error: (f32, f32, f32),
+    highlight: (f32, f32, f32),
+}
+
+/// Why a theme couldn't be loaded.
+pub enum ThemeError {
+    Unknown,
+    Invalid { path: String, error: String },
+}
+
+fn srgb((r, g, b): (f32, f32, f32)) -> Color {
+    Color::srgb(r, g, b)
+}
+
+impl Theme {
+    pub fn preset(name: &str) -> Option<Self> {
+        let (foreground, background, error, highlight) = match name {
+            "classic" => (
+                Color::srgb(0.0, 1.0, 0.0),
+                Color::srgb(0.0, 0.0, 0.0),
+                Color::srgb(1.0, 0.2, 0.2),
+                Color::srgb(0.2, 0.9, 1.0),
+            ),
+            "amber" => (
+                Color::srgb(1.0, 0.7, 0.0),
+                Color::srgb(0.08, 0.04, 0.0),
+                Color::srgb(1.0, 0.3, 0.1),
+                Color::srgb(1.0, 0.95, 0.6),
+            ),
+            "ice" => (
+                Color::srgb(0.6, 0.85, 1.0),
+                Color::srgb(0.0, 0.03, 0.08),
+                Color::srgb(1.0, 0.4, 0.6),
+                Color::WHITE,
+            ),
+            _ => return None,
+        };
+        Some(Theme {
+            name: name.to_string(),
+            foreground,
+            background,
+            error,
+            highlight,
+            colorblind: false,
+        })
+    }
+
+    /// A preset, or else the custom theme file of that name.
+    pub fn load(name: &str) -> Result<Self, ThemeError> {
+        if let Some(theme) = Theme::preset(name) {
+            return Ok(theme);
+        }
+        // Keep the name from reaching outside the themes directory
+        if !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        {
+            return Err(ThemeError::Unknown);
+        }
+        let path = asset_path(&format!("themes/{}.ron", name));
+        let contents = fs::read_to_string(&path).map_err(|_| ThemeError::Unknown)?;
+        let file: ThemeFile = ron::from_str(&contents).map_err(|err| ThemeError::Invalid {
+            path: path.display().to_string(),
+            error: err.to_string(),
+        })?;
+        Ok(Theme {
+            name: name.to_string(),
+            foreground: srgb(file.foreground),
+            background: srgb(file.background),
+            error: srgb(file.error),
+            highlight: srgb(file.highlight),
+            colorblind: false,
+        })
+    }
+}
+
+/// Recolours the terminal background when the theme changes. Output is
+/// recoloured by `render_scrollback`, and the prompt by `render_prompt`.
+/// In high contrast the background is drawn solid, without its texture
+/// showing through.
+pub fn apply_theme(
+    theme: Res<Theme>,
+    visuals: Res<VisualSettings>,
+    a11y: Res<A11ySettings>,
+    config: Res<TerminalConfig>,
+    backgrounds: Query<&Handle<StandardMaterial>, With<TerminalBackground>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    if !theme.is_changed() && !visuals.is_changed() && !a11y.is_changed() && !config.is_changed() {
+        return;
+    }
+    let colors = a11y.theme(&theme, &visuals);
+    for handle in &backgrounds {
+        if let Some(material) = materials.get_mut(handle) {
+            material.base_color = colors.background.with_alpha(config.background_alpha());
+            material.alpha_mode = if a11y.high_contrast {
+                AlphaMode::Opaque
+            } else {
+                AlphaMode::Blend
+            };
+        }
+    }
+}
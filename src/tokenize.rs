@@ -0,0 +1,150 @@
+use crate::locale::{tr, Locale};
+
+/// Why a command line couldn't be split into words.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum TokenizeError {
+    /// A quote of this kind was opened and never closed.
+    UnclosedQuote(char),
+}
+
+impl TokenizeError {
+    pub fn message(&self, locale: &Locale) -> String {
+        match self {
+            TokenizeError::UnclosedQuote(quote) => {
+                tr!(locale, "command.unclosed_quote", quote = quote)
+            }
+        }
+    }
+}
+
+/// Splits `line` into words the way a shell does: at whitespace, except
+/// inside single or double quotes, which are taken off. Outside single
+/// quotes a backslash keeps the character after it as it is, so `\"` is a
+/// quote mark and `\ ` a space within a word.
+pub fn tokenize(line: &str) -> Result<Vec<String>, TokenizeError> {
+    let mut words = Vec::new();
+    // The word being read, if one has started; `""` starts an empty one
+    let mut word: Option<String> = None;
+    let mut quote = None;
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(open), c) if c == open => quote = None,
+            (Some('\''), c) => word.get_or_insert_with(String::new).push(c),
+            (_, '\\') => {
+                // A backslash at the very end has nothing to keep, so stays
+                let escaped = chars.next().unwrap_or('\\');
+                word.get_or_insert_with(String::new).push(escaped);
+            }
+            (None, '"' | '\'') => {
+                quote = Some(c);
+                word.get_or_insert_with(String::new);
+            }
+            (None, c) if c.is_whitespace() => words.extend(word.take()),
+            (_, c) => word.get_or_insert_with(String::new).push(c),
+        }
+    }
+    if let Some(quote) = quote {
+        return Err(TokenizeError::UnclosedQuote(quote));
+    }
+    words.extend(word);
+    Ok(words)
+}
+
+/// Splits `line` at each `separator` that `tokenize` would read as itself:
+/// outside quotes and not escaped. The parts keep their quotes, to be
+/// tokenized in turn.
+pub fn split_unquoted(line: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut start = 0;
+    let mut quote = None;
+    let mut chars = line.char_indices();
+    while let Some((index, c)) = chars.next() {
+        match (quote, c) {
+            (Some(open), c) if c == open => quote = None,
+            (Some('\''), _) => {}
+            (_, '\\') => {
+                chars.next();
+            }
+            (None, '"' | '\'') => quote = Some(c),
+            (None, c) if c == separator => {
+                parts.push(&line[start..index]);
+                start = index + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(&line[start..]);
+    parts
+}
+
+/// The first word of `line` as `tokenize` reads it, so a quoted verb is
+/// found without its quotes.
+pub fn verb(line: &str) -> Option<String> {
+    tokenize(line).ok()?.into_iter().next()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn words(line: &str) -> Vec<String> {
+        tokenize(line).unwrap()
+    }
+
+    #[test]
+    fn quotes_keep_spaces_and_come_off() {
+        assert_eq!(
+            words(r#"cat "/etc/secret keys.txt""#),
+            ["cat", "/etc/secret keys.txt"]
+        );
+        assert_eq!(words("  echo 'a  b'c  d "), ["echo", "a  bc", "d"]);
+        assert_eq!(
+            words(r#"echo "it's" 'say "hi"'"#),
+            ["echo", "it's", "say \"hi\""]
+        );
+        assert!(words("   ").is_empty());
+    }
+
+    #[test]
+    fn empty_quotes_are_an_empty_argument() {
+        assert_eq!(words(r#"grep "" x"#), ["grep", "", "x"]);
+        assert_eq!(words("cat ''"), ["cat", ""]);
+    }
+
+    #[test]
+    fn backslashes_escape_outside_single_quotes() {
+        assert_eq!(words(r#"echo \"hi\" a\ b"#), ["echo", "\"hi\"", "a b"]);
+        assert_eq!(words(r#"echo "say \"hi\"""#), ["echo", "say \"hi\""]);
+        assert_eq!(
+            words(r"echo 'C:\temp' \\ end\"),
+            ["echo", r"C:\temp", r"\", r"end\"]
+        );
+    }
+
+    #[test]
+    fn separators_split_only_outside_quotes() {
+        assert_eq!(
+            split_unquoted(r#"echo "a|b" 'c|d' e\|f | grep a"#, '|'),
+            [r#"echo "a|b" 'c|d' e\|f "#, " grep a"]
+        );
+        assert_eq!(split_unquoted("echo 'a &'", '&'), ["echo 'a &'"]);
+        assert_eq!(split_unquoted("nmap x &", '&'), ["nmap x ", ""]);
+        assert_eq!(verb(r#""nmap" host"#).as_deref(), Some("nmap"));
+        assert_eq!(verb("  "), None);
+    }
+
+    #[test]
+    fn unclosed_quotes_are_an_error() {
+        assert_eq!(
+            tokenize(r#"cat "secret keys.txt"#),
+            Err(TokenizeError::UnclosedQuote('"'))
+        );
+        assert_eq!(
+            tokenize(r#"echo 'it\'s'"#),
+            Err(TokenizeError::UnclosedQuote('\''))
+        );
+        let message = TokenizeError::UnclosedQuote('"').message(&Locale::default());
+        assert_eq!(message, "parse error: unmatched \"");
+    }
+}
@@ -0,0 +1,179 @@
+use bevy::prelude::*;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::commands::CommandRegistry;
+use crate::locale::tr;
+use crate::markup;
+use crate::output::OutputLine;
+
+/// Set to any value to turn transcripts off, like `--no-transcript`.
+pub const DISABLE_ENV: &str = "NEON_CITY_NO_TRANSCRIPT";
+const FLUSH_SECONDS: f32 = 5.0;
+
+/// One command of this session as it was run, for `replay`.
+#[derive(Clone, PartialEq, Debug)]
+pub struct TranscriptEntry {
+    /// How far into the run the command was submitted.
+    pub at: Duration,
+    /// The time of day the response was stamped with.
+    pub stamp: String,
+    pub command: String,
+    /// The response as terminal markup, colours and all.
+    pub response: String,
+}
+
+/// Appends every submitted command and its response to a per-session log.
+#[derive(Resource, Default)]
+pub struct TranscriptLogger {
+    /// `None` when transcripts are disabled or the file couldn't be opened.
+    /// The lock keeps each entry's lines together.
+    writer: Option<Mutex<BufWriter<File>>>,
+    path: Option<PathBuf>,
+    /// Everything logged so far, kept even with the file disabled.
+    entries: Mutex<Vec<TranscriptEntry>>,
+}
+
+impl TranscriptLogger {
+    /// Opens `transcripts/session-<UTC time>.log` in the platform data dir.
+    pub fn open() -> Self {
+        match Self::create() {
+            Ok((path, file)) => TranscriptLogger {
+                writer: Some(Mutex::new(BufWriter::new(file))),
+                path: Some(path),
+                ..default()
+            },
+            Err(err) => {
+                warn!("Transcript disabled, could not create log file: {}", err);
+                TranscriptLogger::default()
+            }
+        }
+    }
+
+    fn create() -> io::Result<(PathBuf, File)> {
+        let dir = dirs::data_dir()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no data directory"))?
+            .join("neon-city")
+            .join("transcripts");
+        fs::create_dir_all(&dir)?;
+        let elapsed = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        let path = dir.join(format!("session-{}.log", utc_stamp(elapsed)));
+        let file = File::options().create(true).append(true).open(&path)?;
+        Ok((path, file))
+    }
+
+    pub fn path(&self) -> Option<&Path> {
+        self.path.as_deref()
+    }
+
+    /// Writes one command and its response, with colour markup removed,
+    /// and keeps them as they were shown for `replay`. `at` is how far into
+    /// the run the command was submitted.
+    pub fn log(&self, at: Duration, stamp: &str, prompt: &str, command: &str, response: &str) {
+        self.entries
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .push(TranscriptEntry {
+                at,
+                stamp: stamp.to_string(),
+                command: command.to_string(),
+                response: response.to_string(),
+            });
+        let Some(writer) = &self.writer else {
+            return;
+        };
+        let mut entry = format!("[{}] {}{}\n", stamp, prompt, command);
+        for line in response.lines() {
+            entry += &markup::strip(line);
+            entry.push('\n');
+        }
+        let mut writer = writer
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        if let Err(err) = writer.write_all(entry.as_bytes()) {
+            warn!("Failed to write transcript: {}", err);
+        }
+    }
+
+    /// Every command logged so far, oldest first.
+    pub fn entries(&self) -> Vec<TranscriptEntry> {
+        self.entries
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .clone()
+    }
+
+    pub fn flush(&self) {
+        if let Some(writer) = &self.writer {
+            let mut writer = writer
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner());
+            if let Err(err) = writer.flush() {
+                warn!("Failed to flush transcript: {}", err);
+            }
+        }
+    }
+}
+
+/// Adds the `transcript` command, which reports where this session is logged.
+pub fn register_command(registry: &mut CommandRegistry, path: Option<&Path>) {
+    let path = path.map(|path| path.display().to_string());
+    registry.register(
+        "transcript",
+        "",
+        "Show where this session is logged",
+        move |_, game| match &path {
+            Some(path) => vec![OutputLine::Info(tr!(
+                game.locale,
+                "transcript.path",
+                path = path
+            ))],
+            None => vec![OutputLine::info(game.locale.get("transcript.disabled"))],
+        },
+    );
+}
+
+/// Flushes every few seconds. `save_on_exit` flushes once more when the
+/// app is closing.
+pub fn flush_transcript(
+    time: Res<Time>,
+    logger: Res<TranscriptLogger>,
+    mut timer: Local<Option<Timer>>,
+) {
+    let timer =
+        timer.get_or_insert_with(|| Timer::from_seconds(FLUSH_SECONDS, TimerMode::Repeating));
+    if timer.tick(time.delta()).just_finished() {
+        logger.flush();
+    }
+}
+
+/// `YYYYMMDD-HHMMSS` in UTC for a time since the Unix epoch.
+fn utc_stamp(since_epoch: Duration) -> String {
+    let secs = since_epoch.as_secs();
+    let (days, time) = (secs / 86_400, secs % 86_400);
+    // Civil-from-days, after Howard Hinnant's date algorithms
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let day_of_era = z.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    format!(
+        "{:04}{:02}{:02}-{:02}{:02}{:02}",
+        year,
+        month,
+        day,
+        time / 3600,
+        time / 60 % 60,
+        time % 60
+    )
+}
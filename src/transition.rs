@@ -0,0 +1,197 @@
+use bevy::prelude::*;
+use rand::seq::SliceRandom;
+use rand::Rng;
+
+use crate::commands::GameLogicState;
+use crate::events::HostConnected;
+use crate::layout::TerminalLayout;
+use crate::locale::tr;
+use crate::output::OutputLine;
+use crate::terminal::{PendingOutput, ResponseDelay, ScrollbackState, TerminalConfig};
+
+/// Lines a transition prints on the way in: a proxy for each but the
+/// last, and then the handshake with the host itself.
+const HOPS: usize = 3;
+/// Widest the line of static gets, so it stays a burst on a wide window.
+const MAX_STATIC_COLUMNS: usize = 64;
+/// What the static is made of. No braces, which would read as markup.
+const STATIC_CHARS: &[u8] = b"#$%&*+-/<=>?@\\^_|~01";
+
+/// How long a login's transition takes and how hard it glitches the
+/// screen while it plays.
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub struct TransitionSettings {
+    /// Seconds it plays for; 0 turns it off.
+    pub seconds: f32,
+    /// How much static and tearing there is, from 0 to 1.
+    pub intensity: f32,
+}
+
+impl Default for TransitionSettings {
+    fn default() -> Self {
+        TransitionSettings {
+            seconds: 1.2,
+            intensity: 0.6,
+        }
+    }
+}
+
+/// The static and routing lines played between `ssh` reaching a host
+/// and its greeting showing, while the response is held back.
+#[derive(Resource, Default)]
+pub struct ConnectTransition {
+    /// The host being logged in to, while there is a transition.
+    host: Option<String>,
+    timer: Timer,
+    intensity: f32,
+    /// Whether it has begun, which waits for the host's own latency.
+    playing: bool,
+    /// Routing lines printed so far.
+    hops_shown: usize,
+}
+
+impl ConnectTransition {
+    fn new(host: &str, settings: TransitionSettings) -> Self {
+        ConnectTransition {
+            host: Some(host.to_string()),
+            timer: Timer::from_seconds(settings.seconds, TimerMode::Once),
+            intensity: settings.intensity.clamp(0.0, 1.0),
+            playing: false,
+            hops_shown: 0,
+        }
+    }
+
+    /// How hard the screen glitches right now, dying away as the
+    /// transition settles, or 0 when none is playing.
+    pub fn burst(&self) -> f32 {
+        if !self.playing {
+            return 0.0;
+        }
+        self.intensity * (1.0 - self.timer.fraction())
+    }
+
+    /// Routing lines due by now that haven't been printed yet.
+    fn due_hops(&mut self) -> std::ops::Range<usize> {
+        let due = ((self.timer.fraction() * HOPS as f32) as usize + 1).min(HOPS);
+        let hops = self.hops_shown..due.max(self.hops_shown);
+        self.hops_shown = hops.end;
+        hops
+    }
+}
+
+/// A line of static `columns` wide, with `density` of it noise.
+fn static_line(columns: usize, density: f32, rng: &mut impl Rng) -> String {
+    (0..columns.min(MAX_STATIC_COLUMNS))
+        .map(|_| {
+            if rng.gen::<f32>() < density {
+                *STATIC_CHARS.choose(rng).unwrap_or(&b'#') as char
+            } else {
+                ' '
+            }
+        })
+        .collect()
+}
+
+/// Plays a transition when `ssh` logs in: once the host's latency has
+/// passed, the screen tears and fills with static while the connection
+/// is routed through a couple of proxies, and then the host's greeting
+/// shows. Commands typed meanwhile wait their turn as they would for any
+/// held-back response, and Enter skips straight to the greeting.
+pub fn play_connect_transition(
+    time: Res<Time>,
+    layout: Res<TerminalLayout>,
+    config: Res<TerminalConfig>,
+    game: Res<GameLogicState>,
+    mut connected: EventReader<HostConnected>,
+    mut transition: ResMut<ConnectTransition>,
+    mut delay: ResMut<ResponseDelay>,
+    mut pending: ResMut<PendingOutput>,
+    mut scrollback: ResMut<ScrollbackState>,
+) {
+    // Only a skip asked for while it plays counts
+    let requested = std::mem::take(&mut delay.bypass_change_detection().skip_requested);
+    let settings = config.connect_transition;
+    // It plays over the greeting being held back, which a job's isn't
+    if let Some(ev) = connected.read().last() {
+        if settings.seconds > 0.0 && delay.is_waiting() {
+            *transition = ConnectTransition::new(&ev.host, settings);
+        }
+    }
+    let Some(host) = transition.host.clone() else {
+        return;
+    };
+    // Enter on an empty line skips it; with a command, queues that instead
+    let skipped = transition.playing && requested;
+    // The response has gone, by an interrupt or by showing at last
+    if !delay.is_waiting() || skipped {
+        if delay.is_waiting() {
+            let duration = delay.timer.duration();
+            delay.timer.set_elapsed(duration);
+        }
+        if transition.playing && game.download.is_none() {
+            scrollback.set_live(None);
+        }
+        *transition = ConnectTransition::default();
+        return;
+    }
+    // The host's own latency passes first, and the two end together
+    if delay.timer.remaining() > transition.timer.remaining() {
+        return;
+    }
+    transition.playing = true;
+    transition.timer.tick(time.delta());
+    // Cosmetic, so it stays off the seeded game RNG
+    let mut rng = rand::thread_rng();
+    let stamp = game.clock.time_of_day();
+    for hop in transition.due_hops() {
+        let line = if hop + 1 < HOPS {
+            let address: Vec<String> = (0..4).map(|_| rng.gen_range(1..=254).to_string()).collect();
+            tr!(game.locale, "connect.routing", address = address.join("."))
+        } else {
+            tr!(game.locale, "connect.handshake", host = host)
+        };
+        pending.push(
+            &format!(
+                "[{}] {}",
+                stamp,
+                OutputLine::Info(line).render(&game.locale)
+            ),
+            &layout,
+        );
+    }
+    // The download's bar keeps the live line if one is running
+    if game.download.is_none() {
+        let noise = static_line(layout.columns, transition.burst(), &mut rng);
+        scrollback.set_live(Some(noise));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn routing_lines_come_out_as_the_transition_plays() {
+        let settings = TransitionSettings {
+            seconds: 3.0,
+            intensity: 2.0,
+        };
+        let mut transition = ConnectTransition::new("neotechlabs.com", settings);
+        assert_eq!(transition.burst(), 0.0);
+        transition.playing = true;
+        assert_eq!(transition.burst(), 1.0);
+        assert_eq!(transition.due_hops(), 0..1);
+        assert_eq!(transition.due_hops(), 1..1);
+        transition.timer.tick(Duration::from_secs(2));
+        assert_eq!(transition.due_hops(), 1..3);
+        transition.timer.tick(Duration::from_secs(1));
+        assert_eq!(transition.due_hops(), 3..3);
+        assert_eq!(transition.burst(), 0.0);
+        let mut rng = rand::thread_rng();
+        assert_eq!(static_line(10, 0.0, &mut rng), " ".repeat(10));
+        let full = static_line(usize::MAX, 1.0, &mut rng);
+        assert_eq!(full.chars().count(), MAX_STATIC_COLUMNS);
+        assert!(full.bytes().all(|c| STATIC_CHARS.contains(&c)));
+    }
+}
@@ -0,0 +1,129 @@
+use bevy::core_pipeline::bloom::BloomSettings;
+use bevy::prelude::*;
+use serde::Deserialize;
+use std::fs;
+use std::time::Duration;
+
+use crate::assets::asset_path;
+use crate::theme::Theme;
+
+/// How much the terminal glows. Read from `assets/visuals.ron` at startup,
+/// so the look can be tuned without recompiling.
+#[derive(Resource, Deserialize)]
+#[serde(default)]
+pub struct VisualSettings {
+    pub bloom: bool,
+    /// Strength of the bloom pass, as in `BloomSettings::intensity`.
+    pub bloom_intensity: f32,
+    /// Factor text colours are over-brightened by so they bloom.
+    pub text_glow: f32,
+    /// Seconds a new output line takes to fade in; 0 shows it at once.
+    pub line_fade_seconds: f32,
+    /// Draws the text straight into the scene rather than onto the
+    /// terminal screen's texture, which saves a render pass.
+    pub flat_text: bool,
+}
+
+impl Default for VisualSettings {
+    fn default() -> Self {
+        VisualSettings {
+            bloom: true,
+            bloom_intensity: 0.3,
+            text_glow: 2.5,
+            line_fade_seconds: 0.3,
+            flat_text: false,
+        }
+    }
+}
+
+impl VisualSettings {
+    /// Defaults overridden by whatever `assets/visuals.ron` specifies.
+    pub fn load() -> Self {
+        let path = asset_path("visuals.ron");
+        let Ok(contents) = fs::read_to_string(&path) else {
+            return VisualSettings::default();
+        };
+        ron::from_str(&contents).unwrap_or_else(|err| {
+            warn!("Ignoring invalid {}: {}", path.display(), err);
+            VisualSettings::default()
+        })
+    }
+
+    /// How long a new output line takes to fade in.
+    pub fn line_fade(&self) -> Duration {
+        Duration::from_secs_f32(self.line_fade_seconds.max(0.0))
+    }
+
+    /// `theme` with its text colours brightened past 1.0 when bloom is on.
+    pub fn glowing(&self, theme: &Theme) -> Theme {
+        if !self.bloom {
+            return theme.clone();
+        }
+        let boost = |color: Color| {
+            let linear = color.to_linear();
+            Color::LinearRgba(LinearRgba {
+                red: linear.red * self.text_glow,
+                green: linear.green * self.text_glow,
+                blue: linear.blue * self.text_glow,
+                alpha: linear.alpha,
+            })
+        };
+        Theme {
+            foreground: boost(theme.foreground),
+            error: boost(theme.error),
+            highlight: boost(theme.highlight),
+            ..theme.clone()
+        }
+    }
+}
+
+/// Adds or removes the camera's bloom pass to match the settings.
+pub fn sync_visual_settings(
+    mut commands: Commands,
+    settings: Res<VisualSettings>,
+    cameras: Query<Entity, With<Camera3d>>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+    for camera in &cameras {
+        if settings.bloom {
+            commands.entity(camera).insert(BloomSettings {
+                intensity: settings.bloom_intensity,
+                ..BloomSettings::NATURAL
+            });
+        } else {
+            commands.entity(camera).remove::<BloomSettings>();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::ecs::system::RunSystemOnce;
+
+    #[test]
+    fn the_camera_blooms_only_while_bloom_is_on() {
+        let mut world = World::new();
+        world.insert_resource(VisualSettings {
+            bloom_intensity: 0.5,
+            ..default()
+        });
+        let camera = world.spawn(Camera3d::default()).id();
+        world.run_system_once(sync_visual_settings);
+        let bloom = world
+            .get::<BloomSettings>(camera)
+            .expect("bloom should be on");
+        assert_eq!(bloom.intensity, 0.5);
+        // Text goes past white so there is something to bloom
+        let theme = Theme::default();
+        let glowing = world.resource::<VisualSettings>().glowing(&theme);
+        assert!(glowing.foreground.to_linear().green > 1.0);
+        world.resource_mut::<VisualSettings>().bloom = false;
+        world.run_system_once(sync_visual_settings);
+        assert!(world.get::<BloomSettings>(camera).is_none());
+        let plain = world.resource::<VisualSettings>().glowing(&theme);
+        assert_eq!(plain.foreground, theme.foreground);
+    }
+}
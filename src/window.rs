@@ -0,0 +1,154 @@
+use bevy::prelude::*;
+use bevy::window::{PrimaryWindow, WindowCloseRequested};
+use bevy::winit::WinitWindows;
+use winit::window::Icon;
+
+use crate::assets::asset_path;
+use crate::commands::GameLogicState;
+use crate::keybindings::{Action, KeyBindings};
+use crate::network::HOME_HOST;
+use crate::settings::Settings;
+use crate::terminal::TerminalConfig;
+
+/// The window title while the runner is at home.
+pub const DEFAULT_TITLE: &str = "Data Heist at NeoTech Labs";
+
+/// The window title for the runner standing on `host`.
+fn title_for(game: &GameLogicState) -> String {
+    if game.current_host == HOME_HOST {
+        return DEFAULT_TITLE.to_string();
+    }
+    format!("neon-city — {}@{}", game.handle, game.short_host())
+}
+
+/// Keeps the window title naming the host the runner is connected to.
+pub fn update_window_title(
+    game: Res<GameLogicState>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    if !game.is_changed() {
+        return;
+    }
+    let Ok(mut window) = windows.get_single_mut() else {
+        return;
+    };
+    let title = title_for(&game);
+    // Only touch the window when the title actually changes
+    if window.title != title {
+        window.title = title;
+    }
+}
+
+/// Steps the window on to the next mode with F11, windowed to borderless
+/// to exclusive fullscreen and back, and saves it for next time.
+pub fn toggle_fullscreen(
+    keys: Res<ButtonInput<KeyCode>>,
+    bindings: Res<KeyBindings>,
+    mut config: ResMut<TerminalConfig>,
+) {
+    if !bindings.just_pressed(&keys, Action::ToggleFullscreen) {
+        return;
+    }
+    let mode = config.display_mode.next();
+    config.display_mode = mode;
+    if let Err(err) = Settings::update(config.settings_path.as_deref(), |settings| {
+        settings.display_mode = mode
+    }) {
+        warn!("Failed to save settings: {}", err);
+    }
+}
+
+/// Puts the window in the mode the terminal config asks for. Winit reports
+/// the new size as a resize, so `on_resize` lays the terminal out again.
+pub fn apply_display_mode(
+    config: Res<TerminalConfig>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    if !config.is_changed() {
+        return;
+    }
+    let Ok(mut window) = windows.get_single_mut() else {
+        return;
+    };
+    let mode = config.display_mode.window_mode();
+    if window.mode != mode {
+        window.mode = mode;
+    }
+}
+
+/// Exits when the window's close button is clicked, the way `exit` does,
+/// rather than letting the window go before the session is saved.
+pub fn exit_on_close(
+    mut closes: EventReader<WindowCloseRequested>,
+    mut exit: EventWriter<AppExit>,
+) {
+    if closes.read().count() > 0 {
+        exit.send(AppExit::Success);
+    }
+}
+
+fn load_icon() -> Result<Icon, String> {
+    let path = asset_path("sprites/icon.png");
+    let image = image::open(&path)
+        .map_err(|err| format!("{}: {}", path.display(), err))?
+        .into_rgba8();
+    let (width, height) = image.dimensions();
+    Icon::from_rgba(image.into_raw(), width, height).map_err(|err| err.to_string())
+}
+
+/// Gives the window the icon in `assets/sprites/icon.png`. Winit creates
+/// the window after startup, so this keeps trying until it exists.
+pub fn set_window_icon(
+    winit_windows: NonSend<WinitWindows>,
+    primary: Query<Entity, With<PrimaryWindow>>,
+    mut done: Local<bool>,
+) {
+    if *done {
+        return;
+    }
+    let Some(window) = primary
+        .get_single()
+        .ok()
+        .and_then(|entity| winit_windows.get_window(entity))
+    else {
+        return;
+    };
+    *done = true;
+    match load_icon() {
+        Ok(icon) => window.set_window_icon(Some(icon)),
+        Err(err) => warn!("Could not load the window icon: {}", err),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::layout::TerminalLayout;
+
+    #[test]
+    fn widescreen_windows_fit_the_whole_terminal() {
+        let fov = PerspectiveProjection::default().fov;
+        for (window, aspect) in [
+            (Vec2::new(1920.0, 1080.0), 16.0 / 9.0),
+            (Vec2::new(2560.0, 1080.0), 64.0 / 27.0),
+        ] {
+            let layout = TerminalLayout::new(window, 1.0, 0.7);
+            assert!(layout.columns > TerminalLayout::default().columns);
+            // Half the view across, at the quad's depth
+            let camera = layout.camera_transform();
+            let distance = camera.translation.z - layout.background_transform().translation.z;
+            let half_height = distance * (fov / 2.0).tan();
+            assert!(layout.size.y / 2.0 < half_height);
+            assert!(layout.size.x / 2.0 < half_height * aspect);
+        }
+    }
+
+    #[test]
+    fn title_names_the_connected_host() {
+        let mut game = GameLogicState::default();
+        assert_eq!(title_for(&game), DEFAULT_TITLE);
+        game.current_host = "neotechlabs.com".to_string();
+        assert_eq!(title_for(&game), "neon-city — runner@neotechlabs");
+    }
+}
@@ -1 +1,204 @@
-// 3D environment (hideout, objects)
\ No newline at end of file
+use bevy::prelude::*;
+use serde::Deserialize;
+use std::fs;
+
+use crate::assets::asset_path;
+use crate::loading::{AssetLoadState, Environment};
+
+/// Scene used when nothing else is configured, and when the one that is
+/// fails to load.
+pub const DEFAULT_SCENE: &str = "models/hideout.glb";
+
+/// Which model the room around the terminal is loaded from, relative to
+/// `assets`. Read from `--scene <path>`, or else `assets/world.ron`, so
+/// levels can be swapped without recompiling.
+#[derive(Resource, Deserialize, Clone, PartialEq, Debug)]
+#[serde(default)]
+pub struct WorldConfig {
+    pub scene_path: String,
+}
+
+impl Default for WorldConfig {
+    fn default() -> Self {
+        WorldConfig {
+            scene_path: DEFAULT_SCENE.to_string(),
+        }
+    }
+}
+
+impl WorldConfig {
+    /// The scene named on the command line, or else in the config file.
+    pub fn load() -> Self {
+        let args: Vec<String> = std::env::args().collect();
+        if let Some(path) = args
+            .iter()
+            .position(|arg| arg == "--scene")
+            .and_then(|index| args.get(index + 1))
+        {
+            return WorldConfig {
+                scene_path: path.clone(),
+            };
+        }
+        let path = asset_path("world.ron");
+        let Ok(contents) = fs::read_to_string(&path) else {
+            return WorldConfig::default();
+        };
+        ron::from_str(&contents).unwrap_or_else(|err| {
+            warn!("Ignoring invalid {}: {}", path.display(), err);
+            WorldConfig::default()
+        })
+    }
+}
+
+/// Starts loading the configured scene, hidden until it is ready.
+pub fn setup_world(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    config: Res<WorldConfig>,
+) {
+    info!("Loading {}...", config.scene_path);
+    let scene_handle = asset_server.load(config.scene_path.clone());
+    commands.spawn((
+        SceneBundle {
+            scene: scene_handle.clone(),
+            transform: Transform::from_xyz(0.0, 0.0, 0.0),
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        Environment,
+    ));
+    commands.insert_resource(AssetLoadState {
+        scene: scene_handle,
+    });
+}
+
+/// Lights the room, whichever scene it is.
+pub fn setup_light(mut commands: Commands) {
+    commands.spawn(PointLightBundle {
+        transform: Transform::from_xyz(0.0, 5.0, 0.0),
+        point_light: PointLight {
+            intensity: 1500.0,
+            shadows_enabled: true,
+            ..default()
+        },
+        ..default()
+    });
+}
+
+/// Name of the glTF node spun as a ceiling fan, if the scene has one.
+const FAN_NODE: &str = "CeilingFan";
+/// Name of the glTF node whose glow flickers like a failing neon tube.
+const NEON_NODE: &str = "NeonSign";
+/// How fast the fan turns, in radians per second.
+const FAN_SPEED: f32 = 1.2;
+/// How long the neon holds one brightness before picking another.
+const FLICKER_STEP_SECONDS: f32 = 0.06;
+/// Share of steps in which the neon sputters instead of burning steadily.
+const FLICKER_CHANCE: f32 = 0.08;
+
+/// Marks a node that turns steadily about its own vertical axis.
+#[derive(Component)]
+pub struct Fan;
+
+/// Marks a node whose meshes flicker.
+#[derive(Component)]
+pub struct NeonSign;
+
+/// A mesh of a neon sign, with how brightly it glows when fully lit.
+#[derive(Component)]
+pub struct Flicker {
+    base: LinearRgba,
+}
+
+/// Tags the scene's named nodes as they spawn, so they can be animated.
+/// A scene without them simply stays still.
+pub fn tag_scene_nodes(mut commands: Commands, nodes: Query<(Entity, &Name), Added<Name>>) {
+    for (entity, name) in &nodes {
+        match name.as_str() {
+            FAN_NODE => {
+                commands.entity(entity).insert(Fan);
+            }
+            NEON_NODE => {
+                commands.entity(entity).insert(NeonSign);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Gives every mesh of a newly tagged neon sign its own copy of its
+/// material, so flickering it leaves the rest of the scene alone.
+pub fn wire_neon_signs(
+    mut commands: Commands,
+    signs: Query<Entity, Added<NeonSign>>,
+    children: Query<&Children>,
+    mut meshes: Query<&mut Handle<StandardMaterial>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    for sign in &signs {
+        for entity in std::iter::once(sign).chain(children.iter_descendants(sign)) {
+            let Ok(mut handle) = meshes.get_mut(entity) else {
+                continue;
+            };
+            let Some(material) = materials.get(&*handle).cloned() else {
+                continue;
+            };
+            let base = material.emissive;
+            *handle = materials.add(material);
+            commands.entity(entity).insert(Flicker { base });
+        }
+    }
+}
+
+pub fn spin_fans(time: Res<Time>, mut fans: Query<&mut Transform, With<Fan>>) {
+    for mut transform in &mut fans {
+        transform.rotate_local_y(FAN_SPEED * time.delta_seconds());
+    }
+}
+
+/// Brightness of a neon tube `seconds` in, from 0 to 1: lit almost all the
+/// time, with the odd sputter. The same moment always looks the same.
+fn neon_brightness(seconds: f32) -> f32 {
+    let step = (seconds / FLICKER_STEP_SECONDS) as u32;
+    // A cheap integer hash, so each step gets a steady pseudo-random roll
+    let mut hash = step.wrapping_mul(0x9E37_79B9);
+    hash ^= hash >> 15;
+    hash = hash.wrapping_mul(0x85EB_CA6B);
+    hash ^= hash >> 13;
+    let roll = hash as f32 / u32::MAX as f32;
+    if roll < FLICKER_CHANCE {
+        // Spread the sputters from nearly dark to half lit
+        0.05 + 0.45 * roll / FLICKER_CHANCE
+    } else {
+        0.9 + 0.1 * roll
+    }
+}
+
+pub fn flicker_neon(
+    time: Res<Time>,
+    tubes: Query<(&Handle<StandardMaterial>, &Flicker)>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    let brightness = neon_brightness(time.elapsed_seconds());
+    for (handle, flicker) in &tubes {
+        if let Some(material) = materials.get_mut(handle) {
+            material.emissive = flicker.base * brightness;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn neon_is_mostly_lit_with_the_odd_sputter() {
+        let samples: Vec<f32> = (0..2000)
+            .map(|i| neon_brightness(i as f32 * FLICKER_STEP_SECONDS))
+            .collect();
+        assert!(samples.iter().all(|b| (0.0..=1.0).contains(b)));
+        let dim = samples.iter().filter(|b| **b < 0.9).count();
+        assert!(dim > 0 && dim < samples.len() / 5, "{} dim", dim);
+        assert_eq!(neon_brightness(12.3), neon_brightness(12.3));
+    }
+}